@@ -1,6 +1,27 @@
 #version 330 core
 out vec4 color;
 
+in vec3 frag_pos;
+in float shade;
+
+uniform vec3 view_pos;
+// 0 at night, 1 during the day
+uniform float daylight;
+
+// distance fog, see opengl::fog
+uniform vec3 fog_color;
+uniform float fog_start;
+uniform float fog_end;
+uniform float fog_density;
+
+float fog_factor(float distance) {
+    float linear = clamp((distance - fog_start) / (fog_end - fog_start), 0.0, 1.0);
+    float exponential = 1.0 - exp(-pow(distance * fog_density, 2.0));
+    return max(linear, exponential);
+}
+
 void main() {
-    color = vec4(1.0);
-}
\ No newline at end of file
+    float fog = fog_factor(length(view_pos - frag_pos));
+    vec3 cloud_color = mix(vec3(0.15, 0.17, 0.25), vec3(1.0), daylight) * shade;
+    color = vec4(mix(cloud_color, fog_color, fog), 1.0);
+}
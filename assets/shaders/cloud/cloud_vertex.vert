@@ -1,13 +1,16 @@
 #version 330 core
 layout (location = 0) in vec3 a_pos;
+layout (location = 1) in float a_shade;
 
-out vec3 pos;
+out vec3 frag_pos;
+out float shade;
 
 uniform mat4 projection;
 uniform mat4 view;
 uniform mat4 model;
 
 void main() {
-    gl_Position = projection * view * model * vec4(a_pos, 1.0);
-    pos = a_pos;
-}
\ No newline at end of file
+    frag_pos = vec3(model * vec4(a_pos, 1.0));
+    gl_Position = projection * view * vec4(frag_pos, 1.0);
+    shade = a_shade;
+}
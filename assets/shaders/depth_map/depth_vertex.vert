@@ -1,8 +1,17 @@
 #version 330 core
+// same layout as the voxal vertex shader so chunk
+// meshes can be drawn into the shadow map as is
 layout (location = 0) in vec3 a_pos;
+layout (location = 7) in float a_faces_to_draw;
+layout (location = 8) in float a_height;
 
-uniform mat4 light_space_matrix;
+out VS_OUT {
+    int faces_to_draw;
+    float height;
+} vs_out;
 
 void main() {
-    gl_Position = light_space_matrix * vec4(a_pos, 1.0);
-}  
\ No newline at end of file
+    gl_Position = vec4(a_pos, 1.0);
+    vs_out.faces_to_draw = int(a_faces_to_draw);
+    vs_out.height = a_height;
+}
@@ -0,0 +1,42 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TileCoord;
+flat in int Tile;
+
+uniform sampler2D texture_map;
+uniform vec3 view_pos;
+
+// set from the time of day, see core::world_time
+uniform vec3 light_dir;
+uniform vec3 light_color;
+uniform float ambient_strength;
+
+// distance fog, see opengl::fog
+uniform vec3 fog_color;
+uniform float fog_start;
+uniform float fog_end;
+uniform float fog_density;
+
+float fog_factor(float distance) {
+    float linear = clamp((distance - fog_start) / (fog_end - fog_start), 0.0, 1.0);
+    float exponential = 1.0 - exp(-pow(distance * fog_density, 2.0));
+    return max(linear, exponential);
+}
+
+void main() {
+    vec2 uv = (fract(TileCoord) + vec2(float(Tile % 6), float(Tile / 6))) / 6.0;
+    // base level only, fract() jumps at block edges which
+    // would throw off the choice of mip level there
+    vec4 color = textureLod(texture_map, uv, 0.0);
+
+    // same lighting as full detail blocks, without shadows
+    vec3 norm = normalize(Normal);
+    float diff = max(dot(normalize(-light_dir), norm), 0.0);
+    vec3 lighting = (vec3(ambient_strength) + 0.8 * diff * light_color) * color.rgb;
+
+    float fog = fog_factor(length(view_pos - FragPos));
+    FragColor = vec4(mix(lighting, fog_color, fog), 1.0);
+}
@@ -0,0 +1,24 @@
+#version 330 core
+layout (location = 0) in vec2 aCorner;
+layout (location = 1) in float aSize;
+layout (location = 2) in float aTop;
+layout (location = 3) in float aBottom;
+layout (location = 4) in float aTopTile;
+layout (location = 5) in float aSideTile;
+
+out VS_OUT {
+    float size;
+    float top;
+    float bottom;
+    int top_tile;
+    int side_tile;
+} vs_out;
+
+void main() {
+    gl_Position = vec4(aCorner.x, 0.0, aCorner.y, 1.0);
+    vs_out.size = aSize;
+    vs_out.top = aTop;
+    vs_out.bottom = aBottom;
+    vs_out.top_tile = int(aTopTile);
+    vs_out.side_tile = int(aSideTile);
+}
@@ -2,10 +2,24 @@
 out vec4 color;
 
 in vec2 tex_coords;
+in vec3 frag_pos;
 
 uniform sampler2D player_texture;
+uniform vec3 view_pos;
+
+// distance fog, see opengl::fog
+uniform vec3 fog_color;
+uniform float fog_start;
+uniform float fog_end;
+uniform float fog_density;
+
+float fog_factor(float distance) {
+    float linear = clamp((distance - fog_start) / (fog_end - fog_start), 0.0, 1.0);
+    float exponential = 1.0 - exp(-pow(distance * fog_density, 2.0));
+    return max(linear, exponential);
+}
 
 void main() {
-    color = vec4(texture(player_texture, tex_coords).rgb, 1.0);
-    // color = vec4(1.0, 0.0, 0.0, 1.0);
-}
\ No newline at end of file
+    float fog = fog_factor(length(view_pos - frag_pos));
+    color = vec4(mix(texture(player_texture, tex_coords).rgb, fog_color, fog), 1.0);
+}
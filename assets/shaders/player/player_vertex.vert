@@ -7,8 +7,10 @@ uniform mat4 view;
 uniform mat4 projection;
 
 out vec2 tex_coords;
+out vec3 frag_pos;
 
 void main() {
-   tex_coords = a_texcoords;
-    gl_Position = projection * view * model * vec4(position, 1.0);
-}
\ No newline at end of file
+    tex_coords = a_texcoords;
+    frag_pos = vec3(model * vec4(position, 1.0));
+    gl_Position = projection * view * vec4(frag_pos, 1.0);
+}
@@ -0,0 +1,57 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D screenTexture;
+uniform vec2 inverse_screen_size;
+
+#define FXAA_SPAN_MAX 8.0
+#define FXAA_REDUCE_MUL (1.0 / 8.0)
+#define FXAA_REDUCE_MIN (1.0 / 128.0)
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+    vec3 rgb_nw = texture(screenTexture, TexCoords + vec2(-1.0, -1.0) * inverse_screen_size).rgb;
+    vec3 rgb_ne = texture(screenTexture, TexCoords + vec2(1.0, -1.0) * inverse_screen_size).rgb;
+    vec3 rgb_sw = texture(screenTexture, TexCoords + vec2(-1.0, 1.0) * inverse_screen_size).rgb;
+    vec3 rgb_se = texture(screenTexture, TexCoords + vec2(1.0, 1.0) * inverse_screen_size).rgb;
+    vec3 rgb_m = texture(screenTexture, TexCoords).rgb;
+
+    float luma_nw = luma(rgb_nw);
+    float luma_ne = luma(rgb_ne);
+    float luma_sw = luma(rgb_sw);
+    float luma_se = luma(rgb_se);
+    float luma_m = luma(rgb_m);
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // blur along the edge, perpendicular to the luma gradient
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    float rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2(-FXAA_SPAN_MAX), vec2(FXAA_SPAN_MAX)) * inverse_screen_size;
+
+    vec3 rgb_a = 0.5 * (
+        texture(screenTexture, TexCoords + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(screenTexture, TexCoords + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+        texture(screenTexture, TexCoords + dir * -0.5).rgb +
+        texture(screenTexture, TexCoords + dir * 0.5).rgb
+    );
+
+    // the wider sample crossed another edge, fall back to the narrow one
+    float luma_b = luma(rgb_b);
+    if (luma_b < luma_min || luma_b > luma_max) {
+        FragColor = vec4(rgb_a, 1.0);
+    } else {
+        FragColor = vec4(rgb_b, 1.0);
+    }
+}
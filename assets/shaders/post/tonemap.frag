@@ -0,0 +1,21 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D screenTexture;
+uniform float exposure;
+uniform float gamma;
+
+// fitted ACES filmic curve
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+void main() {
+    // textures and colours are authored in sRGB, so
+    // tonemap in linear space and convert back
+    vec3 color = pow(max(texture(screenTexture, TexCoords).rgb, 0.0), vec3(gamma));
+    color = aces(color * exposure);
+    FragColor = vec4(pow(color, vec3(1.0 / gamma)), 1.0);
+}
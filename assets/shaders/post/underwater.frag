@@ -0,0 +1,21 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D screenTexture;
+uniform float time;
+uniform vec3 tint;
+
+void main() {
+    // wobble the image in slow waves as if seen through moving water
+    vec2 offset = vec2(
+        sin(TexCoords.y * 24.0 + time * 2.0),
+        cos(TexCoords.x * 18.0 + time * 1.6)
+    ) * 0.004;
+    vec2 uv = clamp(TexCoords + offset, 0.0, 1.0);
+
+    vec3 color = texture(screenTexture, uv).rgb;
+    color = mix(color, tint, 0.55);
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D screenTexture;
+uniform float strength;
+
+void main() {
+    vec3 color = texture(screenTexture, TexCoords).rgb;
+
+    // darken towards the corners
+    float dist = length(TexCoords - vec2(0.5)) * 1.414;
+    color *= 1.0 - strength * smoothstep(0.4, 1.0, dist);
+    FragColor = vec4(color, 1.0);
+}
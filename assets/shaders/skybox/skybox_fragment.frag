@@ -3,6 +3,55 @@ out vec4 color;
 
 in vec3 pos;
 
+// set from the time of day, see core::world_time
+uniform vec3 horizon_color;
+uniform vec3 zenith_color;
+uniform vec3 sun_direction;
+uniform float daylight;
+uniform mat3 star_rotation;
+
+uniform bool use_cubemap;
+uniform samplerCube cubemap;
+
+float hash(vec3 p) {
+    return fract(sin(dot(p, vec3(12.9898, 78.233, 37.719))) * 43758.5453);
+}
+
+// sparse points of light on a grid over the sky
+float stars(vec3 dir) {
+    vec3 cell = floor(dir * 150.0);
+    float h = hash(cell);
+    if (h < 0.996) {
+        return 0.0;
+    }
+
+    // fade each star towards the edge of its cell so they're round-ish
+    vec3 local = fract(dir * 150.0) - 0.5;
+    float twinkle = 0.6 + 0.4 * hash(cell + 1.0);
+    return twinkle * (1.0 - smoothstep(0.1, 0.4, length(local)));
+}
+
 void main() {
-    color = vec4(pos, 1.0);
-}
\ No newline at end of file
+    vec3 dir = normalize(pos);
+
+    vec3 sky;
+    if (use_cubemap) {
+        // darken the textured sky at night
+        sky = texture(cubemap, dir).rgb * mix(0.08, 1.0, daylight);
+    } else {
+        // horizon to zenith gradient, flat below the horizon
+        float height = max(dir.y, 0.0);
+        sky = mix(horizon_color, zenith_color, smoothstep(0.0, 0.6, height));
+
+        // glow around the sun, strongest at sunrise/sunset
+        float sun_glow = pow(max(dot(dir, sun_direction), 0.0), 8.0);
+        float low_sun = 1.0 - smoothstep(0.0, 0.4, abs(sun_direction.y));
+        sky += vec3(1.0, 0.6, 0.3) * sun_glow * low_sun * 0.4;
+    }
+
+    // stars fade in at night, fading out towards the horizon
+    float star_visibility = (1.0 - daylight) * smoothstep(0.0, 0.2, dir.y);
+    sky += vec3(stars(star_rotation * dir)) * star_visibility;
+
+    color = vec4(sky, 1.0);
+}
@@ -0,0 +1,17 @@
+#version 330 core
+out vec4 color;
+
+in vec2 local_pos;
+
+uniform vec3 body_color;
+
+void main() {
+    // solid disc with a soft glow around it
+    float d = length(local_pos);
+    if (d > 1.0) {
+        discard;
+    }
+
+    float alpha = d < 0.5 ? 1.0 : 1.0 - smoothstep(0.5, 1.0, d);
+    color = vec4(body_color, alpha * alpha);
+}
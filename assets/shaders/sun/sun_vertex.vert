@@ -0,0 +1,23 @@
+#version 330 core
+layout (location = 0) in vec2 a_pos;
+
+out vec2 local_pos;
+
+uniform mat4 projection;
+uniform mat4 view;
+// direction from the camera to the body
+uniform vec3 direction;
+uniform float size;
+
+void main() {
+    // billboard facing the camera, drawn with the view's
+    // translation removed so it stays at the same spot in the sky
+    vec3 world_up = abs(direction.y) > 0.99 ? vec3(1.0, 0.0, 0.0) : vec3(0.0, 1.0, 0.0);
+    vec3 right = normalize(cross(direction, world_up));
+    vec3 up = cross(right, direction);
+    vec3 position = direction * 100.0 + (a_pos.x * right + a_pos.y * up) * size;
+
+    mat4 v = mat4(mat3(view));
+    gl_Position = projection * v * vec4(position, 1.0);
+    local_pos = a_pos;
+}
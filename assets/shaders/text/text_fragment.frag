@@ -4,10 +4,11 @@ out vec4 color;
 
 uniform sampler2D text;
 uniform vec3 textColor;
+uniform float textOpacity;
 
 void main() {
     if (texture(text, TexCoord).r < 0.1) {
         discard;
     }
-    color = vec4(textColor, 1.0);
+    color = vec4(textColor, textOpacity);
 }
\ No newline at end of file
@@ -4,60 +4,147 @@ out vec4 FragColor;
 in vec2 TexCoord;
 in vec3 Normal;
 in vec3 FragPos;
-in vec4 frag_pos_light_space;
+// 1.0 unless the corners of the face are occluded
+in float AO;
 
 uniform sampler2D texture_map;
-uniform sampler2D shadow_map;
 
 uniform vec3 light_pos;
 uniform vec3 view_pos;
 uniform float time;
 
-/*
-float shadow_calculation(vec4 frag_pos_light_space) {
-    // perform perspective divide 
+// set from the time of day, see core::world_time
+uniform vec3 light_dir;
+uniform vec3 light_color;
+uniform float ambient_strength;
+uniform vec3 sky_color;
+
+// set while drawing water
+uniform bool water_pass;
+// set while drawing blocks with see-through gaps (leaves)
+uniform bool cutout_pass;
+
+// distance fog, see opengl::fog
+uniform vec3 fog_color;
+uniform float fog_start;
+uniform float fog_end;
+uniform float fog_density;
+
+float fog_factor(float distance) {
+    float linear = clamp((distance - fog_start) / (fog_end - fog_start), 0.0, 1.0);
+    float exponential = 1.0 - exp(-pow(distance * fog_density, 2.0));
+    return max(linear, exponential);
+}
+
+// shadow map cascades, see opengl::shadow_map
+#define MAX_CASCADES 4
+uniform sampler2DArray shadow_map;
+uniform int cascade_count;
+uniform mat4 light_space_matrices[MAX_CASCADES];
+// distance from the camera each cascade covers up to
+uniform float cascade_splits[MAX_CASCADES];
+
+float shadow_calculation(vec3 norm, vec3 to_light) {
+    if (cascade_count == 0) {
+        return 0.0;
+    }
+
+    // pick the closest cascade covering this fragment
+    float distance = length(view_pos - FragPos);
+    int cascade = -1;
+    for (int i = 0; i < cascade_count; i++) {
+        if (distance < cascade_splits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+
+    if (cascade == -1) {
+        return 0.0;
+    }
+
+    // perform perspective divide
+    vec4 frag_pos_light_space = light_space_matrices[cascade] * vec4(FragPos, 1.0);
     vec3 proj_coords = frag_pos_light_space.xyz / frag_pos_light_space.w;
 
     // transform to 0-1 uv coord range for sampling
     proj_coords = proj_coords * 0.5 + 0.5;
+    if (proj_coords.z > 1.0) {
+        return 0.0;
+    }
+
+    // bias more on surfaces at a steep angle to the light,
+    // less in nearer (higher resolution) cascades
+    float bias = max(0.004 * (1.0 - dot(norm, to_light)), 0.0008) / float(cascade_count - cascade);
 
-    float closest_depth = texture(shadow_map, proj_coords.xy).r;
-    float current_depth = proj_coords.z;
+    // percentage closer filtering, average a 3x3 area
+    // of depth comparisons to soften shadow edges
+    float shadow = 0.0;
+    vec2 texel_size = 1.0 / vec2(textureSize(shadow_map, 0).xy);
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            float closest_depth = texture(shadow_map, vec3(proj_coords.xy + vec2(x, y) * texel_size, cascade)).r;
+            shadow += proj_coords.z - bias > closest_depth ? 1.0 : 0.0;
+        }
+    }
+    shadow /= 9.0;
 
-    // if fragment z is behind depth map z than it's in shadow
-    float bias = 0.05;
-    float shadow = (current_depth + bias) > closest_depth ? 1.0 : 0.0;
+    // fade out towards the end of the last cascade
+    float last_split = cascade_splits[cascade_count - 1];
+    shadow *= 1.0 - smoothstep(last_split * 0.85, last_split, distance);
     return shadow;
 }
-*/
 
 void main() {
     vec4 color = texture(texture_map, vec2(TexCoord.x / 6.0, TexCoord.y / 6.0));
+    if (cutout_pass) {
+        if (color.a < 0.5) {
+            discard;
+        }
+        color.a = 1.0;
+    }
 
     // blinn-phong lighting
     vec3 norm = normalize(Normal); 
-    vec3 light_color = vec3(1.0, 1.0, 1.0);
+
+    // ripple the normals of water surfaces
+    if (water_pass && norm.y > 0.5) {
+        norm = normalize(norm + vec3(
+            sin(FragPos.x * 1.7 + time * 1.3) * 0.06,
+            0.0,
+            cos(FragPos.z * 1.9 + time * 1.1) * 0.06
+        ));
+    }
 
     // ambient
-    float ambient_strength = 0.5;
-    vec3 ambient = ambient_strength * light_color;
+    vec3 ambient = vec3(ambient_strength);
 
     // diffuse
-    vec3 light_dir = vec3(-0.8, -1.0, 0.0);
-    light_dir = normalize(-light_dir);
-    float diff = max(dot(light_dir, norm), 0.0);
+    vec3 to_light = normalize(-light_dir);
+    float diff = max(dot(to_light, norm), 0.0);
     vec3 diffuse = 0.8 * diff * light_color;
 
     // specular
     vec3 view_dir = normalize(view_pos - FragPos);
-    vec3 halfway_dir = normalize(light_dir + view_dir);
+    vec3 halfway_dir = normalize(to_light + view_dir);
     float spec = pow(max(dot(norm, halfway_dir), 0.0), 10);
     vec3 specular = spec * light_color;
 
     // shadow
-    // float shadow = shadow_calculation(frag_pos_light_space);
+    float shadow = shadow_calculation(norm, to_light);
 
     // calculate result by summing light sources
-    vec3 lighting = (ambient + diffuse /*+ specular*/) * color.rgb;
-    FragColor = vec4(lighting, color.a);
+    vec3 lighting = (ambient + (1.0 - shadow) * diffuse /*+ specular*/) * AO * color.rgb;
+
+    // water reflects the sky more (and lets less through)
+    // the shallower the angle it's seen at
+    if (water_pass) {
+        float fresnel = pow(1.0 - max(dot(view_dir, norm), 0.0), 3.0);
+        lighting = mix(lighting, sky_color, fresnel * 0.7) + (1.0 - shadow) * specular * 0.4;
+        color.a = mix(color.a * 0.8, 1.0, fresnel);
+    }
+
+    // fade into the fog with distance
+    float fog = fog_factor(length(view_pos - FragPos));
+    FragColor = vec4(mix(lighting, fog_color, fog), color.a);
 }
\ No newline at end of file
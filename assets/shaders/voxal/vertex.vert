@@ -7,10 +7,15 @@ layout (location = 4) in float aBottomUV;
 layout (location = 5) in float aLeftUV;
 layout (location = 6) in float aTopUV;
 layout (location = 7) in float aFacesToDraw;
+layout (location = 8) in float aHeight;
+layout (location = 9) in float aOcclusion0;
+layout (location = 10) in float aOcclusion1;
 
 out VS_OUT {
     float[6] blockUVIndices;
     int facesToDraw;
+    float height;
+    ivec2 occlusion;
 } vs_out;
 
 void main() {
@@ -18,5 +23,7 @@ void main() {
     // TexCoord = aTexCoord;
     vs_out.blockUVIndices = float[6](aFrontUV, aRightUV, aBackUV, aBottomUV, aLeftUV, aTopUV);
     vs_out.facesToDraw = int(aFacesToDraw);
+    vs_out.height = aHeight;
+    vs_out.occlusion = ivec2(int(aOcclusion0), int(aOcclusion1));
     // position = aPos;
 }    
\ No newline at end of file
@@ -0,0 +1,93 @@
+use crate::traits::game_chunk::GameChunk;
+
+use super::{block_type::BlockType, chunk::{CHUNK_HEIGHT, CHUNK_SIZE}};
+
+// cube corners and the corners of each face in the order
+// voxal/geometry.geom emits them (cubeVerts and cubeIndices)
+const CUBE_CORNERS: [(i32, i32, i32); 8] = [
+    (-1, -1, -1), (-1, 1, -1), (1, -1, -1), (1, 1, -1),
+    (-1, -1, 1), (-1, 1, 1), (1, -1, 1), (1, 1, 1)
+];
+
+const FACE_CORNERS: [[usize; 4]; 6] = [
+    [0, 1, 2, 3], // front
+    [7, 6, 3, 2], // right
+    [7, 5, 6, 4], // back
+    [4, 0, 6, 2], // bottom
+    [1, 0, 5, 4], // left
+    [3, 1, 7, 5]  // top
+];
+
+const FACE_NORMALS: [(i32, i32, i32); 6] = [
+    (0, 0, -1), // front
+    (1, 0, 0),  // right
+    (0, 0, 1),  // back
+    (0, -1, 0), // bottom
+    (-1, 0, 0), // left
+    (0, 1, 0)   // top
+];
+
+// how occluded (0-3) each corner of each drawn face of a block is by
+// the blocks around it, packed 2 bits per corner and 8 bits per face
+// with front, right and back in the first value and bottom, left and
+// top in the second (both fit exactly in an f32 vertex attribute)
+pub fn block_occlusion(chunk: &dyn GameChunk, x: i32, y: i32, z: i32, faces: u8, right_chunk: &dyn GameChunk, left_chunk: &dyn GameChunk, front_chunk: &dyn GameChunk, back_chunk: &dyn GameChunk) -> (f32, f32) {
+    let occludes = |dx: i32, dy: i32, dz: i32| -> bool {
+        let (x, y, z) = (x + dx, y + dy, z + dz);
+        if y < 0 || y >= CHUNK_HEIGHT as i32 {
+            return false;
+        }
+
+        // corners diagonally across a chunk edge
+        // aren't looked up and never occlude
+        let size = CHUNK_SIZE as i32;
+        let (chunk, x, z) = match (x, z) {
+            (x, z) if x >= 0 && x < size && z >= 0 && z < size => (chunk, x, z),
+            (x, z) if x == size && z >= 0 && z < size => (right_chunk, 0, z),
+            (x, z) if x == -1 && z >= 0 && z < size => (left_chunk, size - 1, z),
+            (x, z) if z == size && x >= 0 && x < size => (front_chunk, x, 0),
+            (x, z) if z == -1 && x >= 0 && x < size => (back_chunk, x, size - 1),
+            _ => return false
+        };
+
+        let block = chunk.get_blocks().get(x as usize, y as usize, z as usize);
+        block != BlockType::Air && block != BlockType::Water
+    };
+
+    let mut packed = [0u32; 2];
+    for face in 0..6 {
+        if (faces >> (7 - face)) & 1 != 1 {
+            continue;
+        }
+
+        let (nx, ny, nz) = FACE_NORMALS[face];
+        for (i, corner) in FACE_CORNERS[face].iter().enumerate() {
+            let (cx, cy, cz) = CUBE_CORNERS[*corner];
+
+            // the two blocks beside the corner in front of the face, along
+            // each axis of the face, and the block diagonal between them
+            let (side1, side2) = if nx != 0 {
+                ((0, cy, 0), (0, 0, cz))
+            } else if ny != 0 {
+                ((cx, 0, 0), (0, 0, cz))
+            } else {
+                ((cx, 0, 0), (0, cy, 0))
+            };
+
+            let side1_occludes = occludes(nx + side1.0, ny + side1.1, nz + side1.2);
+            let side2_occludes = occludes(nx + side2.0, ny + side2.1, nz + side2.2);
+            let corner_occludes = occludes(nx + side1.0 + side2.0, ny + side1.1 + side2.1, nz + side1.2 + side2.2);
+
+            // both sides fully block light reaching the corner
+            let occlusion = if side1_occludes && side2_occludes {
+                3
+            } else {
+                side1_occludes as u32 + side2_occludes as u32 + corner_occludes as u32
+            };
+
+            packed[face / 3] |= occlusion << ((face % 3) * 8 + i * 2);
+        }
+    }
+
+    (packed[0] as f32, packed[1] as f32)
+}
@@ -41,15 +41,15 @@ impl BlockMap {
 
     pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
         if x >= CHUNK_SIZE {
-            panic!(format!("Segfault, attempted to read map at invalid x: {}", x))
+            panic!("Segfault, attempted to read map at invalid x: {}", x)
         }
 
         if y >= CHUNK_HEIGHT {
-            panic!(format!("Segfault, attempted to read map at invalid y: {}", y))
+            panic!("Segfault, attempted to read map at invalid y: {}", y)
         }
 
         if z >= CHUNK_SIZE {
-            panic!(format!("Segfault, attempted to read map at invalid z: {}", z))
+            panic!("Segfault, attempted to read map at invalid z: {}", z)
         }
 
         self.map[x][z][y] = block;
@@ -0,0 +1,135 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::prelude::*;
+
+use crate::traits::game_world::GameWorld;
+
+use super::{block_type::{BlockType, block_ticks, is_transparent}, fluid::{WATER_TICK_DELAY, update_fluid}, world::World};
+
+// blocks picked at random per 16 block tall
+// section of each nearby chunk every tick
+pub const RANDOM_TICKS_PER_SECTION: usize = 3;
+pub const SECTION_HEIGHT: usize = 16;
+
+// chunks further than this from the player
+// don't receive random ticks
+pub const RANDOM_TICK_DISTANCE: i32 = 8;
+
+// leaves further than this from any log decay
+const LEAF_DECAY_DISTANCE: i32 = 4;
+
+const NEIGHBOURS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+// called after a block at the given spot is changed, schedules
+// ticks for the block itself and any neighbours that react to it,
+// each block's delay and handler come from block_type::block_ticks
+pub fn on_block_changed(world: &mut World, x: i32, y: i32, z: i32, previous: BlockType, block: BlockType) {
+    let positions = std::iter::once((0, 0, 0)).chain(NEIGHBOURS.iter().cloned());
+    for (dx, dy, dz) in positions {
+        let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+        let delay = match world.get_block(nx, ny, nz) {
+            Some(neighbour) => (block_ticks(neighbour).delay)(previous, block),
+            None => None
+        };
+
+        if let Some(delay) = delay {
+            world.schedule_tick(nx, ny, nz, delay);
+        }
+    }
+}
+
+// runs a tick scheduled through World::schedule_tick
+pub fn scheduled_tick(world: &mut World, x: i32, y: i32, z: i32) {
+    if let Some(block) = world.get_block(x, y, z) {
+        if let Some(tick) = block_ticks(block).scheduled {
+            tick(world, x, y, z, block);
+        }
+    }
+}
+
+// runs for blocks picked at random, see World::update_random_ticks
+pub fn random_tick(world: &mut World, x: i32, y: i32, z: i32, block: BlockType) {
+    if let Some(tick) = block_ticks(block).random {
+        tick(world, x, y, z, block);
+    }
+}
+
+// spreads water and schedules wherever it changed
+pub fn water_tick(world: &mut World, x: i32, y: i32, z: i32, _: BlockType) {
+    for (fx, fy, fz) in update_fluid(world, x, y, z) {
+        world.schedule_tick(fx, fy, fz, WATER_TICK_DELAY);
+    }
+}
+
+// snow settles on anything exposed to the sky above the snow line
+pub fn settle_snow(world: &mut World, x: i32, y: i32, z: i32, _: BlockType) {
+    if world.snows_at(x, y + 1, z) && world.get_block(x, y + 1, z) == Some(BlockType::Air) && world.highest_in_column(x, z) == Some(y as usize) {
+        world.set_block_data(x, y + 1, z, BlockType::Snow);
+    }
+}
+
+// grass dies under opaque blocks other than snow, otherwise
+// spreads to nearby dirt that is uncovered
+pub fn grass_tick(world: &mut World, x: i32, y: i32, z: i32, block: BlockType) {
+    if !is_covered(world, x, y, z) {
+        let mut rng = rand::thread_rng();
+        let nx = x + rng.gen_range(-1..=1);
+        let ny = y + rng.gen_range(-3..=1);
+        let nz = z + rng.gen_range(-1..=1);
+        if world.get_block(nx, ny, nz) == Some(BlockType::Dirt) && !is_covered(world, nx, ny, nz) {
+            world.set_block_data(nx, ny, nz, BlockType::Grass);
+        }
+    } else {
+        world.set_block_data(x, y, z, BlockType::Dirt);
+    }
+
+    settle_snow(world, x, y, z, block);
+}
+
+fn is_covered(world: &World, x: i32, y: i32, z: i32) -> bool {
+    match world.get_block(x, y + 1, z) {
+        Some(BlockType::Snow) => false,
+        Some(block) => !is_transparent(block),
+        None => false
+    }
+}
+
+// unsupported sand etc. turns into a falling entity
+// that falls through air and water until it lands
+pub fn falling_block_tick(world: &mut World, x: i32, y: i32, z: i32, block: BlockType) {
+    match world.get_block(x, y - 1, z) {
+        Some(BlockType::Air) | Some(BlockType::Water) => {
+            world.set_block_data(x, y, z, BlockType::Air);
+            world.spawn_falling_block(x, y, z, block);
+        },
+        _ => ()
+    }
+}
+
+// leaves decay if no log can be reached through
+// other leaves within LEAF_DECAY_DISTANCE
+pub fn leaves_tick(world: &mut World, x: i32, y: i32, z: i32, _: BlockType) {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert((x, y, z));
+    queue.push_back((x, y, z, 0));
+    while let Some((cx, cy, cz, distance)) = queue.pop_front() {
+        for (dx, dy, dz) in NEIGHBOURS.iter() {
+            let position = (cx + dx, cy + dy, cz + dz);
+            if visited.contains(&position) {
+                continue;
+            }
+            visited.insert(position);
+
+            match world.get_block(position.0, position.1, position.2) {
+                Some(BlockType::Log) => return,
+                // unloaded chunks might hold the log, don't decay
+                None => return,
+                Some(BlockType::Leaves) if distance + 1 < LEAF_DECAY_DISTANCE => queue.push_back((position.0, position.1, position.2, distance + 1)),
+                _ => ()
+            }
+        }
+    }
+
+    world.set_block_data(x, y, z, BlockType::Air);
+}
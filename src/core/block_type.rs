@@ -1,6 +1,9 @@
-use super::face::Face;
+use super::{block_tick::{falling_block_tick, grass_tick, leaves_tick, settle_snow, water_tick}, face::Face, fluid::WATER_TICK_DELAY, world::World};
 use serde::{Serialize, Deserialize};
 
+const FALLING_BLOCK_TICK_DELAY: u64 = 2;
+const LEAF_DECAY_DELAY: u64 = 20;
+
 #[derive(Debug, PartialEq, Clone, Copy, Deserialize, Serialize)]
 pub enum BlockType {
     Grass = 0,
@@ -43,6 +46,37 @@ pub fn index_to_block(index: usize) -> Option<BlockType> {
     }
 }
 
+// lower snake case name used in commands, e.g. stone_brick
+pub fn block_name(block: BlockType) -> String {
+    let mut name = String::new();
+    for (i, ch) in format!("{:?}", block).chars().enumerate() {
+        if ch.is_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(ch.to_ascii_lowercase());
+    }
+    name
+}
+
+pub fn block_from_name(name: &str) -> Option<BlockType> {
+    block_names().into_iter()
+        .find(|(_, block_name)| block_name == name)
+        .map(|(block, _)| block)
+}
+
+// every block along with its name, in index order
+pub fn block_names() -> Vec<(BlockType, String)> {
+    (0..).map_while(index_to_block)
+        .map(|block| (block, block_name(block)))
+        .collect()
+}
+
+// atlas tiles that animate through a strip of frames in the tiles
+// after them: (first tile, frame count, seconds per frame)
+pub const ANIMATED_TILES: [(u32, u32, f32); 1] = [
+    (24, 4, 0.25) // water
+];
+
 pub fn block_to_uv(block_face_type: BlockType, face: Face) -> f32 {
     match block_face_type {
         BlockType::Dirt => 0.0,
@@ -60,7 +94,7 @@ pub fn block_to_uv(block_face_type: BlockType, face: Face) -> f32 {
         BlockType::Orange => 8.0,
         BlockType::DarkOrange => 9.0,
         BlockType::Black => 10.0,
-        BlockType::Water => 11.0,
+        BlockType::Water => 24.0,
         BlockType::Sand => 12.0,
         BlockType::Snow => match face {
             Face::Top => 13.0,
@@ -73,4 +107,51 @@ pub fn block_to_uv(block_face_type: BlockType, face: Face) -> f32 {
         BlockType::Brick => 18.0,
         BlockType::Air => panic!("Attempted to get block uv for BlockType::Air")
     }
-}
\ No newline at end of file
+}
+
+// blocks that light passes through and that don't
+// count as covering whatever is beneath them
+pub fn is_transparent(block: BlockType) -> bool {
+    matches!(block, BlockType::Air | BlockType::Water | BlockType::Leaves)
+}
+
+pub type TickHandler = fn(&mut World, i32, i32, i32, BlockType);
+
+// how a block hooks into the tick scheduler, see core::block_tick
+#[derive(Clone, Copy)]
+pub struct BlockTicks {
+    // fixed ticks until the block's scheduled tick once the spot it's on or
+    // next to changed from one block to another, None if that doesn't concern it
+    pub delay: fn(BlockType, BlockType) -> Option<u64>,
+    pub scheduled: Option<TickHandler>,
+    // runs when the block is picked at random
+    pub random: Option<TickHandler>
+}
+
+const NO_TICKS: BlockTicks = BlockTicks { delay: |_, _| None, scheduled: None, random: None };
+
+pub fn block_ticks(block: BlockType) -> BlockTicks {
+    match block {
+        BlockType::Water => BlockTicks { delay: |_, _| Some(WATER_TICK_DELAY), scheduled: Some(water_tick), random: None },
+        // turns into a falling block entity when there's nothing beneath it
+        BlockType::Sand => BlockTicks { delay: |_, _| Some(FALLING_BLOCK_TICK_DELAY), scheduled: Some(falling_block_tick), random: None },
+        // leaves only check for decay once part of a tree is
+        // removed so leaves placed by the player stay put
+        BlockType::Leaves => BlockTicks {
+            delay: |previous, block| match (previous, block) {
+                (BlockType::Log, BlockType::Air) | (BlockType::Leaves, BlockType::Air) => Some(LEAF_DECAY_DELAY),
+                _ => None
+            },
+            scheduled: Some(leaves_tick),
+            random: None
+        },
+        BlockType::Grass => BlockTicks { random: Some(grass_tick), ..NO_TICKS },
+        BlockType::Dirt | BlockType::Stone => BlockTicks { random: Some(settle_snow), ..NO_TICKS },
+        _ => NO_TICKS
+    }
+}
+
+// blocks that do something when randomly ticked
+pub fn receives_random_ticks(block: BlockType) -> bool {
+    block_ticks(block).random.is_some()
+}
@@ -0,0 +1,246 @@
+use std::{collections::VecDeque, fs::{self, File, OpenOptions}, io::{self, BufWriter, Write}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use cgmath::Vector3;
+use chrono::{Local, TimeZone};
+
+// lines kept, both in memory and in a server's saved log
+const MAX_LINES: usize = 200;
+
+// own messages and commands that can be recalled with the arrow keys
+const MAX_SENT: usize = 50;
+
+// how long lines stay up with chat closed, then how long they take to fade
+const SHOW_FOR: Duration = Duration::from_secs(10);
+const FADE_FOR: Duration = Duration::from_secs(1);
+
+// logs of each server, named after its address
+const CHAT_LOGS_DIR: &str = "game_data/chat_logs";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChatKind {
+    // said by a player, including yourself
+    Player,
+    // joins, leaves and anything else from the game or server
+    System,
+    // failed commands, rejected edits etc.
+    Error
+}
+
+impl ChatKind {
+    pub fn color(&self) -> Vector3<f32> {
+        match self {
+            ChatKind::Player => Vector3::new(1.0, 1.0, 1.0),
+            ChatKind::System => Vector3::new(1.0, 1.0, 0.45),
+            ChatKind::Error => Vector3::new(1.0, 0.45, 0.45)
+        }
+    }
+
+    // letter the kind is saved as
+    fn code(&self) -> char {
+        match self {
+            ChatKind::Player => 'p',
+            ChatKind::System => 's',
+            ChatKind::Error => 'e'
+        }
+    }
+
+    fn from_code(code: &str) -> Option<ChatKind> {
+        match code {
+            "p" => Some(ChatKind::Player),
+            "s" => Some(ChatKind::System),
+            "e" => Some(ChatKind::Error),
+            _ => None
+        }
+    }
+}
+
+pub struct ChatLine {
+    pub kind: ChatKind,
+    pub text: String,
+    // unix seconds
+    pub time: i64,
+    // when it arrived, None for lines loaded from a saved log
+    // which are only shown while chat is open
+    received: Option<Instant>
+}
+
+impl ChatLine {
+    // local time it arrived, e.g. 14:05
+    pub fn timestamp(&self) -> String {
+        match Local.timestamp_opt(self.time, 0).single() {
+            Some(time) => time.format("%H:%M").to_string(),
+            None => String::new()
+        }
+    }
+
+    // how visible the line is with chat closed, 1 while new,
+    // fading to 0 once it has been up for a while
+    pub fn opacity(&self) -> f32 {
+        let age = match self.received {
+            Some(received) => received.elapsed(),
+            None => return 0.0
+        };
+
+        match age.checked_sub(SHOW_FOR) {
+            None => 1.0,
+            Some(fading) => (1.0 - fading.as_secs_f32() / FADE_FOR.as_secs_f32()).max(0.0)
+        }
+    }
+
+    // "time kind text"
+    fn to_saved(&self) -> String {
+        format!("{} {} {}\n", self.time, self.kind.code(), self.text)
+    }
+
+    fn from_saved(line: &str) -> Option<ChatLine> {
+        let mut words = line.splitn(3, ' ');
+        let time = words.next()?.parse().ok()?;
+        let kind = ChatKind::from_code(words.next()?)?;
+        let text = String::from(words.next().unwrap_or(""));
+        Some(ChatLine { kind, text, time, received: None })
+    }
+}
+
+// chat of a world or server, along with what the player has
+// sent so it can be recalled into the chat input
+pub struct ChatLog {
+    lines: VecDeque<ChatLine>,
+    sent: Vec<String>,
+    // index into sent being recalled, and what was typed before recalling
+    recalling: Option<usize>,
+    draft: String,
+    // lines scrolled up from the newest while chat is open
+    scroll: usize,
+    // file lines are appended to, None for local worlds, buffered
+    // so chat arriving doesn't wait on the disk while the log is locked
+    file: Option<BufWriter<File>>
+}
+
+impl ChatLog {
+    pub fn new() -> ChatLog {
+        ChatLog { lines: VecDeque::new(), sent: Vec::new(), recalling: None, draft: String::new(), scroll: 0, file: None }
+    }
+
+    // log of a server, loaded from and saved to game_data/chat_logs
+    pub fn for_server(address: &str) -> ChatLog {
+        let path = format!("{}/{}", CHAT_LOGS_DIR, address.replace(|ch: char| !ch.is_ascii_alphanumeric() && ch != '.' && ch != '-', "_"));
+        let mut log = ChatLog::new();
+        if let Ok(contents) = fs::read_to_string(path.as_str()) {
+            log.lines = contents.lines().filter_map(ChatLine::from_saved).collect();
+            while log.lines.len() > MAX_LINES {
+                log.lines.pop_front();
+            }
+
+            // rewrite the file with only what was kept so it doesn't grow forever
+            let saved: String = log.lines.iter().map(|line| line.to_saved()).collect();
+            fs::write(path.as_str(), saved).ok();
+        }
+
+        log.file = match open_log(path.as_str()) {
+            Ok(file) => Some(BufWriter::new(file)),
+            Err(error) => {
+                println!("Failed to open chat log {}: {}", path, error);
+                None
+            }
+        };
+        log
+    }
+
+    pub fn push(&mut self, kind: ChatKind, text: &str) {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let line = ChatLine { kind, text: String::from(text), time, received: Some(Instant::now()) };
+
+        if let Some(file) = &mut self.file {
+            if let Err(error) = file.write_all(line.to_saved().as_bytes()) {
+                println!("Failed to save chat: {}", error);
+            }
+        }
+
+        self.lines.push_back(line);
+        if self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+
+        // keep the same lines in view while scrolled up
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + 1).min(self.lines.len().saturating_sub(1));
+        }
+    }
+
+    // writes out lines still buffered, when leaving the server
+    pub fn flush(&mut self) {
+        if let Some(file) = &mut self.file {
+            if let Err(error) = file.flush() {
+                println!("Failed to save chat: {}", error);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.scroll = 0;
+    }
+
+    // newest first, skipping those scrolled past
+    pub fn newest(&self) -> impl Iterator<Item = &ChatLine> {
+        self.lines.iter().rev().skip(self.scroll)
+    }
+
+    pub fn scroll(&mut self, lines: i32) {
+        let max_scroll = self.lines.len().saturating_sub(1) as i32;
+        self.scroll = (self.scroll as i32 + lines).max(0).min(max_scroll) as usize;
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    // remembers a message or command the player sent
+    pub fn sent(&mut self, text: &str) {
+        if !text.is_empty() && self.sent.last().map(|last| last.as_str()) != Some(text) {
+            self.sent.push(String::from(text));
+            if self.sent.len() > MAX_SENT {
+                self.sent.remove(0);
+            }
+        }
+        self.recalling = None;
+    }
+
+    // what was sent before the one being recalled, starting
+    // with the last one, current is what's typed right now
+    pub fn recall_previous(&mut self, current: &str) -> Option<String> {
+        let index = match self.recalling {
+            Some(0) => return None,
+            Some(index) => index - 1,
+            None if self.sent.is_empty() => return None,
+            None => {
+                self.draft = String::from(current);
+                self.sent.len() - 1
+            }
+        };
+        self.recalling = Some(index);
+        Some(self.sent[index].clone())
+    }
+
+    // what was sent after the one being recalled, or
+    // what was being typed once past the newest
+    pub fn recall_next(&mut self) -> Option<String> {
+        let index = self.recalling?;
+        if index + 1 < self.sent.len() {
+            self.recalling = Some(index + 1);
+            return Some(self.sent[index + 1].clone())
+        }
+
+        self.recalling = None;
+        Some(self.draft.clone())
+    }
+
+    pub fn stop_recalling(&mut self) {
+        self.recalling = None;
+    }
+}
+
+fn open_log(path: &str) -> io::Result<File> {
+    fs::create_dir_all(CHAT_LOGS_DIR)?;
+    OpenOptions::new().create(true).append(true).open(path)
+}
@@ -1,4 +1,4 @@
-use std::{fs, rc::Rc};
+use std::{collections::HashMap, fs, rc::Rc};
 
 use noise::OpenSimplex;
 
@@ -6,11 +6,18 @@ use rand::prelude::*;
 
 use crate::{traits::game_chunk::GameChunk, utils::{chunk_utils::{from_serialized, to_serialized}, simplex_utils::sample}};
 
-use super::{block_map::BlockMap, block_type::{BlockType, block_to_uv}, face::Face};
+use super::{ambient_occlusion::block_occlusion, block_map::BlockMap, chunk_mesh::ChunkMesh, block_type::{BlockType, block_to_uv}, face::Face, fluid::{SOURCE_LEVEL, fluid_height}};
 
 pub const CHUNK_SIZE: usize = 16;
 pub const CHUNK_HEIGHT: usize = 256;
 
+// generated water fills columns up to (not including) this y
+pub const SEA_LEVEL: usize = 9;
+
+// floats per block point in a chunk mesh: position (3), face texture
+// indices (6), faces to draw (1), height (1), corner occlusion (2)
+pub const BLOCK_VERTEX_SIZE: usize = 13;
+
 #[derive(Clone)]
 pub struct Chunk {
     pub blocks: BlockMap,
@@ -18,7 +25,11 @@ pub struct Chunk {
     x: i32,
     z: i32,
     save_path: String,
-    pub mesh: Rc<(Vec<f32>, Vec<f32>)> // cache mesh
+    // levels of non-source water, see core::fluid
+    fluid_levels: HashMap<(usize, usize, usize), u8>,
+    // world tick each pending block tick is due at, see core::block_tick
+    scheduled_ticks: HashMap<(usize, usize, usize), u64>,
+    pub mesh: Rc<ChunkMesh> // cache mesh
 }
 
 impl GameChunk for Chunk {
@@ -28,31 +39,33 @@ impl GameChunk for Chunk {
 }
 
 impl Chunk {
-    pub fn from(save_path: String, contents: String, x: i32, z: i32) -> Chunk {
+    pub fn from(save_path: String, contents: String, x: i32, z: i32, tick: u64) -> Chunk {
         let  (blocks_in_mesh, blocks) = from_serialized(&contents); 
-        Chunk { blocks, blocks_in_mesh, x: x * 16, z: z * 16, save_path, mesh: Rc::new((vec![], vec![])) }
+        let fluid_levels = load_fluid_levels(format!("{}_fluids", save_path).as_str());
+        let scheduled_ticks = load_scheduled_ticks(format!("{}_ticks", save_path).as_str(), tick);
+        Chunk { blocks, blocks_in_mesh, x: x * 16, z: z * 16, save_path, fluid_levels, scheduled_ticks, mesh: Rc::new(ChunkMesh::new(x * 16, z * 16)) }
     }
 
-    pub fn new(x_offset: i32, z_offset: i32, simplex: OpenSimplex, chunk_dir: String) -> Chunk {
+    // tick is the current world tick, pending block
+    // ticks are saved relative to it
+    pub fn new(x_offset: i32, z_offset: i32, simplex: OpenSimplex, chunk_dir: String, tick: u64) -> Chunk {
         let save_path = format!("{}/{}_{}", chunk_dir, x_offset, z_offset);
         let contents = fs::read_to_string(save_path.clone());
         if let Ok(contents) = contents {
-            return Chunk::from(save_path, contents, x_offset, z_offset)
+            return Chunk::from(save_path, contents, x_offset, z_offset, tick)
         }
 
-        let amplitude = 15.0;
         let mut blocks = BlockMap::new();
         let mut blocks_in_mesh = Vec::new();
         let x_offset = x_offset * 16;
         let z_offset = z_offset * 16;
         for x in 0..CHUNK_SIZE {
             for z in 0..CHUNK_SIZE {
-                let simplex_x = (x as i32 + x_offset) as f32;
-                let simplex_z = (z as i32 + z_offset) as f32;
-                let noise = gen_heightmap(simplex_x, simplex_z, simplex);
-                let height = ((amplitude * noise) as usize) + 1;
-                if height < 10 {
-                    for y in 0..9 {
+                let world_x = x as i32 + x_offset;
+                let world_z = z as i32 + z_offset;
+                let height = terrain_height(world_x, world_z, simplex);
+                if height <= SEA_LEVEL {
+                    for y in 0..SEA_LEVEL {
                         let block = if y < height - 1 {
                             BlockType::Sand
                         } else {
@@ -61,18 +74,10 @@ impl Chunk {
                         add_block(&mut blocks, &mut blocks_in_mesh, x, y, z, block);
                     }
                 } else {
-                    let snow_offset = (sample(simplex_x * 4.0, simplex_z * 4.0, simplex) * 20.0) as usize;
+                    let top = land_surface_block(world_x, world_z, height, simplex);
                     for y in 0..height {
                         let block = if y == height - 1 {
-                            if height > 30 + snow_offset {
-                                BlockType::Snow   
-                            } else if height > 30 - snow_offset {
-                                BlockType::Stone
-                            } else if height == 10 {
-                                BlockType::Sand
-                            } else {
-                                BlockType::Grass
-                            }
+                            top
                         } else if y > height - 3 {
                             if height > 20 {
                                 BlockType::Stone
@@ -120,16 +125,13 @@ impl Chunk {
             }
         }
 
-        let chunk = Chunk { blocks, blocks_in_mesh, x: x_offset, z: z_offset, save_path, mesh: Rc::new((vec![], vec![])) };
-        chunk.save();
+        let chunk = Chunk { blocks, blocks_in_mesh, x: x_offset, z: z_offset, save_path, fluid_levels: HashMap::new(), scheduled_ticks: HashMap::new(), mesh: Rc::new(ChunkMesh::new(x_offset, z_offset)) };
+        chunk.save(tick);
         chunk
     }
 
-    pub fn gen_mesh(&self, right_chunk: &Chunk, left_chunk: &Chunk, front_chunk: &Chunk, back_chunk: &Chunk) -> Rc<(Vec<f32>, Vec<f32>)> {
-        let mut vertices = Vec::new();
-        // water is transparent so is in separate
-        // vector to draw after opaque blocks
-        let mut water_vertices = Vec::new();
+    pub fn gen_mesh(&self, right_chunk: &Chunk, left_chunk: &Chunk, front_chunk: &Chunk, back_chunk: &Chunk) -> Rc<ChunkMesh> {
+        let mut mesh = ChunkMesh::new(self.x, self.z);
         for (x, y, z) in self.blocks_in_mesh.iter() {
             // let instant = std::time::Instant::now();
             let x = *x;
@@ -145,8 +147,7 @@ impl Chunk {
             let y = y as i32;
             let z = z as i32;
             let faces =
-                0
-                | if self.can_place_mesh_face_at_block(x, y, z - 1, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b10000000 } else { 0 }
+                if self.can_place_mesh_face_at_block(x, y, z - 1, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b10000000 } else { 0 }
                 | if self.can_place_mesh_face_at_block(x + 1, y, z, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b01000000 } else { 0 }
                 | if self.can_place_mesh_face_at_block(x, y, z + 1, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b00100000 } else { 0 }
                 | if self.can_place_mesh_face_at_block(x, y - 1, z, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b00010000 } else { 0 }
@@ -162,11 +163,7 @@ impl Chunk {
             let world_y = y as f32;
             let world_z = (z + self.z) as f32;
             
-            let vertices_to_push_to = if block == BlockType::Water {
-                &mut water_vertices
-            }else {
-                &mut vertices
-            };
+            let vertices_to_push_to = mesh.points_for(block);
             vertices_to_push_to.push(world_x);
             vertices_to_push_to.push(world_y);
             vertices_to_push_to.push(world_z);
@@ -185,34 +182,121 @@ impl Chunk {
             }
 
             vertices_to_push_to.push(faces as f32);
+
+            // water surfaces sit lower the further they've flowed
+            let height = if block == BlockType::Water && (y + 1 >= CHUNK_HEIGHT as i32 || self.blocks.get(x as usize, y as usize + 1, z as usize) != BlockType::Water) {
+                fluid_height(self.fluid_level(x as usize, y as usize, z as usize))
+            } else {
+                1.0
+            };
+            vertices_to_push_to.push(height);
+
+            // water is see-through so isn't shaded by its surroundings
+            let (occlusion0, occlusion1) = if block == BlockType::Water {
+                (0.0, 0.0)
+            } else {
+                block_occlusion(self, x, y, z, faces, right_chunk, left_chunk, front_chunk, back_chunk)
+            };
+            vertices_to_push_to.push(occlusion0);
+            vertices_to_push_to.push(occlusion1);
         }
 
-        Rc::new((vertices, water_vertices))
+        Rc::new(mesh)
     }
 
-    fn save(&self) {
+    pub fn save(&self, tick: u64) {
         fs::write(self.save_path.clone(), to_serialized(&self.blocks_in_mesh, &self.blocks))
-            .expect(format!("Failed to save chunk to {}", self.save_path.clone()).as_str());
+            .unwrap_or_else(|_| panic!("Failed to save chunk to {}", self.save_path.clone()));
+
+        let fluids_path = format!("{}_fluids", self.save_path);
+        if !self.fluid_levels.is_empty() {
+            let mut serialized = String::new();
+            for ((x, y, z), level) in self.fluid_levels.iter() {
+                serialized.push_str(format!("{} {} {} {}\n", x, y, z, level).as_str());
+            }
+            fs::write(fluids_path.clone(), serialized)
+                .unwrap_or_else(|_| panic!("Failed to save fluid levels to {}", fluids_path));
+        } else if fs::metadata(fluids_path.as_str()).is_ok() {
+            fs::remove_file(fluids_path.as_str())
+                .unwrap_or_else(|_| panic!("Failed to remove {}", fluids_path));
+        }
+
+        let ticks_path = format!("{}_ticks", self.save_path);
+        if !self.scheduled_ticks.is_empty() {
+            let mut serialized = String::new();
+            for ((x, y, z), due) in self.scheduled_ticks.iter() {
+                serialized.push_str(format!("{} {} {} {}\n", x, y, z, due.saturating_sub(tick)).as_str());
+            }
+            fs::write(ticks_path.clone(), serialized)
+                .unwrap_or_else(|_| panic!("Failed to save scheduled ticks to {}", ticks_path));
+        } else if fs::metadata(ticks_path.as_str()).is_ok() {
+            fs::remove_file(ticks_path.as_str())
+                .unwrap_or_else(|_| panic!("Failed to remove {}", ticks_path));
+        }
+    }
+
+    // schedules a tick for a block, keeping the earlier
+    // one if the block already has a tick pending
+    pub fn schedule_tick(&mut self, x: usize, y: usize, z: usize, due: u64) {
+        let pending = self.scheduled_ticks.entry((x, y, z)).or_insert(due);
+        *pending = (*pending).min(due);
+    }
+
+    pub fn has_scheduled_ticks(&self) -> bool {
+        !self.scheduled_ticks.is_empty()
+    }
+
+    // removes and returns the local positions of all ticks due by the given tick
+    pub fn take_due_ticks(&mut self, tick: u64) -> Vec<(usize, usize, usize)> {
+        let due: Vec<(usize, usize, usize)> = self.scheduled_ticks.iter()
+            .filter(|(_, due)| **due <= tick)
+            .map(|(position, _)| *position)
+            .collect();
+
+        for position in due.iter() {
+            self.scheduled_ticks.remove(position);
+        }
+        due
+    }
+
+    pub fn fluid_level(&self, x: usize, y: usize, z: usize) -> u8 {
+        match self.fluid_levels.get(&(x, y, z)) {
+            Some(level) => *level,
+            None => SOURCE_LEVEL
+        }
+    }
+
+    // spots holding water that isn't a source
+    pub fn flowing_water(&self) -> impl Iterator<Item = &(usize, usize, usize)> {
+        self.fluid_levels.keys()
+    }
+
+    pub fn set_fluid_level(&mut self, x: usize, y: usize, z: usize, level: u8) {
+        if level == SOURCE_LEVEL {
+            self.fluid_levels.remove(&(x, y, z));
+        } else {
+            self.fluid_levels.insert((x, y, z), level);
+        }
     }
 
     pub fn block_at(&self, x: usize, y: usize, z: usize) -> BlockType {
         self.blocks.get(x, y, z)
     }
 
+    // changes are only written to disk on save(),
+    // World saves chunks after each batch of edits
     pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
         self.blocks.set(x, y, z, block);
+        // any water placed directly is a source
+        self.fluid_levels.remove(&(x, y, z));
+
         if block == BlockType::Air {
-            for i in 0..self.blocks_in_mesh.len() - 1 {
-                if self.blocks_in_mesh[i] == (x, y, z) {
-                    self.blocks_in_mesh.remove(i);
-                    break;
-                }
+            if let Some(i) = self.blocks_in_mesh.iter().position(|position| *position == (x, y, z)) {
+                self.blocks_in_mesh.remove(i);
             }
-        } else {
+        } else if !self.blocks_in_mesh.contains(&(x, y, z)) {
             self.blocks_in_mesh.push((x, y, z));
         }
-        self.save();
-        //self.gen_mesh();
     }
 
     pub fn can_place_at_local_spot(&self, x: i32, y: i32, z: i32, block: BlockType) -> bool {
@@ -252,6 +336,40 @@ impl Chunk {
     }
 }
 
+fn load_fluid_levels(path: &str) -> HashMap<(usize, usize, usize), u8> {
+    let mut fluid_levels = HashMap::new();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return fluid_levels
+    };
+
+    // x y z level
+    for line in contents.lines() {
+        let values: Vec<usize> = line.split(" ").filter_map(|word| word.parse::<usize>().ok()).collect();
+        if values.len() == 4 {
+            fluid_levels.insert((values[0], values[1], values[2]), values[3] as u8);
+        }
+    }
+    fluid_levels
+}
+
+fn load_scheduled_ticks(path: &str, tick: u64) -> HashMap<(usize, usize, usize), u64> {
+    let mut scheduled_ticks = HashMap::new();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return scheduled_ticks
+    };
+
+    // x y z ticks remaining
+    for line in contents.lines() {
+        let values: Vec<u64> = line.split(" ").filter_map(|word| word.parse::<u64>().ok()).collect();
+        if values.len() == 4 {
+            scheduled_ticks.insert((values[0] as usize, values[1] as usize, values[2] as usize), tick + values[3]);
+        }
+    }
+    scheduled_ticks
+}
+
 fn add_block(blocks: &mut BlockMap, blocks_in_mesh: &mut Vec<(usize, usize, usize)>, x: usize, y: usize, z: usize, block: BlockType) {
     blocks.set(x, y, z, block);
     blocks_in_mesh.push((x, y, z));
@@ -265,4 +383,47 @@ fn gen_heightmap(x: f32, z: f32, simplex: OpenSimplex) -> f32 {
     + coeff * sample(2.0 * x, 2.0 * z, simplex)
     + 0.5 * coeff * sample(4.0 * x, 4.0 * z, simplex);
     height.powf(1.5)
-}
\ No newline at end of file
+}
+
+// blocks in the generated terrain column at the given world coords, before trees
+pub fn terrain_height(world_x: i32, world_z: i32, simplex: OpenSimplex) -> usize {
+    let amplitude = 15.0;
+    let noise = gen_heightmap(world_x as f32, world_z as f32, simplex);
+    ((amplitude * noise) as usize) + 1
+}
+
+// y and type of the topmost block generated at the given world coords,
+// columns at or below sea level are topped with water
+pub fn terrain_surface(world_x: i32, world_z: i32, simplex: OpenSimplex) -> (usize, BlockType) {
+    let height = terrain_height(world_x, world_z, simplex);
+    if height <= SEA_LEVEL {
+        return (SEA_LEVEL - 1, BlockType::Water)
+    }
+
+    (height - 1, land_surface_block(world_x, world_z, height, simplex))
+}
+
+// block on top of a column taller than sea level, snow
+// and bare stone reach lower in some places than others
+fn land_surface_block(world_x: i32, world_z: i32, height: usize, simplex: OpenSimplex) -> BlockType {
+    let snow_offset = snow_offset(world_x, world_z, simplex);
+    if height > 30 + snow_offset {
+        BlockType::Snow
+    } else if height > 30 - snow_offset {
+        BlockType::Stone
+    } else if height == SEA_LEVEL + 1 {
+        BlockType::Sand
+    } else {
+        BlockType::Grass
+    }
+}
+
+// whether a column this tall is topped with snow, also
+// where snow settles on blocks placed by the player
+pub fn above_snow_line(world_x: i32, world_z: i32, height: usize, simplex: OpenSimplex) -> bool {
+    height > 30 + snow_offset(world_x, world_z, simplex)
+}
+
+fn snow_offset(world_x: i32, world_z: i32, simplex: OpenSimplex) -> usize {
+    (sample(world_x as f32 * 4.0, world_z as f32 * 4.0, simplex) * 20.0) as usize
+}
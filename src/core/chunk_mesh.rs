@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{block_type::BlockType, chunk::CHUNK_SIZE};
+
+// a chunk's block points (see chunk::BLOCK_VERTEX_SIZE) split
+// by how they're drawn, see opengl::chunk_renderer
+#[derive(Clone)]
+pub struct ChunkMesh {
+    // world coords of the chunk's corner
+    pub x: i32,
+    pub z: i32,
+    pub opaque: Vec<f32>,
+    // blocks with see-through gaps in their texture (leaves),
+    // drawn with the gaps discarded rather than blended
+    pub cutout: Vec<f32>,
+    // blended blocks (water), drawn back to front after everything else
+    pub translucent: Vec<f32>,
+    // unique to each mesh built, so anything derived
+    // from one can tell when it has been rebuilt
+    generation: u64
+}
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+impl ChunkMesh {
+    pub fn new(x: i32, z: i32) -> ChunkMesh {
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        ChunkMesh { x, z, opaque: vec![], cutout: vec![], translucent: vec![], generation }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.opaque.len() == 0 && self.cutout.len() == 0 && self.translucent.len() == 0
+    }
+
+    // the points a block belongs in
+    pub fn points_for(&mut self, block: BlockType) -> &mut Vec<f32> {
+        match block {
+            BlockType::Water => &mut self.translucent,
+            BlockType::Leaves => &mut self.cutout,
+            _ => &mut self.opaque
+        }
+    }
+
+    pub fn contains(&self, world_x: f32, world_z: f32) -> bool {
+        let size = CHUNK_SIZE as f32;
+        world_x >= self.x as f32 - 0.5 && world_x < self.x as f32 + size - 0.5
+            && world_z >= self.z as f32 - 0.5 && world_z < self.z as f32 + size - 0.5
+    }
+
+    // squared distance along the ground from the chunk's center
+    pub fn distance_squared(&self, world_x: f32, world_z: f32) -> f32 {
+        let half = CHUNK_SIZE as f32 / 2.0 - 0.5;
+        let dx = self.x as f32 + half - world_x;
+        let dz = self.z as f32 + half - world_z;
+        dx * dx + dz * dz
+    }
+}
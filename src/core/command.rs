@@ -0,0 +1,614 @@
+use cgmath::Vector3;
+
+use crate::traits::command_context::CommandContext;
+
+use super::{block_type::{BlockType, block_from_name, block_name, block_names}, player::GameMode};
+
+// most blocks /fill sets at once
+const MAX_FILL_BLOCKS: usize = 32768;
+
+// coordinates further out than this are refused, far past anywhere
+// that can be reached and well within what an i32 can hold
+const MAX_COORDINATE: f32 = 30_000_000.0;
+
+// times of day /time set takes by name, see world_time
+const NAMED_TIMES: [(&str, u64); 5] = [
+    ("day", 1000),
+    ("noon", 6000),
+    ("sunset", 12000),
+    ("night", 13000),
+    ("midnight", 18000)
+];
+
+// who can run a command, servers give each player a level
+// while the player of a local world can run everything
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum PermissionLevel {
+    Player,
+    Operator
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ArgumentKind {
+    // a number, or ~ (optionally followed by an offset)
+    // relative to the player's position
+    Coordinate,
+    Block,
+    // ticks, or one of NAMED_TIMES
+    Time,
+    Choice(&'static [&'static str]),
+    // name of another command, for /help
+    CommandName
+}
+
+struct Argument {
+    name: &'static str,
+    kind: ArgumentKind,
+    optional: bool
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Ticks(u64),
+    Coordinate { value: f32, relative: bool },
+    Block(BlockType),
+    Word(String)
+}
+
+// parsed arguments of a command, optional ones left
+// off the end of the line are missing
+pub struct Arguments {
+    values: Vec<Value>
+}
+
+impl Arguments {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn word(&self, index: usize) -> Option<&str> {
+        match self.values.get(index) {
+            Some(Value::Word(word)) => Some(word.as_str()),
+            _ => None
+        }
+    }
+
+    pub fn ticks(&self, index: usize) -> Option<u64> {
+        match self.values.get(index) {
+            Some(Value::Ticks(ticks)) => Some(*ticks),
+            _ => None
+        }
+    }
+
+    pub fn block(&self, index: usize) -> Option<BlockType> {
+        match self.values.get(index) {
+            Some(Value::Block(block)) => Some(*block),
+            _ => None
+        }
+    }
+
+    // three coordinates starting at index, relative ones offset from origin
+    pub fn position(&self, index: usize, origin: Vector3<f32>) -> Option<Vector3<f32>> {
+        let mut position = [0.0; 3];
+        for i in 0..3 {
+            position[i] = match self.values.get(index + i) {
+                Some(Value::Coordinate { value, relative: true }) => origin[i] + value,
+                Some(Value::Coordinate { value, relative: false }) => *value,
+                _ => return None
+            };
+        }
+        Some(Vector3::new(position[0], position[1], position[2]))
+    }
+
+    // the block at a position, which even with relative
+    // coordinates has to be inside the world
+    pub fn block_position(&self, index: usize, origin: Vector3<f32>) -> Result<(i32, i32, i32), String> {
+        let position = self.position(index, origin).ok_or("Missing coordinates")?;
+        if !in_world(position.x) || !in_world(position.y) || !in_world(position.z) {
+            return Err(format!("{:.0} {:.0} {:.0} is outside the world", position.x, position.y, position.z))
+        }
+        Ok((position.x.round() as i32, position.y.round() as i32, position.z.round() as i32))
+    }
+}
+
+enum Action {
+    // answered by the dispatcher itself, so it works even where
+    // there's no context to run commands against
+    Help,
+    Run(fn(&mut dyn CommandContext, &Arguments) -> Result<String, String>)
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub permission: PermissionLevel,
+    arguments: Vec<Argument>,
+    action: Action
+}
+
+impl Command {
+    // e.g. "/tp <x> <y> <z>", optional arguments in square brackets
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+        for argument in self.arguments.iter() {
+            match argument.optional {
+                true => usage.push_str(format!(" [{}]", argument.name).as_str()),
+                false => usage.push_str(format!(" <{}>", argument.name).as_str())
+            }
+        }
+        usage
+    }
+}
+
+// parses and runs chat lines starting with a slash, shared by local
+// worlds and servers which each run commands against their own
+// CommandContext
+pub struct CommandDispatcher {
+    commands: Vec<Command>
+}
+
+impl CommandDispatcher {
+    pub fn new() -> CommandDispatcher {
+        let mut dispatcher = CommandDispatcher { commands: Vec::new() };
+        dispatcher.register_builtin_commands();
+        dispatcher
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    // runs a command line, giving the lines to show in chat
+    // or why the command failed
+    pub fn dispatch(&self, line: &str, context: &mut dyn CommandContext) -> Result<String, String> {
+        let (command, arguments) = self.parse(line)?;
+        if context.permission() < command.permission {
+            return Err(format!("You don't have permission to use /{}", command.name))
+        }
+
+        match command.action {
+            Action::Help => self.help(&arguments),
+            Action::Run(run) => run(context, &arguments)
+        }
+    }
+
+    // checks a command line meant for a server, which runs it itself,
+    // answering here only what doesn't need the server (/help).
+    // Ok(None) means the line should be sent on
+    pub fn dispatch_remote(&self, line: &str) -> Result<Option<String>, String> {
+        let (command, arguments) = self.parse(line)?;
+        match command.action {
+            Action::Help => self.help(&arguments).map(Some),
+            Action::Run(_) => Ok(None)
+        }
+    }
+
+    fn parse(&self, line: &str) -> Result<(&Command, Arguments), String> {
+        let mut words = line.trim_start_matches('/').split_whitespace();
+        let name = words.next().unwrap_or("");
+        let command = self.find(name)
+            .ok_or_else(|| format!("Unknown command /{}, try /help", name))?;
+
+        let words: Vec<&str> = words.collect();
+        if words.len() > command.arguments.len() {
+            return Err(format!("Too many arguments, usage: {}", command.usage()))
+        }
+
+        let mut values = Vec::new();
+        for (i, argument) in command.arguments.iter().enumerate() {
+            match words.get(i) {
+                Some(word) => values.push(parse_value(argument, word)?),
+                None if argument.optional => break,
+                None => return Err(format!("Missing <{}>, usage: {}", argument.name, command.usage()))
+            }
+        }
+        Ok((command, Arguments { values }))
+    }
+
+    // completions for the word being typed at the end of a line,
+    // each the whole line with that word finished
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        if !line.starts_with('/') {
+            return Vec::new()
+        }
+
+        let words: Vec<&str> = line[1..].split(' ').collect();
+        let (typed, current) = words.split_at(words.len() - 1);
+        let current = current[0];
+        let candidates = match typed.first() {
+            None => self.command_names(),
+            Some(name) => {
+                let argument = self.find(name).and_then(|command| command.arguments.get(typed.len() - 1));
+                match argument {
+                    Some(argument) => self.candidates(argument.kind),
+                    None => Vec::new()
+                }
+            }
+        };
+
+        let start = &line[..line.len() - current.len()];
+        candidates.into_iter()
+            .filter(|candidate| candidate.starts_with(current))
+            .map(|candidate| format!("{}{}", start, candidate))
+            .collect()
+    }
+
+    // usage of the command being typed, shown above the chat input
+    pub fn usage(&self, line: &str) -> Option<String> {
+        if !line.starts_with('/') {
+            return None
+        }
+
+        let name = line[1..].split(' ').next().unwrap_or("");
+        self.find(name).map(|command| command.usage())
+    }
+
+    fn command_names(&self) -> Vec<String> {
+        self.commands.iter().map(|command| String::from(command.name)).collect()
+    }
+
+    fn candidates(&self, kind: ArgumentKind) -> Vec<String> {
+        match kind {
+            ArgumentKind::Coordinate => vec![String::from("~")],
+            ArgumentKind::Block => block_names().into_iter().map(|(_, name)| name).collect(),
+            ArgumentKind::Time => NAMED_TIMES.iter().map(|(name, _)| String::from(*name)).collect(),
+            ArgumentKind::Choice(options) => options.iter().map(|option| String::from(*option)).collect(),
+            ArgumentKind::CommandName => self.command_names()
+        }
+    }
+
+    fn help(&self, arguments: &Arguments) -> Result<String, String> {
+        if let Some(name) = arguments.word(0) {
+            let command = self.find(name).ok_or_else(|| format!("Unknown command /{}", name))?;
+            return Ok(format!("{} - {}", command.usage(), command.description))
+        }
+
+        let lines: Vec<String> = self.commands.iter()
+            .map(|command| format!("{} - {}", command.usage(), command.description))
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn register(&mut self, name: &'static str, description: &'static str, permission: PermissionLevel, arguments: Vec<Argument>, action: Action) {
+        self.commands.push(Command { name, description, permission, arguments, action });
+    }
+
+    fn register_builtin_commands(&mut self) {
+        use ArgumentKind::*;
+
+        self.register("help", "lists commands, or explains one", PermissionLevel::Player,
+            vec![optional("command", CommandName)], Action::Help);
+        self.register("tp", "teleports you", PermissionLevel::Operator,
+            vec![required("x", Coordinate), required("y", Coordinate), required("z", Coordinate)], Action::Run(tp));
+        self.register("give", "puts a block in your hand", PermissionLevel::Operator,
+            vec![required("block", Block)], Action::Run(give));
+        self.register("time", "shows or changes the time of day", PermissionLevel::Operator,
+            vec![required("set|add|query", Choice(&["set", "add", "query"])), optional("time", Time)], Action::Run(time));
+        self.register("seed", "shows the world seed", PermissionLevel::Player,
+            vec![], Action::Run(seed));
+        self.register("gamemode", "switches between walking and flying through blocks", PermissionLevel::Operator,
+            vec![required("survival|spectator", Choice(&["survival", "spectator"]))], Action::Run(gamemode));
+        self.register("setblock", "sets a block", PermissionLevel::Operator,
+            vec![required("x", Coordinate), required("y", Coordinate), required("z", Coordinate), required("block", Block)], Action::Run(setblock));
+        self.register("fill", "sets every block in a box", PermissionLevel::Operator,
+            vec![
+                required("x1", Coordinate), required("y1", Coordinate), required("z1", Coordinate),
+                required("x2", Coordinate), required("y2", Coordinate), required("z2", Coordinate),
+                required("block", Block)
+            ], Action::Run(fill));
+        self.register("spawnpoint", "sets where players start, here if no position is given", PermissionLevel::Operator,
+            vec![optional("x", Coordinate), optional("y", Coordinate), optional("z", Coordinate)], Action::Run(spawnpoint));
+    }
+}
+
+fn required(name: &'static str, kind: ArgumentKind) -> Argument {
+    Argument { name, kind, optional: false }
+}
+
+fn optional(name: &'static str, kind: ArgumentKind) -> Argument {
+    Argument { name, kind, optional: true }
+}
+
+fn parse_value(argument: &Argument, word: &str) -> Result<Value, String> {
+    match argument.kind {
+        ArgumentKind::Coordinate => {
+            let (number, relative) = match word.strip_prefix('~') {
+                Some("") => return Ok(Value::Coordinate { value: 0.0, relative: true }),
+                Some(offset) => (offset, true),
+                None => (word, false)
+            };
+            match number.parse::<f32>() {
+                Ok(value) if in_world(value) => Ok(Value::Coordinate { value, relative }),
+                Ok(_) => Err(format!("<{}> is outside the world, it can be at most {}", argument.name, MAX_COORDINATE)),
+                Err(_) => Err(format!("<{}> should be a number or ~, not {}", argument.name, word))
+            }
+        },
+        ArgumentKind::Block => block_from_name(word)
+            .map(Value::Block)
+            .ok_or_else(|| format!("Unknown block {}", word)),
+        ArgumentKind::Time => match NAMED_TIMES.iter().find(|(name, _)| *name == word) {
+            Some((_, ticks)) => Ok(Value::Ticks(*ticks)),
+            None => word.parse::<u64>()
+                .map(Value::Ticks)
+                .map_err(|_| format!("<{}> should be ticks or one of day, noon, sunset, night, midnight, not {}", argument.name, word))
+        },
+        ArgumentKind::Choice(options) => match options.contains(&word) {
+            true => Ok(Value::Word(String::from(word))),
+            false => Err(format!("<{}> should be one of {}, not {}", argument.name, options.join(", "), word))
+        },
+        ArgumentKind::CommandName => Ok(Value::Word(String::from(word)))
+    }
+}
+
+fn in_world(coordinate: f32) -> bool {
+    coordinate.is_finite() && coordinate.abs() <= MAX_COORDINATE
+}
+
+fn tp(context: &mut dyn CommandContext, arguments: &Arguments) -> Result<String, String> {
+    let position = arguments.position(0, context.position()).unwrap();
+    context.teleport(position)?;
+    Ok(format!("Teleported to {:.1} {:.1} {:.1}", position.x, position.y, position.z))
+}
+
+fn give(context: &mut dyn CommandContext, arguments: &Arguments) -> Result<String, String> {
+    let block = arguments.block(0).unwrap();
+    if block == BlockType::Air {
+        return Err(String::from("Air can't be held"))
+    }
+    context.give(block)?;
+    Ok(format!("Holding {}", block_name(block)))
+}
+
+fn time(context: &mut dyn CommandContext, arguments: &Arguments) -> Result<String, String> {
+    match (arguments.word(0).unwrap(), arguments.ticks(1)) {
+        ("query", _) => Ok(format!("The time is {}", context.time())),
+        ("set", Some(ticks)) => {
+            context.set_time(ticks)?;
+            Ok(format!("Set the time to {}", ticks))
+        },
+        ("add", Some(ticks)) => {
+            let time = context.time().checked_add(ticks).ok_or("That's too far ahead")?;
+            context.set_time(time)?;
+            Ok(format!("Set the time to {}", time))
+        },
+        (action, _) => Err(format!("/time {} needs a time", action))
+    }
+}
+
+fn seed(context: &mut dyn CommandContext, _: &Arguments) -> Result<String, String> {
+    match context.seed() {
+        Some(seed) => Ok(format!("Seed: {}", seed)),
+        None => Err(String::from("This world's seed isn't known"))
+    }
+}
+
+fn gamemode(context: &mut dyn CommandContext, arguments: &Arguments) -> Result<String, String> {
+    let game_mode = match arguments.word(0).unwrap() {
+        "spectator" => GameMode::Spectator,
+        _ => GameMode::Survival
+    };
+    context.set_game_mode(game_mode)?;
+    Ok(format!("Switched to {:?} mode", game_mode))
+}
+
+fn setblock(context: &mut dyn CommandContext, arguments: &Arguments) -> Result<String, String> {
+    let (x, y, z) = arguments.block_position(0, context.position())?;
+    let block = arguments.block(3).unwrap();
+    context.set_block(x, y, z, block)?;
+    Ok(format!("Set {} {} {} to {}", x, y, z, block_name(block)))
+}
+
+fn fill(context: &mut dyn CommandContext, arguments: &Arguments) -> Result<String, String> {
+    let from = arguments.block_position(0, context.position())?;
+    let to = arguments.block_position(3, context.position())?;
+    let block = arguments.block(6).unwrap();
+
+    // in i64 so opposite ends of the world can't overflow
+    let extent = |from: i32, to: i32| (from as i64 - to as i64).abs() + 1;
+    let size = extent(from.0, to.0).checked_mul(extent(from.1, to.1))
+        .and_then(|size| size.checked_mul(extent(from.2, to.2)));
+    match size {
+        Some(size) if size <= MAX_FILL_BLOCKS as i64 => (),
+        Some(size) => return Err(format!("Can't fill {} blocks at once, the most is {}", size, MAX_FILL_BLOCKS)),
+        None => return Err(format!("Can't fill that many blocks at once, the most is {}", MAX_FILL_BLOCKS))
+    }
+
+    let count = context.fill(from, to, block)?;
+    Ok(format!("Filled {} blocks with {}", count, block_name(block)))
+}
+
+fn spawnpoint(context: &mut dyn CommandContext, arguments: &Arguments) -> Result<String, String> {
+    let position = match arguments.len() {
+        0 => context.position(),
+        3 => arguments.position(0, context.position()).unwrap(),
+        _ => return Err(String::from("/spawnpoint needs all of x, y and z, or none"))
+    };
+    context.set_spawn_point(position)?;
+    Ok(format!("Set the spawn point to {:.1} {:.1} {:.1}", position.x, position.y, position.z))
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::{core::{block_type::BlockType, player::GameMode}, traits::command_context::CommandContext};
+
+    use super::{CommandDispatcher, MAX_FILL_BLOCKS, PermissionLevel};
+
+    // records what commands did instead of changing a world
+    struct FakeContext {
+        permission: PermissionLevel,
+        position: Vector3<f32>,
+        time: u64,
+        held: Option<BlockType>,
+        blocks: Vec<((i32, i32, i32), BlockType)>
+    }
+
+    impl FakeContext {
+        fn new(permission: PermissionLevel) -> FakeContext {
+            FakeContext { permission, position: Vector3::new(10.0, 64.0, -5.0), time: 0, held: None, blocks: Vec::new() }
+        }
+    }
+
+    impl CommandContext for FakeContext {
+        fn permission(&self) -> PermissionLevel {
+            self.permission
+        }
+
+        fn position(&self) -> Vector3<f32> {
+            self.position
+        }
+
+        fn teleport(&mut self, position: Vector3<f32>) -> Result<(), String> {
+            self.position = position;
+            Ok(())
+        }
+
+        fn give(&mut self, block: BlockType) -> Result<(), String> {
+            self.held = Some(block);
+            Ok(())
+        }
+
+        fn time(&self) -> u64 {
+            self.time
+        }
+
+        fn set_time(&mut self, ticks: u64) -> Result<(), String> {
+            self.time = ticks;
+            Ok(())
+        }
+
+        fn seed(&self) -> Option<u32> {
+            Some(42)
+        }
+
+        fn set_game_mode(&mut self, _: GameMode) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockType) -> Result<(), String> {
+            self.blocks.push(((x, y, z), block));
+            Ok(())
+        }
+
+        fn set_spawn_point(&mut self, _: Vector3<f32>) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    fn run(line: &str, context: &mut FakeContext) -> Result<String, String> {
+        CommandDispatcher::new().dispatch(line, context)
+    }
+
+    #[test]
+    fn parses_arguments() {
+        let mut context = FakeContext::new(PermissionLevel::Operator);
+        run("/tp 1 2.5 -3", &mut context).unwrap();
+        assert_eq!(context.position, Vector3::new(1.0, 2.5, -3.0));
+
+        run("/setblock 4 5 6 stone", &mut context).unwrap();
+        assert_eq!(context.blocks, vec![((4, 5, 6), BlockType::Stone)]);
+
+        run("/time set noon", &mut context).unwrap();
+        assert_eq!(context.time, 6000);
+        run("/time add 500", &mut context).unwrap();
+        assert_eq!(context.time, 6500);
+    }
+
+    #[test]
+    fn refuses_malformed_lines() {
+        let mut context = FakeContext::new(PermissionLevel::Operator);
+        assert!(run("/nothing", &mut context).unwrap_err().starts_with("Unknown command"));
+        assert!(run("/tp 1 2", &mut context).unwrap_err().starts_with("Missing <z>"));
+        assert!(run("/tp 1 2 3 4", &mut context).unwrap_err().starts_with("Too many arguments"));
+        assert!(run("/tp 1 two 3", &mut context).is_err());
+        assert!(run("/setblock 1 2 3 bedrock", &mut context).is_err());
+        assert!(run("/time set later", &mut context).is_err());
+        assert!(run("/gamemode creative", &mut context).is_err());
+        assert_eq!(context.position, Vector3::new(10.0, 64.0, -5.0));
+        assert!(context.blocks.is_empty());
+    }
+
+    #[test]
+    fn offsets_relative_coordinates_from_the_player() {
+        let mut context = FakeContext::new(PermissionLevel::Operator);
+        run("/tp ~ ~1.5 ~-2", &mut context).unwrap();
+        assert_eq!(context.position, Vector3::new(10.0, 65.5, -7.0));
+
+        run("/setblock ~1 ~ ~ dirt", &mut context).unwrap();
+        assert_eq!(context.blocks, vec![((11, 66, -7), BlockType::Dirt)]);
+    }
+
+    #[test]
+    fn refuses_coordinates_outside_the_world() {
+        let mut context = FakeContext::new(PermissionLevel::Operator);
+        assert!(run("/tp 40000000 0 0", &mut context).is_err());
+        assert!(run("/tp inf 0 0", &mut context).is_err());
+        // each part is in range, but not once added to the player's position
+        assert!(run("/setblock ~29999999 0 0 stone", &mut context).is_err());
+        assert!(context.blocks.is_empty());
+    }
+
+    #[test]
+    fn refuses_oversized_fills() {
+        let mut context = FakeContext::new(PermissionLevel::Operator);
+        assert!(run("/fill 0 0 0 100 100 100 stone", &mut context).is_err());
+        assert!(run("/fill -30000000 0 -30000000 30000000 255 30000000 stone", &mut context).is_err());
+        assert!(context.blocks.is_empty());
+
+        assert_eq!(run("/fill 0 0 0 1 1 1 stone", &mut context).unwrap(), "Filled 8 blocks with stone");
+        assert_eq!(context.blocks.len(), 8);
+
+        // exactly the limit is fine
+        let side = (MAX_FILL_BLOCKS as f32).cbrt().round() as usize;
+        assert_eq!(side * side * side, MAX_FILL_BLOCKS);
+        run(format!("/fill 0 0 0 {} {} {} stone", side - 1, side - 1, side - 1).as_str(), &mut context).unwrap();
+        assert_eq!(context.blocks.len(), 8 + MAX_FILL_BLOCKS);
+    }
+
+    #[test]
+    fn refuses_adding_time_past_the_end() {
+        let mut context = FakeContext::new(PermissionLevel::Operator);
+        context.time = 1;
+        assert!(run(format!("/time add {}", u64::MAX).as_str(), &mut context).is_err());
+        assert_eq!(context.time, 1);
+    }
+
+    #[test]
+    fn refuses_giving_air() {
+        let mut context = FakeContext::new(PermissionLevel::Operator);
+        assert!(run("/give air", &mut context).is_err());
+        assert_eq!(context.held, None);
+
+        run("/give sand", &mut context).unwrap();
+        assert_eq!(context.held, Some(BlockType::Sand));
+    }
+
+    #[test]
+    fn checks_permissions() {
+        let mut context = FakeContext::new(PermissionLevel::Player);
+        assert!(run("/tp 0 0 0", &mut context).unwrap_err().starts_with("You don't have permission"));
+        assert_eq!(run("/seed", &mut context).unwrap(), "Seed: 42");
+        assert!(run("/help tp", &mut context).unwrap().starts_with("/tp <x> <y> <z>"));
+    }
+
+    #[test]
+    fn answers_help_without_the_server() {
+        let dispatcher = CommandDispatcher::new();
+        assert!(dispatcher.dispatch_remote("/help").unwrap().is_some());
+        assert!(dispatcher.dispatch_remote("/tp 0 0 0").unwrap().is_none());
+        assert!(dispatcher.dispatch_remote("/tp 0").is_err());
+    }
+
+    #[test]
+    fn completes_the_word_being_typed() {
+        let dispatcher = CommandDispatcher::new();
+        assert_eq!(dispatcher.complete("/ti"), vec!["/time"]);
+        assert_eq!(dispatcher.complete("/time s"), vec!["/time set"]);
+        assert_eq!(dispatcher.complete("/time set mid"), vec!["/time set midnight"]);
+        assert_eq!(dispatcher.complete("/tp "), vec!["/tp ~"]);
+        assert!(dispatcher.complete("/give ").contains(&String::from("/give stone")));
+        assert!(dispatcher.complete("/seed ").is_empty());
+        assert!(dispatcher.complete("hello").is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+use std::fs;
+
+use cgmath::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::world_utils::localize_coords_to_chunk;
+
+use super::{block_type::BlockType, interpolation::Interpolation};
+
+// what an entity is, anything kind specific (behaviour, rendering,
+// size) is matched on this so new kinds only need a variant here
+// plus handling in World::update_entities and opengl::entity_renderer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EntityKind {
+    // another player on a server, never saved
+    Player { name: String },
+    FallingBlock { block: BlockType }
+}
+
+impl EntityKind {
+    // whether entities of this kind are saved with the chunk they're in
+    pub fn persistent(&self) -> bool {
+        !matches!(self, EntityKind::Player { .. })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entity {
+    pub id: u32,
+    // id the server uses for the entity, if mirrored from one
+    pub remote_id: Option<String>,
+    pub kind: EntityKind,
+    pub position: Vector3<f32>,
+    pub velocity: Vector3<f32>, // blocks per second
+    pub yaw: f32,
+    pub pitch: f32,
+    // snapshots from the server for remote players, see core::interpolation
+    pub interpolation: Option<Interpolation>,
+    // blocks walked, drives the limb animation
+    pub stride: f32
+}
+
+impl Entity {
+    pub fn new(id: u32, kind: EntityKind, position: Vector3<f32>) -> Entity {
+        Entity {
+            id,
+            remote_id: None,
+            kind,
+            position,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            yaw: -90.0,
+            pitch: 0.0,
+            interpolation: None,
+            stride: 0.0
+        }
+    }
+
+    pub fn block_position(&self) -> (i32, i32, i32) {
+        (self.position.x.round() as i32, self.position.y.round() as i32, self.position.z.round() as i32)
+    }
+
+    // chunk the entity belongs to and is saved with
+    pub fn chunk(&self) -> (i32, i32) {
+        let (chunk_x, chunk_z, _, _) = localize_coords_to_chunk(self.position.x.round() as i32, self.position.z.round() as i32);
+        (chunk_x, chunk_z)
+    }
+}
+
+// entity data as written to disk, ids are
+// reassigned when the entity is loaded
+#[derive(Serialize, Deserialize)]
+struct SavedEntity {
+    kind: EntityKind,
+    position: (f32, f32, f32),
+    velocity: (f32, f32, f32),
+    yaw: f32,
+    pitch: f32
+}
+
+// every loaded entity in a world
+#[derive(Debug, Clone, Default)]
+pub struct Entities {
+    entities: Vec<Entity>,
+    next_id: u32
+}
+
+impl Entities {
+    pub fn new() -> Entities {
+        Entities { entities: vec![], next_id: 0 }
+    }
+
+    pub fn spawn(&mut self, kind: EntityKind, position: Vector3<f32>) -> &mut Entity {
+        let entity = Entity::new(self.next_id, kind, position);
+        self.next_id = self.next_id.wrapping_add(1);
+        self.entities.push(entity);
+        self.entities.last_mut().unwrap()
+    }
+
+    // adds or replaces an entity mirrored from a server
+    pub fn spawn_remote(&mut self, remote_id: String, kind: EntityKind, position: Vector3<f32>) -> &mut Entity {
+        self.remove_remote(remote_id.as_str());
+        let entity = self.spawn(kind, position);
+        entity.remote_id = Some(remote_id);
+        entity
+    }
+
+    pub fn find_remote(&self, remote_id: &str) -> Option<&Entity> {
+        self.entities.iter().find(|entity| entity.remote_id.as_deref() == Some(remote_id))
+    }
+
+    pub fn find_remote_mut(&mut self, remote_id: &str) -> Option<&mut Entity> {
+        self.entities.iter_mut().find(|entity| entity.remote_id.as_deref() == Some(remote_id))
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<Entity> {
+        let index = self.entities.iter().position(|entity| entity.id == id)?;
+        Some(self.entities.remove(index))
+    }
+
+    pub fn remove_remote(&mut self, remote_id: &str) -> Option<Entity> {
+        let index = self.entities.iter().position(|entity| entity.remote_id.as_deref() == Some(remote_id))?;
+        Some(self.entities.remove(index))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Entity> {
+        self.entities.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Entity> {
+        self.entities.iter_mut()
+    }
+
+    // spawns the entities saved with a chunk, one JSON entity per line
+    pub fn load_chunk(&mut self, path: &str) {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return
+        };
+
+        for line in contents.lines() {
+            if let Ok(saved) = serde_json::from_str::<SavedEntity>(line) {
+                let (x, y, z) = saved.position;
+                let (vx, vy, vz) = saved.velocity;
+                let entity = self.spawn(saved.kind, Vector3::new(x, y, z));
+                entity.velocity = Vector3::new(vx, vy, vz);
+                entity.yaw = saved.yaw;
+                entity.pitch = saved.pitch;
+            }
+        }
+    }
+
+    // writes the persistent entities in a chunk, removing
+    // the file if there are none
+    pub fn save_chunk(&self, path: &str, chunk_x: i32, chunk_z: i32) {
+        let mut serialized = String::new();
+        for entity in self.entities.iter() {
+            if !entity.kind.persistent() || entity.chunk() != (chunk_x, chunk_z) {
+                continue;
+            }
+
+            let saved = SavedEntity {
+                kind: entity.kind.clone(),
+                position: (entity.position.x, entity.position.y, entity.position.z),
+                velocity: (entity.velocity.x, entity.velocity.y, entity.velocity.z),
+                yaw: entity.yaw,
+                pitch: entity.pitch
+            };
+            serialized.push_str(serde_json::to_string(&saved).unwrap().as_str());
+            serialized.push('\n');
+        }
+
+        if !serialized.is_empty() {
+            fs::write(path, serialized)
+                .unwrap_or_else(|_| panic!("Failed to save entities to {}", path));
+        } else if fs::metadata(path).is_ok() {
+            fs::remove_file(path)
+                .unwrap_or_else(|_| panic!("Failed to remove {}", path));
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::traits::game_world::GameWorld;
+
+use super::{block_type::BlockType, entity::Entity};
+
+// blocks per second squared
+const FALLING_BLOCK_GRAVITY: f32 = 32.0;
+
+// kept under a block per tick so a falling
+// block can't skip over the ground
+const FALLING_BLOCK_TERMINAL_VEL: f32 = 18.0;
+
+// longest step simulated at once, in ms
+const MAX_STEP: f32 = 50.0;
+
+// moves a falling block entity (sand etc. that lost its support)
+// down for the given ms, returning the y coordinate it landed at
+// once it hits the ground and should become a block again
+pub fn step_falling_block(entity: &mut Entity, world: &dyn GameWorld, deltatime: f32) -> Option<i32> {
+    let seconds = deltatime.min(MAX_STEP) / 1000.0;
+    let (x, _, z) = entity.block_position();
+    let velocity = (entity.velocity.y - FALLING_BLOCK_GRAVITY * seconds).max(-FALLING_BLOCK_TERMINAL_VEL);
+    let y = entity.position.y + velocity * seconds;
+
+    // spot the bottom of the block would be in
+    let below = (y - 0.5).floor() as i32;
+    match world.get_block(x, below, z) {
+        Some(BlockType::Air) | Some(BlockType::Water) => {
+            entity.position.y = y;
+            entity.velocity.y = velocity;
+            None
+        },
+        // chunk below hasn't loaded yet, wait for it
+        None if below >= 0 => None,
+        _ => {
+            entity.position.y = (below + 1) as f32;
+            entity.velocity.y = 0.0;
+            Some(below + 1)
+        }
+    }
+}
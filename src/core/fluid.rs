@@ -0,0 +1,138 @@
+use crate::traits::game_world::GameWorld;
+
+use super::{block_type::BlockType, world::World};
+
+// fluid levels, stored per block alongside chunk data
+// (only non-source water is stored, anything else is a source)
+// 1..=7   flowing water, decreasing with distance from its source
+// 8       falling water, a column poured from water above
+// 9       source water
+pub const MAX_FLOW_LEVEL: u8 = 7;
+pub const FALLING_LEVEL: u8 = 8;
+pub const SOURCE_LEVEL: u8 = 9;
+
+// fixed ticks between each spread of water
+pub const WATER_TICK_DELAY: u64 = 5;
+
+const HORIZONTAL_NEIGHBOURS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+// height the top of a full block of water is drawn at when there's
+// no water above it, so surfaces sit a little below the shore
+pub const WATER_SURFACE_HEIGHT: f32 = 0.875;
+
+// fraction of a full block the surface of the fluid sits at
+pub fn fluid_height(level: u8) -> f32 {
+    match level {
+        FALLING_LEVEL | SOURCE_LEVEL => WATER_SURFACE_HEIGHT,
+        level => level as f32 / (MAX_FLOW_LEVEL + 1) as f32 * WATER_SURFACE_HEIGHT
+    }
+}
+
+// level water at a spot will flow out to its neighbours with
+fn outgoing_level(level: u8) -> u8 {
+    match level {
+        FALLING_LEVEL | SOURCE_LEVEL => MAX_FLOW_LEVEL,
+        level => level - 1
+    }
+}
+
+// updates the water at the given world coordinates, returning
+// any spots whose fluid changed and should be updated next
+pub fn update_fluid(world: &mut World, x: i32, y: i32, z: i32) -> Vec<(i32, i32, i32)> {
+    let mut changed = Vec::new();
+    if world.get_fluid_level(x, y, z).is_none() {
+        return changed
+    }
+
+    // flowing water recalculates its level from its neighbours
+    // and dries up if it's no longer fed by anything
+    let mut level = world.get_fluid_level(x, y, z).unwrap();
+    if level != SOURCE_LEVEL {
+        let expected_level = expected_level(world, x, y, z);
+        if expected_level != level {
+            match expected_level {
+                0 => world.set_fluid(x, y, z, None),
+                expected_level => world.set_fluid(x, y, z, Some(expected_level))
+            }
+            push_neighbours(&mut changed, x, y, z);
+            if expected_level == 0 {
+                return changed
+            }
+            level = expected_level;
+        }
+    }
+
+    // fall if possible, otherwise spread out sideways
+    match world.get_block(x, y - 1, z) {
+        Some(BlockType::Air) => {
+            world.set_fluid(x, y - 1, z, Some(FALLING_LEVEL));
+            changed.push((x, y - 1, z));
+            return changed
+        },
+        Some(BlockType::Water) => {
+            let below = world.get_fluid_level(x, y - 1, z).unwrap();
+            if below != SOURCE_LEVEL && below != FALLING_LEVEL {
+                world.set_fluid(x, y - 1, z, Some(FALLING_LEVEL));
+                changed.push((x, y - 1, z));
+            }
+            return changed
+        },
+        // spot below isn't loaded yet
+        None if y > 0 => return changed,
+        _ => ()
+    }
+
+    let outgoing = outgoing_level(level);
+    if outgoing == 0 {
+        return changed
+    }
+
+    for (dx, dz) in HORIZONTAL_NEIGHBOURS.iter() {
+        let (nx, nz) = (x + dx, z + dz);
+        let can_flow = match world.get_block(nx, y, nz) {
+            Some(BlockType::Air) => true,
+            Some(BlockType::Water) => world.get_fluid_level(nx, y, nz).unwrap() < outgoing,
+            _ => false
+        };
+
+        if can_flow {
+            world.set_fluid(nx, y, nz, Some(outgoing));
+            changed.push((nx, y, nz));
+        }
+    }
+    changed
+}
+
+// level non-source water at a spot should have given what's around it,
+// 0 meaning it should dry up
+fn expected_level(world: &World, x: i32, y: i32, z: i32) -> u8 {
+    if world.get_block(x, y + 1, z) == Some(BlockType::Water) {
+        return FALLING_LEVEL
+    }
+
+    let mut level = 0;
+    for (dx, dz) in HORIZONTAL_NEIGHBOURS.iter() {
+        if let Some(neighbour) = world.get_fluid_level(x + dx, y, z + dz) {
+            // water can only spread sideways off of something solid
+            // so falling columns don't feed water beside them mid-air
+            let supported = match world.get_block(x + dx, y - 1, z + dz) {
+                Some(BlockType::Air) => false,
+                Some(BlockType::Water) => world.get_fluid_level(x + dx, y - 1, z + dz) == Some(SOURCE_LEVEL),
+                _ => true
+            };
+
+            if neighbour == SOURCE_LEVEL || supported {
+                level = level.max(outgoing_level(neighbour));
+            }
+        }
+    }
+    level
+}
+
+fn push_neighbours(positions: &mut Vec<(i32, i32, i32)>, x: i32, y: i32, z: i32) {
+    positions.push((x, y + 1, z));
+    positions.push((x, y - 1, z));
+    for (dx, dz) in HORIZONTAL_NEIGHBOURS.iter() {
+        positions.push((x + dx, y, z + dz));
+    }
+}
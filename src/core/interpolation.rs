@@ -0,0 +1,164 @@
+use std::collections::VecDeque;
+
+use cgmath::{InnerSpace, Vector3};
+
+use super::entity::Entity;
+
+// how far in the past (ms) remote players are shown, so there's
+// usually a snapshot either side of the time being drawn
+const INTERPOLATION_DELAY: f64 = 100.0;
+
+// longest a player keeps moving along their last velocity
+// once snapshots stop arriving, before stopping in place
+const MAX_EXTRAPOLATION: f64 = 250.0;
+
+const MAX_SNAPSHOTS: usize = 32;
+
+// blocks walked per full swing of the limbs
+const STRIDE_LENGTH: f32 = 1.6;
+
+// speed (blocks per second) at which limbs swing their furthest
+const FULL_SWING_SPEED: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Snapshot {
+    // local time in ms the values were sent at
+    time: f64,
+    values: [f32; 3]
+}
+
+// one kind of value received over time, e.g. positions
+#[derive(Debug, Clone, Default)]
+struct Track {
+    snapshots: VecDeque<Snapshot>
+}
+
+impl Track {
+    fn push(&mut self, time: f64, values: [f32; 3]) {
+        // late packets are older than what's already been shown
+        if let Some(last) = self.snapshots.back() {
+            if time <= last.time {
+                return
+            }
+        }
+
+        self.snapshots.push_back(Snapshot { time, values });
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+    }
+
+    // values at a time, interpolated between the snapshots either side
+    // or extrapolated (for a while) past the newest, angles are taken
+    // the short way round
+    fn sample(&self, time: f64, angles: bool) -> Option<[f32; 3]> {
+        let first = self.snapshots.front()?;
+        if time <= first.time {
+            return Some(first.values)
+        }
+
+        for (a, b) in self.snapshots.iter().zip(self.snapshots.iter().skip(1)) {
+            if time <= b.time {
+                let t = ((time - a.time) / (b.time - a.time)) as f32;
+                return Some(lerp(a.values, b.values, t, angles))
+            }
+        }
+
+        let last = self.snapshots.back().unwrap();
+        let before = match self.snapshots.iter().rev().nth(1) {
+            Some(before) => before,
+            None => return Some(last.values)
+        };
+        let ahead = (time - last.time).min(MAX_EXTRAPOLATION);
+        let t = 1.0 + (ahead / (last.time - before.time)) as f32;
+        Some(lerp(before.values, last.values, t, angles))
+    }
+}
+
+// buffered movement of a remote player, drawn slightly in the past
+// so it moves smoothly between the 20Hz updates from the server
+#[derive(Debug, Clone, Default)]
+pub struct Interpolation {
+    positions: Track,
+    directions: Track,
+    // local minus sender time for the quickest packet so far, used
+    // to place the sender's timestamps on the local clock
+    clock_offset: Option<f64>
+}
+
+impl Interpolation {
+    pub fn new() -> Interpolation {
+        Interpolation::default()
+    }
+
+    // sent is the sender's timestamp (0 if it didn't send one),
+    // received the local time in ms
+    pub fn push_position(&mut self, sent: u64, received: u64, position: Vector3<f32>) {
+        let time = self.local_time(sent, received);
+        self.positions.push(time, [position.x, position.y, position.z]);
+    }
+
+    pub fn push_direction(&mut self, sent: u64, received: u64, yaw: f32, pitch: f32) {
+        let time = self.local_time(sent, received);
+        self.directions.push(time, [yaw, pitch, 0.0]);
+    }
+
+    fn local_time(&mut self, sent: u64, received: u64) -> f64 {
+        if sent == 0 {
+            return received as f64
+        }
+
+        let offset = received as f64 - sent as f64;
+        let offset = match self.clock_offset {
+            Some(clock_offset) => clock_offset.min(offset),
+            None => offset
+        };
+        self.clock_offset = Some(offset);
+        sent as f64 + offset
+    }
+}
+
+// moves a remote player to where their snapshots put them at the given
+// local time, updating its velocity and stride for the limb animation
+pub fn step_remote_player(entity: &mut Entity, now: u64, deltatime: f32) {
+    let interpolation = match &entity.interpolation {
+        Some(interpolation) => interpolation,
+        None => return
+    };
+
+    let time = now as f64 - INTERPOLATION_DELAY;
+    if let Some([x, y, z]) = interpolation.positions.sample(time, false) {
+        let position = Vector3::new(x, y, z);
+        if deltatime > 0.0 {
+            entity.velocity = (position - entity.position) / (deltatime / 1000.0);
+        }
+        entity.position = position;
+    }
+
+    if let Some([yaw, pitch, _]) = interpolation.directions.sample(time, true) {
+        entity.yaw = yaw;
+        entity.pitch = pitch;
+    }
+
+    let speed = Vector3::new(entity.velocity.x, 0.0, entity.velocity.z).magnitude();
+    entity.stride += speed * deltatime / 1000.0;
+}
+
+// angle in degrees limbs are swung forwards (or back when negative)
+pub fn limb_swing(entity: &Entity) -> f32 {
+    let speed = Vector3::new(entity.velocity.x, 0.0, entity.velocity.z).magnitude();
+    let amount = (speed / FULL_SWING_SPEED).min(1.0);
+    (entity.stride / STRIDE_LENGTH * std::f32::consts::PI * 2.0).sin() * amount * 45.0
+}
+
+fn lerp(a: [f32; 3], b: [f32; 3], t: f32, angles: bool) -> [f32; 3] {
+    let mut values = [0.0; 3];
+    for i in 0..3 {
+        let mut difference = b[i] - a[i];
+        if angles {
+            difference = (difference + 180.0).rem_euclid(360.0) - 180.0;
+        }
+        values[i] = a[i] + difference * t;
+    }
+    values
+}
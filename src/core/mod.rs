@@ -1,8 +1,21 @@
 pub mod block_map;
 pub mod block_type;
 pub mod chunk;
+pub mod chunk_mesh;
 pub mod coord_map;
 pub mod face;
 pub mod world;
 pub mod player;
-pub mod window_mode;
\ No newline at end of file
+pub mod window_mode;
+pub mod world_info;
+pub mod fluid;
+pub mod block_tick;
+pub mod falling_block;
+pub mod entity;
+pub mod world_time;
+pub mod settings;
+pub mod ambient_occlusion;
+pub mod interpolation;
+pub mod command;
+pub mod world_commands;
+pub mod chat_log;
@@ -4,11 +4,31 @@ use crate::{core::block_type::BlockType, opengl::{camera::{Camera, CameraMode}},
 const GRAVITY: f32 = -0.005;
 const TERMINAL_VEL: f32 = -0.6;
 
+// vertical velocities in water are offset by the
+// constant 0.1 added in update_alt, so -0.12 sinks
+// slowly and -0.06 floats back up
+const WATER_GRAVITY: f32 = -0.002;
+const WATER_TERMINAL_VEL: f32 = -0.12;
+const SWIM_VEL: f32 = -0.06;
+
+// movement is slowed to this fraction of normal in water
+const WATER_SPEED_MULTIPLIER: f32 = 0.4;
+
+// survival walks with collisions and gravity, spectator
+// flies freely through blocks (the free camera)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Survival,
+    Spectator
+}
+
 pub struct Player {
     pub camera: Camera,
     is_jumping: bool,
     velocity_y: f32,
-    camera_mode: CameraMode
+    camera_mode: CameraMode,
+    in_water: bool,
+    swimming_up: bool
 }
 
 impl Player {
@@ -18,7 +38,9 @@ impl Player {
             camera, 
             is_jumping: false, 
             velocity_y: TERMINAL_VEL,
-            camera_mode: CameraMode::FirstPerson 
+            camera_mode: CameraMode::FirstPerson,
+            in_water: false,
+            swimming_up: false
         }
     }
 
@@ -29,8 +51,20 @@ impl Player {
         }
     }
 
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.camera_mode = match game_mode {
+            GameMode::Survival => CameraMode::FirstPerson,
+            GameMode::Spectator => CameraMode::Free
+        };
+    }
+
     pub fn update_position(&mut self, world: &impl GameWorld, deltatime: f32) {
-        let old_position = self.camera.position.clone();
+        let old_position = self.camera.position;
+        let deltatime = if self.in_water && self.camera_mode != CameraMode::Free {
+            deltatime * WATER_SPEED_MULTIPLIER
+        } else {
+            deltatime
+        };
         self.camera.update_position(deltatime, self.camera_mode); 
         if self.camera_mode == CameraMode::Free {
             return;
@@ -59,21 +93,39 @@ impl Player {
             return;
         }
 
+        // water at either the feet or head
+        self.in_water = world.get_block(x, y, z) == Some(BlockType::Water) 
+            || world.get_block(x, y - 1, z) == Some(BlockType::Water);
+        let (gravity, terminal_vel) = if self.in_water {
+            (WATER_GRAVITY, WATER_TERMINAL_VEL)
+        } else {
+            (GRAVITY, TERMINAL_VEL)
+        };
+
+        if self.in_water {
+            if self.swimming_up {
+                self.velocity_y = SWIM_VEL;
+            } else if self.velocity_y < terminal_vel {
+                // slow down when falling into water
+                self.velocity_y = terminal_vel;
+            }
+        }
+
         let ground_y = ground_y.unwrap() + 2;
-        let test_y = self.camera.position.y + 0.1 + self.velocity_y as f32;
+        let test_y = self.camera.position.y + 0.1 + self.velocity_y;
         if world.moveable(x, test_y.round() as i32, z) {
-            self.camera.position.y = test_y as f32;
+            self.camera.position.y = test_y;
         } else {
-            self.velocity_y = TERMINAL_VEL;
+            self.velocity_y = terminal_vel;
         }
 
         if self.camera.position.y < (ground_y as f32) - 0.001 {
             self.is_jumping = false;
             self.camera.position.y = ground_y as f32;
         }
-        self.velocity_y += GRAVITY;
-        if self.velocity_y < TERMINAL_VEL {
-            self.velocity_y = TERMINAL_VEL;
+        self.velocity_y += gravity;
+        if self.velocity_y < terminal_vel {
+            self.velocity_y = terminal_vel;
         }
     }
 
@@ -92,9 +144,15 @@ impl Player {
     }
 
     pub fn jump(&mut self) {
-        if !self.is_jumping {
+        // holding jump in water swims upwards
+        self.swimming_up = true;
+        if !self.is_jumping && !self.in_water {
             self.velocity_y = 0.05;
             self.is_jumping = true;
         }
     }
+
+    pub fn release_jump(&mut self) {
+        self.swimming_up = false;
+    }
 }
\ No newline at end of file
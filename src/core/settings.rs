@@ -0,0 +1,159 @@
+use std::fs;
+
+const SETTINGS_PATH: &str = "game_data/settings";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CloudMode {
+    Off,
+    // flat layer
+    Fast,
+    // boxes with shaded sides
+    Fancy
+}
+
+// graphics options, stored in game_data/settings as
+// "key value" lines so they can be edited by hand
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub shadows: bool,
+    // width and height of each shadow map cascade
+    pub shadow_resolution: u32,
+    // how far from the camera shadows are drawn, in blocks
+    pub shadow_distance: f32,
+    pub shadow_cascades: u32,
+    // post-processing
+    pub fxaa: bool,
+    pub vignette: bool,
+    // off by default so colours look as they were authored
+    pub tonemap: bool,
+    pub gamma: f32,
+    pub exposure: f32,
+    pub clouds: CloudMode,
+    // y of the bottom of the cloud layer
+    pub cloud_height: f32,
+    // blocks per second the clouds drift by
+    pub cloud_speed: f32,
+    // chunks around the player drawn at full detail
+    pub render_distance: u32,
+    pub server_render_distance: u32,
+    // outer edge (in chunks) of each ring of simplified terrain beyond
+    // the render distance, every ring is half the detail of the last
+    pub lod_rings: Vec<u32>
+}
+
+impl Settings {
+    pub fn default() -> Settings {
+        Settings {
+            shadows: true,
+            shadow_resolution: 2048,
+            shadow_distance: 96.0,
+            shadow_cascades: 3,
+            fxaa: true,
+            vignette: true,
+            tonemap: false,
+            gamma: 2.2,
+            exposure: 1.0,
+            clouds: CloudMode::Fancy,
+            cloud_height: 100.0,
+            cloud_speed: 0.6,
+            render_distance: 20,
+            server_render_distance: 10,
+            lod_rings: vec![32, 48, 64]
+        }
+    }
+
+    // reads settings from disk, falling back to defaults for
+    // anything missing and writing the full file back
+    pub fn load() -> Settings {
+        let mut settings = Settings::default();
+        if let Ok(contents) = fs::read_to_string(SETTINGS_PATH) {
+            for line in contents.lines() {
+                let mut words = line.split_whitespace();
+                if let (Some(key), Some(value)) = (words.next(), words.next()) {
+                    settings.set(key, value);
+                }
+            }
+        }
+
+        settings.save();
+        settings
+    }
+
+    pub fn save(&self) {
+        let contents = format!(
+            "shadows {}\nshadow_resolution {}\nshadow_distance {}\nshadow_cascades {}\nfxaa {}\nvignette {}\ntonemap {}\ngamma {}\nexposure {}\nclouds {}\ncloud_height {}\ncloud_speed {}\nrender_distance {}\nserver_render_distance {}\nlod_rings {}\n",
+            self.shadows,
+            self.shadow_resolution,
+            self.shadow_distance,
+            self.shadow_cascades,
+            self.fxaa,
+            self.vignette,
+            self.tonemap,
+            self.gamma,
+            self.exposure,
+            match self.clouds {
+                CloudMode::Off => "off",
+                CloudMode::Fast => "fast",
+                CloudMode::Fancy => "fancy"
+            },
+            self.cloud_height,
+            self.cloud_speed,
+            self.render_distance,
+            self.server_render_distance,
+            if self.lod_rings.is_empty() {
+                "off".to_string()
+            } else {
+                self.lod_rings.iter().map(|ring| ring.to_string()).collect::<Vec<String>>().join(",")
+            }
+        );
+
+        fs::create_dir_all("game_data").expect("Failed to create game_data directory");
+        fs::write(SETTINGS_PATH, contents).expect("Failed to write settings to file");
+    }
+
+    // furthest chunk drawn in a local world, at any detail
+    pub fn view_distance(&self) -> u32 {
+        match self.lod_rings.last() {
+            Some(ring) => (*ring).max(self.render_distance),
+            None => self.render_distance
+        }
+    }
+
+    // sets a setting from its key and value as text,
+    // ignoring unknown keys and invalid values
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "shadows" => if let Ok(value) = value.parse() { self.shadows = value },
+            "shadow_resolution" => if let Ok(value) = value.parse::<u32>() { self.shadow_resolution = value.clamp(256, 8192) },
+            "shadow_distance" => if let Ok(value) = value.parse::<f32>() { self.shadow_distance = value.max(16.0) },
+            "shadow_cascades" => if let Ok(value) = value.parse::<u32>() { self.shadow_cascades = value.clamp(1, 4) },
+            "fxaa" => if let Ok(value) = value.parse() { self.fxaa = value },
+            "vignette" => if let Ok(value) = value.parse() { self.vignette = value },
+            "tonemap" => if let Ok(value) = value.parse() { self.tonemap = value },
+            "gamma" => if let Ok(value) = value.parse::<f32>() { self.gamma = value.clamp(1.0, 3.0) },
+            "exposure" => if let Ok(value) = value.parse::<f32>() { self.exposure = value.clamp(0.1, 4.0) },
+            "clouds" => match value {
+                "off" => self.clouds = CloudMode::Off,
+                "fast" => self.clouds = CloudMode::Fast,
+                "fancy" => self.clouds = CloudMode::Fancy,
+                _ => ()
+            },
+            "cloud_height" => if let Ok(value) = value.parse::<f32>() { self.cloud_height = value.clamp(0.0, 250.0) },
+            "cloud_speed" => if let Ok(value) = value.parse::<f32>() { self.cloud_speed = value.clamp(0.0, 20.0) },
+            "render_distance" => if let Ok(value) = value.parse::<u32>() { self.render_distance = value.clamp(2, 64) },
+            "server_render_distance" => if let Ok(value) = value.parse::<u32>() { self.server_render_distance = value.clamp(2, 32) },
+            "lod_rings" => {
+                // each ring has to reach further than the one before
+                let rings: Result<Vec<u32>, _> = value.split(',').map(|ring| ring.parse::<u32>()).collect();
+                match rings {
+                    Ok(rings) if rings.windows(2).all(|pair| pair[0] < pair[1]) => {
+                        self.lod_rings = rings.into_iter().map(|ring| ring.min(256)).collect()
+                    },
+                    _ if value == "off" => self.lod_rings = vec![],
+                    _ => ()
+                }
+            },
+            _ => ()
+        }
+    }
+}
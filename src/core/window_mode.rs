@@ -3,7 +3,13 @@
 pub enum WindowMode {
     Title,
     OpenWorld,
+    // saved and LAN servers, see opengl::server_list
+    ServerList,
+    // adding a server to the list or editing one
+    EditServer,
     ConnectToServer,
     InWorld,
-    InServer
+    InServer,
+    // lost connection to a server, see multiplayer::reconnect
+    Disconnected
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use std::{fs, rc::Rc};
+use std::{collections::HashSet, fs, rc::Rc};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use cgmath::{Vector3, InnerSpace};
@@ -6,20 +6,40 @@ use noise::{OpenSimplex, Seedable};
 
 use crate::{traits::{game_chunk::GameChunk, game_world::GameWorld}, utils::{num_utils::distance, world_utils::localize_coords_to_chunk}};
 
-use super::{block_type::BlockType, chunk::{CHUNK_HEIGHT, Chunk}, coord_map::CoordMap, face::Face};
+use rand::prelude::*;
+
+use super::{block_tick::{self, RANDOM_TICKS_PER_SECTION, RANDOM_TICK_DISTANCE, SECTION_HEIGHT}, block_type::{BlockType, receives_random_ticks}, chunk::{CHUNK_HEIGHT, CHUNK_SIZE, Chunk, above_snow_line, terrain_surface}, chunk_mesh::ChunkMesh, coord_map::CoordMap, entity::{Entities, EntityKind}, face::Face, falling_block::step_falling_block, fluid::WATER_TICK_DELAY, world_time::WorldTime};
 
 // Vector of Rc of a tuple of opaque and then transparent block point vertices
-type WorldMesh = Vec<Rc<(Vec<f32>, Vec<f32>)>>; 
+type WorldMesh = Vec<Rc<ChunkMesh>>; 
+
+// fixed rate the world simulation (block ticks) runs at
+pub const TICKS_PER_SECOND: f32 = 20.0;
+
+// ticks to catch up on at most per frame so a long
+// frame doesn't stall the game further
+const MAX_TICKS_PER_UPDATE: u32 = 10;
 
 #[derive(Clone)]
 pub struct World {
     chunks: CoordMap<Chunk>,
     render_distance: u32,
     simplex: OpenSimplex,
+    pub seed: u32,
     player_chunk_x: i32,
     player_chunk_z: i32,
     pub save_dir: String,
-    mesh: WorldMesh
+    mesh: WorldMesh,
+    tick_accumulator: f32,
+    pub tick: u64,
+    pub time: WorldTime,
+    // chunks with block ticks pending
+    ticking_chunks: HashSet<(i32, i32)>,
+    // chunks edited since they were last meshed and saved
+    dirty_chunks: HashSet<(i32, i32)>,
+    // chunks that only need saving, e.g. after scheduling a tick
+    unsaved_chunks: HashSet<(i32, i32)>,
+    pub entities: Entities
 }
 
 impl GameWorld for World {
@@ -30,8 +50,11 @@ impl GameWorld for World {
             return None
         }
 
-        let result = Some(chunk.unwrap().block_at(local_x, world_y as usize, local_z));
-        result
+        Some(chunk.unwrap().block_at(local_x, world_y as usize, local_z))
+    }
+
+    fn render_distance(&self) -> u32 {
+        self.render_distance
     }
 
     fn get_game_chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&dyn GameChunk> {
@@ -51,13 +74,30 @@ impl World {
         // create world directory if it does not exist
         let dir = format!("game_data/worlds/{}/chunks", save_dir);
         fs::create_dir_all(dir.clone()) 
-            .expect(format!("Failed to recursively create {}", dir.clone()).as_str());
+            .unwrap_or_else(|_| panic!("Failed to recursively create {}", dir.clone()));
 
         let chunks = CoordMap::new();
         let simplex = OpenSimplex::new().set_seed(seed);
         
         let save_dir = format!("game_data/worlds/{}", save_dir);
-        World { chunks, render_distance, simplex, player_chunk_x: 0, player_chunk_z: 0, save_dir, mesh: vec![] }
+        let time = WorldTime::load(save_dir.as_str());
+        World { 
+            chunks, 
+            render_distance, 
+            simplex, 
+            seed,
+            player_chunk_x: 0, 
+            player_chunk_z: 0, 
+            save_dir, 
+            mesh: vec![], 
+            tick_accumulator: 0.0, 
+            tick: 0, 
+            time, 
+            ticking_chunks: HashSet::new(), 
+            dirty_chunks: HashSet::new(), 
+            unsaved_chunks: HashSet::new(), 
+            entities: Entities::new() 
+        }
     }
 
     pub fn new(render_distance: u32, save_dir: &str) -> World {
@@ -72,7 +112,7 @@ impl World {
                 fs::create_dir_all(format!("game_data/worlds/{}", save_dir))
                     .expect("Failed to create world directory");
                 fs::write(seed_path.clone(), format!("{}", seed))
-                    .expect(format!("Failed to write seed to {}", seed_path).as_str());
+                    .unwrap_or_else(|_| panic!("Failed to write seed to {}", seed_path));
                 seed
             }
         };
@@ -83,7 +123,7 @@ impl World {
         let player_chunk_x = player_x / 16;
         let player_chunk_z = player_z / 16;
         if !force 
-            && self.mesh.len() > 0 
+            && !self.mesh.is_empty() 
             && self.player_chunk_x == player_chunk_x 
             && self.player_chunk_z == player_chunk_z {
             return &self.mesh
@@ -104,7 +144,7 @@ impl World {
             let x = (x as i32) - (self.render_distance as i32) + player_chunk_x;
             for z in 0..self.render_distance * 2 {
                 let z = (z as i32) - (self.render_distance as i32) + player_chunk_z;
-                if distance(player_chunk_x, player_chunk_z, x, z) > self.render_distance as f32 {
+                if !self.in_render_distance(player_chunk_x, player_chunk_z, x, z) {
                     continue;
                 }
 
@@ -121,17 +161,16 @@ impl World {
             let x = *x;
             let z = *z;
 
-            let mesh;
             let chunk = self.get_chunk(x, z).unwrap();
-            if chunk.mesh.0.len() != 0 {
-                mesh = chunk.mesh.clone();
+            let mesh = if !chunk.mesh.is_empty() {
+                chunk.mesh.clone()
             } else {
                 let right_chunk = self.get_chunk(x + 1, z).unwrap();
                 let left_chunk = self.get_chunk(x - 1, z).unwrap();
                 let front_chunk = self.get_chunk(x, z + 1).unwrap();
                 let back_chunk = self.get_chunk(x, z - 1).unwrap();
-                mesh = chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk);
-            }
+                chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk)
+            };
 
             let chunk = self.get_chunk_mut(x, z).unwrap();
             chunk.mesh = mesh;
@@ -141,17 +180,76 @@ impl World {
         self.mesh = meshes;
     }
 
+    // whether a chunk is meshed at full detail with the player in the given chunk
+    pub fn in_render_distance(&self, player_chunk_x: i32, player_chunk_z: i32, chunk_x: i32, chunk_z: i32) -> bool {
+        let render_distance = self.render_distance as i32;
+        chunk_x >= player_chunk_x - render_distance && chunk_x < player_chunk_x + render_distance
+            && chunk_z >= player_chunk_z - render_distance && chunk_z < player_chunk_z + render_distance
+            && distance(player_chunk_x, player_chunk_z, chunk_x, chunk_z) <= render_distance as f32
+    }
+
+    // chunk the world mesh was last built around
+    pub fn player_chunk(&self) -> (i32, i32) {
+        (self.player_chunk_x, self.player_chunk_z)
+    }
+
     pub fn get_or_insert_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> &Chunk {
         match self.chunks.contains(chunk_x, chunk_z) {
             true => self.chunks.get(chunk_x, chunk_z).unwrap(),
             false => {
-                let c = Chunk::new(chunk_x, chunk_z, self.simplex.clone(), format!("{}/chunks", self.save_dir));
+                let c = Chunk::new(chunk_x, chunk_z, self.simplex, format!("{}/chunks", self.save_dir), self.tick);
+                if c.has_scheduled_ticks() {
+                    self.ticking_chunks.insert((chunk_x, chunk_z));
+                }
+                self.entities.load_chunk(format!("{}/chunks/{}_{}_entities", self.save_dir, chunk_x, chunk_z).as_str());
                 self.chunks.insert(chunk_x, chunk_z, c);
+                self.reschedule_edge_water(chunk_x, chunk_z);
                 self.chunks.get(chunk_x, chunk_z).unwrap()
             }
         }
     }
 
+    // water can't flow into a chunk that isn't loaded, so once one is,
+    // flowing water on either side of its edges that could flow across
+    // is ticked, sources are left alone as they only flow when placed
+    fn reschedule_edge_water(&mut self, chunk_x: i32, chunk_z: i32) {
+        let size = CHUNK_SIZE as i32;
+        let mut to_tick = Vec::new();
+        for (out_x, out_z) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+            let neighbour = (chunk_x + out_x, chunk_z + out_z);
+            if !self.contains_chunk(neighbour.0, neighbour.1) {
+                continue;
+            }
+
+            // water in this chunk flowing out, then in the neighbour flowing in
+            for ((from_x, from_z), (step_x, step_z)) in [((chunk_x, chunk_z), (*out_x, *out_z)), (neighbour, (-out_x, -out_z))].iter() {
+                let spots: Vec<(usize, usize, usize)> = self.get_chunk(*from_x, *from_z).unwrap().flowing_water().cloned().collect();
+                for (local_x, y, local_z) in spots.into_iter() {
+                    // only spots along the edge facing the other chunk
+                    let (across_local_x, across_local_z) = (local_x as i32 + step_x, local_z as i32 + step_z);
+                    if across_local_x >= 0 && across_local_x < size && across_local_z >= 0 && across_local_z < size {
+                        continue;
+                    }
+
+                    let (x, y, z) = (from_x * size + local_x as i32, y as i32, from_z * size + local_z as i32);
+                    let across = (x + step_x, y, z + step_z);
+                    match (self.get_fluid_level(x, y, z), self.get_fluid_level(across.0, y, across.2)) {
+                        (Some(_), None) if self.get_block(across.0, y, across.2) == Some(BlockType::Air) => to_tick.push((x, y, z)),
+                        (Some(level), Some(other)) if level != other => {
+                            to_tick.push((x, y, z));
+                            to_tick.push(across);
+                        },
+                        _ => ()
+                    }
+                }
+            }
+        }
+
+        for (x, y, z) in to_tick.into_iter() {
+            self.schedule_tick(x, y, z, WATER_TICK_DELAY);
+        }
+    }
+
     pub fn get_chunk_mut(&mut self, chunk_x: i32, chunk_z: i32) -> Option<&mut Chunk> {
         match self.chunks.contains(chunk_x, chunk_z) {
             true => self.chunks.get_mut(chunk_x, chunk_z),
@@ -163,25 +261,334 @@ impl World {
         self.chunks.get(chunk_x, chunk_z)
     }
 
+    // y and type of the topmost block at the given world coords, from its
+    // chunk if loaded, otherwise from what the terrain generator would make
+    pub fn surface_at(&self, world_x: i32, world_z: i32) -> (usize, BlockType) {
+        match self.highest_in_column(world_x, world_z) {
+            Some(y) => (y, self.get_block(world_x, y as i32, world_z).unwrap_or(BlockType::Air)),
+            None => terrain_surface(world_x, world_z, self.simplex)
+        }
+    }
+
+    // whether snow settles at the given spot, the same
+    // places the terrain generator tops with snow
+    pub fn snows_at(&self, world_x: i32, world_y: i32, world_z: i32) -> bool {
+        world_y >= 0 && above_snow_line(world_x, world_z, world_y as usize + 1, self.simplex)
+    }
+
+    // block edit from the player, applied immediately
     pub fn set_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) {
+        if self.get_block(world_x, world_y, world_z).is_none() {
+            return;
+        }
+
+        self.set_block_data(world_x, world_y, world_z, block);
+        self.flush_dirty_chunks();
+    }
+
+    // sets every block in the box between two corners, remeshing
+    // once at the end, returns how many blocks were set
+    pub fn fill(&mut self, from: (i32, i32, i32), to: (i32, i32, i32), block: BlockType) -> usize {
+        let mut count = 0;
+        for x in from.0.min(to.0)..=from.0.max(to.0) {
+            for y in from.1.min(to.1)..=from.1.max(to.1) {
+                for z in from.2.min(to.2)..=from.2.max(to.2) {
+                    if self.get_block(x, y, z).is_some() {
+                        self.set_block_data(x, y, z, block);
+                        count += 1;
+                    }
+                }
+            }
+        }
+        self.flush_dirty_chunks();
+        count
+    }
+
+    // where players start in the world, set with /spawnpoint,
+    // otherwise above the ground at 0, 0
+    pub fn spawn_point(&self) -> Vector3<f32> {
+        let saved = fs::read_to_string(format!("{}/spawn_point", self.save_dir))
+            .ok()
+            .and_then(|contents| {
+                let coords: Vec<f32> = contents.split_whitespace().filter_map(|word| word.parse().ok()).collect();
+                match coords.as_slice() {
+                    [x, y, z] => Some(Vector3::new(*x, *y, *z)),
+                    _ => None
+                }
+            });
+
+        match saved {
+            Some(spawn_point) => spawn_point,
+            None => Vector3::new(0.0, self.highest_in_column(0, 0).unwrap_or(CHUNK_HEIGHT - 1) as f32 + 2.0, 0.0)
+        }
+    }
+
+    pub fn set_spawn_point(&self, position: Vector3<f32>) {
+        fs::write(format!("{}/spawn_point", self.save_dir), format!("{} {} {}", position.x, position.y, position.z))
+            .expect("Failed to write spawn point to file");
+    }
+
+    // sets a block without remeshing or saving, the chunk
+    // (and any neighbour sharing the edited edge) is marked dirty
+    // and handled by flush_dirty_chunks, surrounding blocks
+    // are notified so they can schedule ticks
+    pub fn set_block_data(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) {
+        let previous = match self.get_block(world_x, world_y, world_z) {
+            Some(previous) => previous,
+            None => return
+        };
+
         let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        self.get_chunk_mut(chunk_x, chunk_z).unwrap().set_block(local_x, world_y as usize, local_z, block);
+        self.mark_dirty(chunk_x, chunk_z, local_x, local_z);
+        block_tick::on_block_changed(self, world_x, world_y, world_z, previous, block);
+    }
 
-        // set block
-        {
-            let chunk = self.get_chunk_mut(chunk_x, chunk_z).unwrap();
-            chunk.set_block(local_x, world_y as usize, local_z, block);
+    // schedules a block tick the given number of ticks from now
+    pub fn schedule_tick(&mut self, world_x: i32, world_y: i32, world_z: i32, delay: u64) {
+        let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        let due = self.tick + delay;
+        match self.get_chunk_mut(chunk_x, chunk_z) {
+            Some(chunk) => chunk.schedule_tick(local_x, world_y as usize, local_z, due),
+            None => return
         }
 
-        // update chunk mesh
-        self.update_chunk_mesh(chunk_x, chunk_z);
+        self.ticking_chunks.insert((chunk_x, chunk_z));
+        self.unsaved_chunks.insert((chunk_x, chunk_z));
+    }
+
+    fn mark_dirty(&mut self, chunk_x: i32, chunk_z: i32, local_x: usize, local_z: usize) {
+        self.dirty_chunks.insert((chunk_x, chunk_z));
         if local_x == 0 {
-            self.update_chunk_mesh(chunk_x - 1, chunk_z)
+            self.dirty_chunks.insert((chunk_x - 1, chunk_z));
         } else if local_x == 15 {
-            self.update_chunk_mesh(chunk_x + 1, chunk_z)
-        } else if local_z == 0 {
-            self.update_chunk_mesh(chunk_x, chunk_z - 1)
+            self.dirty_chunks.insert((chunk_x + 1, chunk_z));
+        }
+
+        if local_z == 0 {
+            self.dirty_chunks.insert((chunk_x, chunk_z - 1));
         } else if local_z == 15 {
-            self.update_chunk_mesh(chunk_x, chunk_z + 1)
+            self.dirty_chunks.insert((chunk_x, chunk_z + 1));
+        }
+    }
+
+    fn flush_dirty_chunks(&mut self) {
+        let unsaved_chunks: Vec<(i32, i32)> = self.unsaved_chunks.drain().collect();
+        for (chunk_x, chunk_z) in unsaved_chunks.iter() {
+            if !self.dirty_chunks.contains(&(*chunk_x, *chunk_z)) {
+                self.save_chunk(*chunk_x, *chunk_z);
+            }
+        }
+
+        let dirty_chunks: Vec<(i32, i32)> = self.dirty_chunks.drain().collect();
+        for (chunk_x, chunk_z) in dirty_chunks.iter() {
+            let (chunk_x, chunk_z) = (*chunk_x, *chunk_z);
+            if self.get_chunk(chunk_x, chunk_z).is_none() {
+                continue;
+            }
+
+            self.save_chunk(chunk_x, chunk_z);
+
+            // chunks on the edge of the loaded area have no
+            // mesh until their neighbours are generated
+            if self.contains_chunk(chunk_x + 1, chunk_z)
+                && self.contains_chunk(chunk_x - 1, chunk_z)
+                && self.contains_chunk(chunk_x, chunk_z + 1)
+                && self.contains_chunk(chunk_x, chunk_z - 1) {
+                self.update_chunk_mesh(chunk_x, chunk_z);
+            }
+        }
+
+        if !dirty_chunks.is_empty() {
+            self.recalculate_mesh_from_perspective(self.player_chunk_x, self.player_chunk_z);
+        }
+    }
+
+    // saves a chunk's blocks along with the entities in it
+    fn save_chunk(&self, chunk_x: i32, chunk_z: i32) {
+        let chunk = match self.get_chunk(chunk_x, chunk_z) {
+            Some(chunk) => chunk,
+            None => return
+        };
+
+        chunk.save(self.tick);
+        self.entities.save_chunk(format!("{}/chunks/{}_{}_entities", self.save_dir, chunk_x, chunk_z).as_str(), chunk_x, chunk_z);
+    }
+
+    // saves every chunk that has entities in it, so
+    // entities mid-flight are kept when leaving the world
+    pub fn save_entities(&mut self) {
+        for entity in self.entities.iter() {
+            self.unsaved_chunks.insert(entity.chunk());
+        }
+        self.flush_dirty_chunks();
+    }
+
+    fn contains_chunk(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.chunks.contains(chunk_x, chunk_z)
+    }
+
+    // runs as many fixed ticks as have elapsed since the last update
+    pub fn update(&mut self, deltatime: f32) {
+        let tick_length = 1000.0 / TICKS_PER_SECOND;
+        self.tick_accumulator += deltatime;
+
+        let mut ticks = 0;
+        while self.tick_accumulator >= tick_length {
+            self.tick_accumulator -= tick_length;
+            if ticks < MAX_TICKS_PER_UPDATE {
+                self.update_tick();
+                ticks += 1;
+            }
+        }
+    }
+
+    fn update_tick(&mut self) {
+        self.tick += 1;
+        self.time.tick();
+        self.update_scheduled_ticks();
+        self.update_random_ticks();
+        self.update_entities();
+        self.flush_dirty_chunks();
+    }
+
+    pub fn spawn_falling_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) {
+        let position = Vector3::new(world_x as f32, world_y as f32, world_z as f32);
+        let entity = self.entities.spawn(EntityKind::FallingBlock { block }, position);
+        self.unsaved_chunks.insert(entity.chunk());
+    }
+
+    // moves entities by a tick, falling blocks that
+    // have landed are turned back into blocks
+    fn update_entities(&mut self) {
+        let tick_length = 1000.0 / TICKS_PER_SECOND;
+        let mut entities = std::mem::take(&mut self.entities);
+        let mut landed = Vec::new();
+        for entity in entities.iter_mut() {
+            let chunk = entity.chunk();
+            match entity.kind {
+                EntityKind::FallingBlock { block } => {
+                    if let Some(world_y) = step_falling_block(entity, self, tick_length) {
+                        let (world_x, _, world_z) = entity.block_position();
+                        landed.push((entity.id, world_x, world_y, world_z, block));
+                    }
+                },
+                EntityKind::Player { .. } => ()
+            }
+
+            // entity changed chunks, so save it with the new one
+            if entity.chunk() != chunk {
+                self.unsaved_chunks.insert(chunk);
+                self.unsaved_chunks.insert(entity.chunk());
+            }
+        }
+        self.entities = entities;
+
+        for (id, world_x, world_y, world_z, block) in landed.into_iter() {
+            if let Some(entity) = self.entities.remove(id) {
+                self.unsaved_chunks.insert(entity.chunk());
+            }
+            self.place_falling_block(world_x, world_y, world_z, block);
+        }
+    }
+
+    // a falling block landing somewhere already
+    // taken by a solid block is lost
+    fn place_falling_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) {
+        if self.moveable(world_x, world_y, world_z) {
+            self.set_block_data(world_x, world_y, world_z, block);
+        }
+    }
+
+    // runs every pending block tick that is due,
+    // ticks scheduled while running wait until later
+    fn update_scheduled_ticks(&mut self) {
+        let tick = self.tick;
+        let mut due = Vec::new();
+        let ticking_chunks: Vec<(i32, i32)> = self.ticking_chunks.drain().collect();
+        for (chunk_x, chunk_z) in ticking_chunks.into_iter() {
+            let chunk = match self.get_chunk_mut(chunk_x, chunk_z) {
+                Some(chunk) => chunk,
+                None => continue
+            };
+
+            let chunk_due = chunk.take_due_ticks(tick);
+            if chunk.has_scheduled_ticks() {
+                self.ticking_chunks.insert((chunk_x, chunk_z));
+            }
+            if !chunk_due.is_empty() {
+                self.unsaved_chunks.insert((chunk_x, chunk_z));
+            }
+
+            for (local_x, y, local_z) in chunk_due.into_iter() {
+                due.push((chunk_x * CHUNK_SIZE as i32 + local_x as i32, y as i32, chunk_z * CHUNK_SIZE as i32 + local_z as i32));
+            }
+        }
+
+        for (x, y, z) in due.into_iter() {
+            block_tick::scheduled_tick(self, x, y, z);
+        }
+    }
+
+    // picks RANDOM_TICKS_PER_SECTION blocks from each section
+    // of the chunks around the player and ticks any that react
+    fn update_random_ticks(&mut self) {
+        let mut rng = rand::thread_rng();
+        for chunk_x in (self.player_chunk_x - RANDOM_TICK_DISTANCE)..=(self.player_chunk_x + RANDOM_TICK_DISTANCE) {
+            for chunk_z in (self.player_chunk_z - RANDOM_TICK_DISTANCE)..=(self.player_chunk_z + RANDOM_TICK_DISTANCE) {
+                if !self.contains_chunk(chunk_x, chunk_z) {
+                    continue;
+                }
+
+                for section in 0..CHUNK_HEIGHT / SECTION_HEIGHT {
+                    for _ in 0..RANDOM_TICKS_PER_SECTION {
+                        let local_x = rng.gen_range(0..CHUNK_SIZE);
+                        let y = section * SECTION_HEIGHT + rng.gen_range(0..SECTION_HEIGHT);
+                        let local_z = rng.gen_range(0..CHUNK_SIZE);
+                        let block = self.get_chunk(chunk_x, chunk_z).unwrap().block_at(local_x, y, local_z);
+                        if receives_random_ticks(block) {
+                            let x = chunk_x * CHUNK_SIZE as i32 + local_x as i32;
+                            let z = chunk_z * CHUNK_SIZE as i32 + local_z as i32;
+                            block_tick::random_tick(self, x, y as i32, z, block);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // level of the water at a spot, None if it isn't water
+    pub fn get_fluid_level(&self, world_x: i32, world_y: i32, world_z: i32) -> Option<u8> {
+        if self.get_block(world_x, world_y, world_z) != Some(BlockType::Water) {
+            return None
+        }
+
+        let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        Some(self.get_chunk(chunk_x, chunk_z).unwrap().fluid_level(local_x, world_y as usize, local_z))
+    }
+
+    // sets the water level at a spot, removing the water if None
+    pub fn set_fluid(&mut self, world_x: i32, world_y: i32, world_z: i32, level: Option<u8>) {
+        let block = match self.get_block(world_x, world_y, world_z) {
+            Some(block) => block,
+            None => return
+        };
+
+        match level {
+            Some(level) => {
+                if block != BlockType::Water {
+                    self.set_block_data(world_x, world_y, world_z, BlockType::Water);
+                }
+
+                let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+                self.get_chunk_mut(chunk_x, chunk_z).unwrap().set_fluid_level(local_x, world_y as usize, local_z, level);
+                self.mark_dirty(chunk_x, chunk_z, local_x, local_z);
+            },
+            None => {
+                if block == BlockType::Water {
+                    self.set_block_data(world_x, world_y, world_z, BlockType::Air);
+                }
+            }
         }
     }
 
@@ -204,7 +611,7 @@ impl World {
 
         let mut result = Vec::new();
         loop {
-            check_position = check_position + dir;
+            check_position += dir;
             let x = check_position.x.round() as i32;
             let y = check_position.y.round() as i32;
             let z = check_position.z.round() as i32;
@@ -265,7 +672,7 @@ impl World {
             if range == 0 {
                 return None;
             }
-            range = range - 1;
+            range -= 1;
         }
     }
 }
@@ -0,0 +1,76 @@
+use cgmath::Vector3;
+
+use crate::traits::{command_context::CommandContext, game_world::GameWorld};
+
+use super::{block_type::{BlockType, index_to_block}, command::PermissionLevel, player::{GameMode, Player}, world::World};
+
+// commands run in a local world, where the player can do anything
+pub struct WorldCommands<'a> {
+    pub world: &'a mut World,
+    pub player: &'a mut Player,
+    pub current_block_index: &'a mut usize,
+    // set when blocks were changed so the mesh is rebuilt
+    pub force_recalculation: &'a mut bool
+}
+
+impl CommandContext for WorldCommands<'_> {
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Operator
+    }
+
+    fn position(&self) -> Vector3<f32> {
+        self.player.camera.position
+    }
+
+    fn teleport(&mut self, position: Vector3<f32>) -> Result<(), String> {
+        self.player.camera.position = position;
+        *self.force_recalculation = true;
+        Ok(())
+    }
+
+    fn give(&mut self, block: BlockType) -> Result<(), String> {
+        *self.current_block_index = (0..).map_while(index_to_block)
+            .position(|other| other == block)
+            .ok_or("That block can't be held")?;
+        Ok(())
+    }
+
+    fn time(&self) -> u64 {
+        self.world.time.ticks
+    }
+
+    fn set_time(&mut self, ticks: u64) -> Result<(), String> {
+        self.world.time.ticks = ticks;
+        Ok(())
+    }
+
+    fn seed(&self) -> Option<u32> {
+        Some(self.world.seed)
+    }
+
+    fn set_game_mode(&mut self, game_mode: GameMode) -> Result<(), String> {
+        self.player.set_game_mode(game_mode);
+        Ok(())
+    }
+
+    fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockType) -> Result<(), String> {
+        if self.world.get_block(x, y, z).is_none() {
+            return Err(format!("{} {} {} is not loaded", x, y, z))
+        }
+
+        self.world.set_block(x, y, z, block);
+        *self.force_recalculation = true;
+        Ok(())
+    }
+
+    fn set_spawn_point(&mut self, position: Vector3<f32>) -> Result<(), String> {
+        self.world.set_spawn_point(position);
+        Ok(())
+    }
+
+    fn fill(&mut self, from: (i32, i32, i32), to: (i32, i32, i32), block: BlockType) -> Result<usize, String> {
+        let count = self.world.fill(from, to, block);
+        *self.force_recalculation = true;
+        Ok(count)
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fs, io, path::Path, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::utils::file_utils::{copy_dir_all, dir_size, format_size};
+
+pub const WORLDS_DIR: &str = "game_data/worlds";
+
+// world used for the title screen background,
+// hidden from the world list
+const MENU_WORLD: &str = "menu_world";
+
+// metadata shown for each entry in the world list
+#[derive(Debug, Clone)]
+pub struct WorldInfo {
+    pub name: String,
+    pub last_played: Option<u64>, // unix seconds
+    pub size: u64, // bytes
+    pub thumbnail_path: Option<String>
+}
+
+impl WorldInfo {
+    pub fn from_dir(name: &str) -> WorldInfo {
+        let dir = format!("{}/{}", WORLDS_DIR, name);
+
+        // prefer the timestamp written on exit, otherwise
+        // fall back to when the world folder was last touched
+        let last_played = fs::read_to_string(format!("{}/last_played", dir))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .or_else(|| {
+                fs::metadata(&dir).ok()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|duration| duration.as_secs())
+            });
+
+        let thumbnail_path = format!("{}/thumbnail.png", dir);
+        let thumbnail_path = if Path::new(&thumbnail_path).exists() {
+            Some(thumbnail_path)
+        } else {
+            None
+        };
+
+        WorldInfo {
+            name: name.to_string(),
+            last_played,
+            size: dir_size(Path::new(&dir)),
+            thumbnail_path
+        }
+    }
+
+    pub fn last_played_text(&self) -> String {
+        let last_played = match self.last_played {
+            Some(last_played) => last_played,
+            None => return String::from("Never played")
+        };
+
+        let seconds = now().saturating_sub(last_played);
+        let (amount, unit) = if seconds < 60 {
+            return String::from("Played just now")
+        } else if seconds < 60 * 60 {
+            (seconds / 60, "minute")
+        } else if seconds < 60 * 60 * 24 {
+            (seconds / 60 / 60, "hour")
+        } else {
+            (seconds / 60 / 60 / 24, "day")
+        };
+
+        format!("Played {} {}{} ago", amount, unit, if amount == 1 { "" } else { "s" })
+    }
+
+    pub fn size_text(&self) -> String {
+        format_size(self.size)
+    }
+}
+
+// enumerates game_data/worlds/*, most recently played first
+pub fn list_worlds() -> Vec<WorldInfo> {
+    let entries = match fs::read_dir(WORLDS_DIR) {
+        Ok(entries) => entries,
+        Err(_) => return vec![]
+    };
+
+    let mut worlds: Vec<WorldInfo> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name != MENU_WORLD)
+        .map(|name| WorldInfo::from_dir(name.as_str()))
+        .collect();
+
+    worlds.sort_by(|a, b| b.last_played.cmp(&a.last_played).then(a.name.cmp(&b.name)));
+    worlds
+}
+
+pub fn world_exists(name: &str) -> bool {
+    Path::new(&format!("{}/{}", WORLDS_DIR, name)).exists()
+}
+
+pub fn delete_world(name: &str) -> io::Result<()> {
+    check_world_name(name).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    fs::remove_dir_all(format!("{}/{}", WORLDS_DIR, name))
+}
+
+// names become directories in WORLDS_DIR, so they can't be blank,
+// lead out of it, be the directory itself or the menu's world
+pub fn check_world_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err(String::from("World names can't be empty"))
+    }
+
+    if name.contains('/') || name.contains('\\') || name.contains("..") {
+        return Err(String::from("World names can't contain /, \\ or .."))
+    }
+
+    if name == "." || name == MENU_WORLD {
+        return Err(format!("\"{}\" is a reserved name", name))
+    }
+    Ok(())
+}
+
+pub fn rename_world(name: &str, new_name: &str) -> io::Result<()> {
+    check_world_name(new_name).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    if world_exists(new_name) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("World {} already exists", new_name)));
+    }
+
+    fs::rename(format!("{}/{}", WORLDS_DIR, name), format!("{}/{}", WORLDS_DIR, new_name))
+}
+
+// copies a world to the first free "<name> copy", "<name> copy 2", ...
+// and returns the name of the new world
+pub fn duplicate_world(name: &str) -> io::Result<String> {
+    let mut new_name = format!("{} copy", name);
+    let mut i = 2;
+    while world_exists(new_name.as_str()) {
+        new_name = format!("{} copy {}", name, i);
+        i += 1;
+    }
+
+    copy_dir_all(Path::new(&format!("{}/{}", WORLDS_DIR, name)), Path::new(&format!("{}/{}", WORLDS_DIR, new_name)))?;
+    Ok(new_name)
+}
+
+pub fn write_last_played(save_dir: &str) {
+    fs::write(format!("{}/last_played", save_dir), format!("{}", now()))
+        .expect("Failed to write last played time to file");
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
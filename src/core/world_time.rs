@@ -0,0 +1,130 @@
+use std::{f32::consts::PI, fs};
+
+use cgmath::{InnerSpace, Vector3};
+
+use super::world::TICKS_PER_SECOND;
+
+// ticks in a full day/night cycle (20 minutes)
+pub const DAY_LENGTH: u64 = 24000;
+
+// time new worlds start at, a little after sunrise
+const START_TIME: u64 = 1000;
+
+// time of day 0 is sunrise, DAY_LENGTH / 4 is noon,
+// DAY_LENGTH / 2 is sunset and 3 * DAY_LENGTH / 4 is midnight
+#[derive(Debug, Clone, Copy)]
+pub struct WorldTime {
+    pub ticks: u64,
+    // ms towards the next tick when advanced with advance()
+    partial: f32
+}
+
+// lighting and colours for a point in the day,
+// set as uniforms when drawing the world
+pub struct DayLighting {
+    // directions from the world towards each body
+    pub sun_direction: Vector3<f32>,
+    pub moon_direction: Vector3<f32>,
+    // direction light travels in, from the sun by day and the moon by night
+    pub light_direction: Vector3<f32>,
+    pub light_color: Vector3<f32>,
+    pub ambient_strength: f32,
+    // 0 at night, 1 during the day
+    pub daylight: f32,
+    // sky colour at the horizon and straight up
+    pub sky_color: Vector3<f32>,
+    pub zenith_color: Vector3<f32>,
+    pub fog_color: Vector3<f32>
+}
+
+impl WorldTime {
+    pub fn new(ticks: u64) -> WorldTime {
+        WorldTime { ticks, partial: 0.0 }
+    }
+
+    // reads the time saved in a world directory, if any
+    pub fn load(save_dir: &str) -> WorldTime {
+        let ticks = fs::read_to_string(format!("{}/time", save_dir))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(START_TIME);
+        WorldTime::new(ticks)
+    }
+
+    pub fn save(&self, save_dir: &str) {
+        fs::write(format!("{}/time", save_dir), format!("{}", self.ticks))
+            .expect("Failed to write world time to file");
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    // advances by the given ms, for when time isn't driven by
+    // World::update (between syncs from a server)
+    pub fn advance(&mut self, deltatime: f32) {
+        let tick_length = 1000.0 / TICKS_PER_SECOND;
+        self.partial += deltatime;
+        while self.partial >= tick_length {
+            self.partial -= tick_length;
+            self.tick();
+        }
+    }
+
+    // 0.0 - 1.0 through the current day
+    pub fn day_fraction(&self) -> f32 {
+        (self.ticks % DAY_LENGTH) as f32 / DAY_LENGTH as f32
+    }
+
+    pub fn lighting(&self) -> DayLighting {
+        let angle = self.day_fraction() * 2.0 * PI;
+
+        // sun rises in +x and sets in -x, tilted slightly
+        // so it's never directly overhead
+        let sun_direction = Vector3::new(angle.cos(), angle.sin(), 0.25).normalize();
+        let moon_direction = -sun_direction;
+
+        // 0 at night, 1 during the day, blending around sunrise/sunset
+        let daylight = smoothstep(-0.1, 0.2, sun_direction.y);
+
+        let (light_direction, light_color) = if sun_direction.y > 0.0 {
+            (-sun_direction, Vector3::new(1.0, 1.0, 1.0) * daylight.max(0.3))
+        } else {
+            (-moon_direction, Vector3::new(0.5, 0.55, 0.7) * (1.0 - daylight))
+        };
+
+        let day_sky = Vector3::new(29.0 / 255.0, 104.0 / 255.0, 224.0 / 255.0);
+        let night_sky = Vector3::new(6.0 / 255.0, 9.0 / 255.0, 24.0 / 255.0);
+        let sunset_sky = Vector3::new(235.0 / 255.0, 120.0 / 255.0, 70.0 / 255.0);
+
+        // sky turns orange while the sun is near the horizon
+        let sunset = (1.0 - sun_direction.y.abs() / 0.25).max(0.0) * 0.6;
+        let sky_color = mix(mix(night_sky, day_sky, daylight), sunset_sky, sunset);
+
+        // deeper blue overhead, barely touched by the sunset
+        let day_zenith = Vector3::new(10.0 / 255.0, 60.0 / 255.0, 170.0 / 255.0);
+        let night_zenith = Vector3::new(1.0 / 255.0, 2.0 / 255.0, 8.0 / 255.0);
+        let zenith_color = mix(mix(night_zenith, day_zenith, daylight), sunset_sky, sunset * 0.2);
+
+        DayLighting {
+            sun_direction,
+            moon_direction,
+            light_direction,
+            light_color,
+            ambient_strength: 0.15 + 0.35 * daylight,
+            daylight,
+            sky_color,
+            zenith_color,
+            fog_color: sky_color
+        }
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn mix(a: Vector3<f32>, b: Vector3<f32>, t: f32) -> Vector3<f32> {
+    a * (1.0 - t) + b * t
+}
@@ -1,3 +1,6 @@
+// gl helpers and the game loop pass their state around as plain arguments
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
 // modules
 mod core;
 mod multiplayer;
@@ -6,21 +9,19 @@ mod traits;
 mod utils;
 
 // imports
-use std::{fs, path::Path, sync::{Arc, Mutex, mpsc::Receiver}, time::Instant};
+use std::{fs, io, sync::{Arc, Mutex}, time::{Duration, Instant}};
 use cgmath::{Deg, Matrix4, Vector3};
-use glutin::{ContextBuilder, dpi::{PhysicalPosition, PhysicalSize}, event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent}, event_loop::{ControlFlow, EventLoop}, window::WindowBuilder};
-use image::GenericImage;
-use noise::OpenSimplex;
-use gl::types::*;
+use glutin::{ContextBuilder, dpi::{PhysicalPosition, PhysicalSize}, event::{DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent}, event_loop::{ControlFlow, EventLoop}, window::{Window, WindowBuilder}};
 use rand::Rng;
-use crate::{core::{block_type::{BlockType, index_to_block}, face::Face, player::Player, window_mode::WindowMode, world::World}, multiplayer::{rc_message::RustyCraftMessage, server_connection::ServerConnection, server_state::ServerState, server_world::ServerWorld}, opengl::{button::Button, camera::Camera, cloud::Cloud, input::Input, player_model::PlayerModel, shader::Shader, tex_quad::TexQuad, text_renderer::{TextJustification, TextRenderer}, texture::Texture, vertex_array::VertexArray, vertex_buffer::VertexBuffer}, traits::game_world::GameWorld, utils::{name_utils::gen_name, num_utils::distance, simplex_utils::sample}};
+use crate::{core::{block_type::{ANIMATED_TILES, BlockType, index_to_block}, chat_log::{ChatKind, ChatLog}, chunk::BLOCK_VERTEX_SIZE, command::CommandDispatcher, entity::EntityKind, face::Face, falling_block::step_falling_block, interpolation::step_remote_player, world_time::{DAY_LENGTH, DayLighting, WorldTime}, player::Player, settings::Settings, window_mode::WindowMode, world::World, world_commands::WorldCommands, world_info::{check_world_name, delete_world, duplicate_world, rename_world, write_last_played}}, multiplayer::{identity::{Authentication, Identity, TRUSTED_SERVERS_PATH, TrustedServers}, rc_message::RustyCraftMessage, lan_discovery::LanDiscovery, reconnect::Reconnect, server_connection::ServerConnection, server_info::SavedServer, server_state::ServerState, server_world::ServerWorld}, opengl::{button::Button, camera::Camera, chat_renderer::ChatRenderer, chunk_renderer::ChunkRenderer, cloud::Cloud, entity_renderer::EntityRenderer, fog::Fog, input::Input, lod_terrain::LodTerrain, post_processing::PostProcessing, shadow_map::ShadowMap, skybox::SkyBox, sun_moon::SunMoon, screenshot::save_screenshot, server_list::ServerList, shader::Shader, text_renderer::{TextJustification, TextRenderer}, texture::Texture, vertex_array::VertexArray, vertex_buffer::VertexBuffer, world_list::WorldList}, traits::game_world::GameWorld, utils::name_utils::gen_name};
 
 // settings
 const SCR_WIDTH: u32 = 1000;
 const SCR_HEIGHT: u32 = 600;
-
-const SERVER_RENDER_DISTANCE: u32 = 10;
-const LOCAL_RENDER_DISTANCE: u32 = 20;
+// how often to ping servers that answer them, see ServerState::TIMEOUT
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+// chat lines scrolled by page up/down
+const CHAT_PAGE: i32 = 10;
 
 fn main() {
     // unsafe { println!("{:?}", gl::GetString(gl::VERSION)); }
@@ -32,7 +33,7 @@ fn main() {
 unsafe fn start() {
     // glfw: initialize
     let el = EventLoop::new();
-    let wb = WindowBuilder::new().with_title("A fantastic window!");
+    let wb = WindowBuilder::new().with_title("A fantastic window!").with_inner_size(PhysicalSize::new(SCR_WIDTH, SCR_HEIGHT));
 
     let windowed_context = ContextBuilder::new().build_windowed(wb, &el).unwrap();
 
@@ -61,6 +62,13 @@ unsafe fn start() {
 
     let shader = Shader::new_with_geom("assets/shaders/voxal/vertex.vert", "assets/shaders/voxal/fragment.frag", "assets/shaders/voxal/geometry.geom");
 
+    // animated atlas tiles
+    shader.use_program();
+    for (tile, frames, frame_time) in ANIMATED_TILES.iter() {
+        shader.set_int(format!("tile_frames[{}]", tile).as_str(), *frames as i32);
+        shader.set_float(format!("tile_frame_times[{}]", tile).as_str(), *frame_time);
+    }
+
     // create vertex array
     let vao = VertexArray::new(); 
     vao.bind();
@@ -71,13 +79,18 @@ unsafe fn start() {
 
     // set vertex attribute pointers
     // position
-    vbo.add_float_attribute(3, 10);
+    vbo.add_float_attribute(3, BLOCK_VERTEX_SIZE);
     for _ in 0..6 {
         // block indices attributes
-        vbo.add_float_attribute(1, 10);
+        vbo.add_float_attribute(1, BLOCK_VERTEX_SIZE);
     }
     // faces to draw via bitwise
-    vbo.add_float_attribute(1, 10);
+    vbo.add_float_attribute(1, BLOCK_VERTEX_SIZE);
+    // block height (for partially filled water)
+    vbo.add_float_attribute(1, BLOCK_VERTEX_SIZE);
+    // packed face corner occlusion (see core::ambient_occlusion)
+    vbo.add_float_attribute(1, BLOCK_VERTEX_SIZE);
+    vbo.add_float_attribute(1, BLOCK_VERTEX_SIZE);
 
     let texture_map = Texture::new(
         "assets/textures/textures.png", 
@@ -93,9 +106,8 @@ unsafe fn start() {
     let mut last_x: f32 = 400.0;
     let mut last_y: f32 = 300.0;
 
-    // store if the mouse has entered the window to prevent
-    // initially yanking the camera
-    let mut first_mouse = true;
+    // mouse movement since the last frame
+    let mut mouse_motion = (0.0, 0.0);
 
     // init text renderer
     let text_renderer = TextRenderer::new(SCR_WIDTH, SCR_HEIGHT, "assets/font/OldSchoolAdventures.ttf");
@@ -109,7 +121,15 @@ unsafe fn start() {
 
     let mut current_block_index = 0;
 
-    let water_tint_quad = TexQuad::new("assets/textures/water.png", gl::TEXTURE0, true, SCR_WIDTH, SCR_HEIGHT);
+    // graphics settings
+    let settings = Settings::load();
+    let mut shadow_map = ShadowMap::new(&settings);
+
+    // see as far as the furthest terrain, with some room for the hills on it
+    player.camera.far = (settings.view_distance() as f32 * 16.0 * 1.1).max(1000.0);
+
+    // the scene is drawn offscreen then run through post effects
+    let mut post_processing = PostProcessing::new(SCR_WIDTH, SCR_HEIGHT);
 
     let mut menu_world = World::new_with_seed(10, "menu_world", 0);
 
@@ -128,90 +148,174 @@ unsafe fn start() {
     let button_height = 48.0;
     let button_x = SCR_WIDTH as f32 / 2.0;
     let select_worlds_button = Button::new("Open World", button_x, 280.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT);
-    let open_world_button = Button::new("Open", button_x, 210.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT);
     let connect_button = Button::new("Connect", button_x, 140.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT);
     let mut back_button = Button::new("Back", button_x, 60.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT);
-    let connect_to_server_button = Button::new("Connect to Server", button_x, 210.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT);
+    let connect_to_server_button = Button::new("Multiplayer", button_x, 210.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT);
+    let reconnect_button = Button::new("Reconnect", button_x, 140.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT);
+
+    // world list buttons
+    let small_button_width = 190.0;
+    let small_button_height = 40.0;
+    let play_button = Button::new("Play", button_x - 200.0, 110.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let create_world_button = Button::new("Create", button_x, 110.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let rename_world_button = Button::new("Rename", button_x - 200.0, 60.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let duplicate_world_button = Button::new("Duplicate", button_x, 60.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let delete_world_button = Button::new("Delete", button_x + 200.0, 60.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let world_list_back_button = Button::new("Back", button_x + 200.0, 110.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+
+    // server list buttons, Back is shared with the world list
+    let join_server_button = Button::new("Join", button_x - 200.0, 110.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let direct_connect_button = Button::new("Direct", button_x, 110.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let add_server_button = Button::new("Add", button_x - 200.0, 60.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let edit_server_button = Button::new("Edit", button_x, 60.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let remove_server_button = Button::new("Remove", button_x + 200.0, 60.0, small_button_width, small_button_height, SCR_WIDTH, SCR_HEIGHT);
+    let save_server_button = Button::new("Save", button_x, 140.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT);
 
     // inputs
-    let mut open_world_input = Input::new(button_x, 280.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT, 1.0, TextJustification::Center);
+    let mut open_world_input = Input::new(button_x, 160.0, 590.0, small_button_height, SCR_WIDTH, SCR_HEIGHT, 1.0, TextJustification::Center);
     let mut connect_to_server_input = Input::new(button_x, 210.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT, 1.0, TextJustification::Center);
-    let mut server_player_name_input = Input::new(button_x, 280.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT, 1.0, TextJustification::Center);
+    let mut server_player_name_input = Input::new(button_x, 160.0, 590.0, small_button_height, SCR_WIDTH, SCR_HEIGHT, 1.0, TextJustification::Center);
+    let mut server_name_input = Input::new(button_x, 280.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT, 1.0, TextJustification::Center);
+    let mut server_address_input = Input::new(button_x, 210.0, button_width, button_height, SCR_WIDTH, SCR_HEIGHT, 1.0, TextJustification::Center);
     let mut chat_input = Input::new(SCR_WIDTH as f32 / 2.0, 30.0, SCR_WIDTH as f32 + 30.0, button_height / 1.3, SCR_WIDTH, SCR_HEIGHT, 0.8, TextJustification::Left);
+    let chat_renderer = ChatRenderer::new(SCR_WIDTH, SCR_HEIGHT);
 
+    // world list
+    let mut world_list = WorldList::new(button_x, SCR_HEIGHT as f32 - 30.0, 590.0, 64.0, 6, SCR_WIDTH, SCR_HEIGHT);
+    world_list.refresh();
     let last_world = fs::read_to_string("game_data/last_world");
-    if last_world.is_ok() {
-        open_world_input.text = last_world.unwrap();
+    if let Ok(last_world) = last_world {
+        world_list.select_by_name(last_world.as_str());
     }
+    // status line under the world list, also used
+    // to confirm deletion with a second click
+    let mut world_list_status = String::new();
+    let mut pending_world_delete: Option<String> = None;
+
+    // server list, LAN servers are looked for while it's open
+    let mut server_list = ServerList::new(button_x, SCR_HEIGHT as f32 - 30.0, 590.0, 64.0, 6, SCR_WIDTH, SCR_HEIGHT);
+    let mut lan_discovery = LanDiscovery::new();
+    let mut server_list_status = String::new();
+    let mut pending_server_remove: Option<usize> = None;
+    // saved server being edited, None when adding one
+    let mut editing_server: Option<usize> = None;
     
-    let last_server = fs::read_to_string("game_data/last_server");
-    if last_server.is_ok() {
-        connect_to_server_input.text = last_server.unwrap();
+    if let Ok(last_server) = fs::read_to_string("game_data/last_server") {
+        connect_to_server_input.text = last_server;
     }
 
-    let player_name = fs::read_to_string("game_data/player_name");
-    if player_name.is_ok() {
-        server_player_name_input.text = player_name.unwrap();
-    } else {
-        server_player_name_input.text = gen_name();
-    }
+    server_player_name_input.text = fs::read_to_string("game_data/player_name").unwrap_or_else(|_| gen_name());
 
     // window
     let mut window_mode = WindowMode::Title;
 
     // placeholder world object
-    let mut world = None;
+    let mut world: Option<World> = None;
     let mut server_connection = None;
-    let mut server_state = None;
-    let mut did_just_fail_to_connect = false;
+    let mut server_state: Option<ServerState> = None;
+    // why the last attempt to connect failed, e.g. a changed server key
+    let mut connect_error: Option<String> = None;
+    // set while on the disconnected screen
+    let mut reconnect: Option<Reconnect> = None;
+    let mut last_ping = Instant::now();
     let mut shift_pressed = false;
-    let mut time = 0.01;
-    let mut server_chat_opened = false;
+    // seconds since the game started, for shader animations
+    let mut time = 0.0;
+    let mut chat_opened = false;
+    // chat lines in local worlds, only command output and what the player types
+    let mut local_chat = ChatLog::new();
+    let command_dispatcher = CommandDispatcher::new();
     let mut last_position_before_update_packet = Vector3::new(0.0, 0.0, 0.0);
     let mut update_position_packet = Instant::now();
 
-    // player model object
-    let player_model = PlayerModel::new("assets/textures/player_skin.png");
+    // player, falling block etc. models
+    let entity_renderer = EntityRenderer::new();
+
+    // sky, sun and moon
+    let skybox = SkyBox::new();
+    let sun_moon = SunMoon::new();
+
+    // the title screen world is always shown in the morning
+    let menu_time = WorldTime::new(DAY_LENGTH / 8);
 
-    // cloud model
-    let cloud = Cloud::new();
-    let cloud_simplex = OpenSimplex::new();
-    let mut cloud_z_offset = 0.0;
+    // cloud layer
+    let mut cloud = Cloud::new(&settings);
+    let mut chunk_renderer = ChunkRenderer::new();
+    let mut lod_terrain = LodTerrain::new(&settings);
 
     let mut show_gui = true;
 
     let subtitle_text = get_subtitle_text();
 
     let mut should_close = false;
+    let mut events: Vec<WindowEvent<'static>> = Vec::new();
     
     // render loop
     el.run(move |event, _, control_flow| {
         if should_close {
             *control_flow = ControlFlow::Exit;
         } else {
-            *control_flow = ControlFlow::Wait;
+            *control_flow = ControlFlow::Poll;
         }
 
+        // window events are queued up and handled once per frame,
+        // when everything that came in since the last one is cleared
+        match event {
+            Event::WindowEvent { event, .. } => {
+                match &event {
+                    WindowEvent::Resized(size) => windowed_context.resize(*size),
+                    WindowEvent::CloseRequested => should_close = true,
+                    _ => ()
+                }
+                if let Some(event) = event.to_static() {
+                    events.push(event);
+                }
+                return;
+            },
+            // raw mouse movement for looking around, unlike the
+            // cursor position it keeps going while the cursor is grabbed
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta: (dx, dy) }, .. } => {
+                mouse_motion.0 += dx;
+                mouse_motion.1 += dy;
+                return;
+            },
+            Event::MainEventsCleared => (),
+            _ => return
+        }
+
+        let (mouse_dx, mouse_dy) = std::mem::take(&mut mouse_motion);
+
         let deltatime = instant.elapsed().as_millis() as f32;
         instant = Instant::now();
-        time += 0.01;
-
-        // bind framebuffer
-        //framebuffer.bind();
+        time += deltatime / 1000.0;
 
-        cloud_z_offset += 0.01;
+        cloud.update(deltatime);
 
         let PhysicalSize { width: screen_width, height: screen_height } = windowed_context.window().inner_size();
 
+        // time of day of whatever world is being shown
+        let world_time = match (&window_mode, &world, &server_state) {
+            (WindowMode::InWorld, Some(world), _) => world.time,
+            (WindowMode::InServer, _, Some(state)) => *state.time.lock().unwrap(),
+            _ => menu_time
+        };
+        let lighting = world_time.lighting();
+
+        // draw into the scene framebuffer, the window
+        // mode sets underwater when the camera is in water
+        post_processing.begin(screen_width, screen_height);
+        let mut underwater = false;
+        // save directory of a world left this frame
+        let mut thumbnail_for: Option<String> = None;
+
         // clear buffers
-        gl::ClearColor(29.0 / 255.0, 104.0 / 255.0, 224.0 / 255.0, 1.0);
-        //gl::ClearColor(0.0 / 255.0, 0.0 / 255.0, 0.0 / 255.0, 1.0);
+        gl::ClearColor(lighting.sky_color.x, lighting.sky_color.y, lighting.sky_color.z, 1.0);
         gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT); 
         gl::Enable(gl::DEPTH_TEST);
         match window_mode {
-            WindowMode::Title | WindowMode::OpenWorld | WindowMode::ConnectToServer => {
-                match event {
-                    Event::WindowEvent { event, .. } => match event {
+            WindowMode::Title | WindowMode::OpenWorld | WindowMode::ServerList | WindowMode::EditServer | WindowMode::ConnectToServer | WindowMode::Disconnected => {
+                for event in events.drain(..) {
+                    match event {
                         WindowEvent::Resized(PhysicalSize { width, height }) => {
                             gl::Viewport(0, 0, width as i32, height as i32) 
                         },
@@ -221,6 +325,8 @@ unsafe fn start() {
                         },
                         WindowEvent::MouseInput { state: ElementState::Pressed, .. } => {
                             let last_y = SCR_HEIGHT as f32 - last_y;
+                            // server picked from the list or typed into direct connect
+                            let mut address_to_join: Option<String> = None;
                             match window_mode {
                                 WindowMode::Title => {
                                     if select_worlds_button.is_hovered(last_x, last_y, screen_width, screen_height) {
@@ -228,86 +334,243 @@ unsafe fn start() {
                                     }
                                 
                                     if connect_to_server_button.is_hovered(last_x, last_y, screen_width, screen_height) {
-                                        window_mode = WindowMode::ConnectToServer;
+                                        window_mode = WindowMode::ServerList;
+                                        server_list.refresh();
                                     }
                                 },
                                 WindowMode::OpenWorld => {
                                     open_world_input.update_focus(last_x, last_y);
-                                    if back_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                    if world_list.click(last_x, last_y) {
+                                        pending_world_delete = None;
+                                        world_list_status = String::new();
+                                    }
+
+                                    if world_list_back_button.is_hovered(last_x, last_y, screen_width, screen_height) {
                                         window_mode = WindowMode::Title;
+                                        pending_world_delete = None;
+                                        world_list_status = String::new();
                                     }
 
-                                    if open_world_button.is_hovered(last_x, last_y, screen_width, screen_height) {
-                                        let mut world_object = World::new(LOCAL_RENDER_DISTANCE, open_world_input.text.clone().as_str());
-                                        world_object.recalculate_mesh_from_perspective(0, 0);
-                                        let last_player_pos = fs::read_to_string(format!("game_data/worlds/{}/player_pos", open_world_input.text.clone().as_str()));
-                                        if let Ok(player_pos_str) = last_player_pos {
-                                            let words: Vec<&str> = player_pos_str.split(" ").collect();
-                                            let x = words[0].parse::<f32>();
-                                            let y = words[1].parse::<f32>();
-                                            let z = words[2].parse::<f32>();
-
-                                            if let Ok(x) = x {
-                                                player.camera.position.x = x;
-                                            }
+                                    // either open the selected world or create
+                                    // a new one with the typed name
+                                    let mut world_to_open = None;
+                                    if play_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        world_to_open = world_list.selected_world().map(|world| world.name.clone());
+                                    }
 
-                                            if let Ok(y) = y {
-                                                player.camera.position.y = y;
-                                            }
+                                    if create_world_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        match check_world_name(open_world_input.text.as_str()) {
+                                            Ok(_) => world_to_open = Some(open_world_input.text.clone()),
+                                            Err(error) => world_list_status = error
+                                        }
+                                    }
+
+                                    if let Some(name) = world_list.selected_world().map(|world| world.name.clone()) {
+                                        if rename_world_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                            world_list_status = match rename_world(name.as_str(), open_world_input.text.as_str()) {
+                                                Ok(_) => {
+                                                    world_list.refresh();
+                                                    world_list.select_by_name(open_world_input.text.as_str());
+                                                    format!("Renamed {} to {}", name, open_world_input.text)
+                                                },
+                                                Err(_) if open_world_input.text.is_empty() => String::from("Type a new name to rename the world"),
+                                                Err(e) => format!("Failed to rename {}: {}", name, e)
+                                            };
+                                        }
+
+                                        if duplicate_world_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                            world_list_status = match duplicate_world(name.as_str()) {
+                                                Ok(new_name) => {
+                                                    world_list.refresh();
+                                                    world_list.select_by_name(new_name.as_str());
+                                                    format!("Duplicated {} as {}", name, new_name)
+                                                },
+                                                Err(e) => format!("Failed to duplicate {}: {}", name, e)
+                                            };
+                                        }
 
-                                            if let Ok(z) = z {
-                                                player.camera.position.z = z;
+                                        if delete_world_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                            if pending_world_delete.as_ref() == Some(&name) {
+                                                world_list_status = match delete_world(name.as_str()) {
+                                                    Ok(_) => format!("Deleted {}", name),
+                                                    Err(e) => format!("Failed to delete {}: {}", name, e)
+                                                };
+                                                pending_world_delete = None;
+                                                world_list.refresh();
+                                            } else {
+                                                world_list_status = format!("Click Delete again to delete {}", name);
+                                                pending_world_delete = Some(name);
                                             }
-                                        } else {
-                                            player.camera.position.y = world_object.highest_in_column(0, 0).unwrap() as f32 + 2.0;
                                         }
+                                    }
 
-                                        world = Some(world_object);
+                                    if let Some(name) = world_to_open {
+                                        world = Some(open_world(name.as_str(), &mut player, &settings));
+                                        local_chat.clear();
+                                        lod_terrain.clear();
                                         window_mode = WindowMode::InWorld;
-                                        windowed_context.window().set_cursor_visible(false);
+                                        mouse_captured = true;
+                                        set_cursor_captured(windowed_context.window(), true);
                                         current_block_index = 0;
-                                        fs::write("game_data/last_world", open_world_input.text.clone())
+                                        pending_world_delete = None;
+                                        world_list_status = String::new();
+                                        fs::write("game_data/last_world", name)
                                             .expect("Failed to write world input text to file");
                                     }
                                 },
-                                WindowMode::ConnectToServer => {
-                                    connect_to_server_input.update_focus(last_x, last_y);
+                                WindowMode::ServerList => {
                                     server_player_name_input.update_focus(last_x, last_y);
-                                    if connect_button.is_hovered(last_x, last_y, screen_width, screen_height) {
-                                        let address = connect_to_server_input.text.clone();
-                                        let connection = ServerConnection::new(address.clone());
-                                        match connection {
-                                            Err(_) => {
-                                                did_just_fail_to_connect = true;
+                                    if server_list.click(last_x, last_y) {
+                                        pending_server_remove = None;
+                                        server_list_status = String::new();
+                                    }
+
+                                    if join_server_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        match server_list.selected_row() {
+                                            Some(row) => address_to_join = Some(row.address.clone()),
+                                            None => server_list_status = String::from("Select a server to join")
+                                        }
+                                    }
+
+                                    if direct_connect_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        window_mode = WindowMode::ConnectToServer;
+                                        connect_error = None;
+                                    }
+
+                                    if add_server_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        editing_server = None;
+                                        // LAN servers are added by saving the selected one
+                                        match server_list.selected_row().filter(|row| row.lan) {
+                                            Some(row) => {
+                                                server_name_input.text = row.name.clone();
+                                                server_address_input.text = row.address.clone();
                                             },
-                                            Ok(connection) => {
-                                                let mut world = ServerWorld::new(SERVER_RENDER_DISTANCE, connection.clone());
-                                                world.recalculate_mesh_from_perspective(0, 0);
-                                                let world = Arc::new(Mutex::new(world));
-                                                server_state = Some(ServerState::new(world.clone()));
-                                                connection.clone().send_message(RustyCraftMessage::PlayerJoin { name: String::from(server_player_name_input.text.clone()) })
-                                                    .expect("Failed to set name on join");
-                                                connection.clone().create_listen_thread(server_state.clone().unwrap());
-                                                server_connection = Some(connection.clone());
-                                                windowed_context.window().set_cursor_visible(true);
-                                                window_mode = WindowMode::InServer;
-                                                fs::write("game_data/last_server", address.clone())
-                                                    .expect("Failed to write world input text to file");
-                                                fs::write("game_data/player_name", server_player_name_input.text.clone())
-                                                    .expect("Failed to write world input text to file");
-                                                did_just_fail_to_connect = false;
-                                                current_block_index = 0;
+                                            None => {
+                                                server_name_input.text = String::new();
+                                                server_address_input.text = String::new();
                                             }
                                         }
+                                        window_mode = WindowMode::EditServer;
                                     }
 
-                                    if back_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                    if edit_server_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        match server_list.selected_saved() {
+                                            Some(index) => {
+                                                editing_server = Some(index);
+                                                server_name_input.text = server_list.servers[index].name.clone();
+                                                server_address_input.text = server_list.servers[index].address.clone();
+                                                window_mode = WindowMode::EditServer;
+                                            },
+                                            None => server_list_status = String::from("Select a saved server to edit")
+                                        }
+                                    }
+
+                                    if remove_server_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        match server_list.selected_saved() {
+                                            Some(index) if pending_server_remove == Some(index) => {
+                                                let name = server_list.servers[index].name.clone();
+                                                server_list_status = match server_list.remove(index) {
+                                                    Ok(()) => format!("Removed {}", name),
+                                                    Err(e) => format!("Failed to remove server: {}", e)
+                                                };
+                                                pending_server_remove = None;
+                                            },
+                                            Some(index) => {
+                                                server_list_status = format!("Click Remove again to remove {}", server_list.servers[index].name);
+                                                pending_server_remove = Some(index);
+                                            },
+                                            None => server_list_status = String::from("Select a saved server to remove")
+                                        }
+                                    }
+
+                                    if world_list_back_button.is_hovered(last_x, last_y, screen_width, screen_height) {
                                         window_mode = WindowMode::Title;
-                                        did_just_fail_to_connect = false;
+                                        pending_server_remove = None;
+                                        server_list_status = String::new();
+                                        connect_error = None;
+                                    }
+                                },
+                                WindowMode::EditServer => {
+                                    server_name_input.update_focus(last_x, last_y);
+                                    server_address_input.update_focus(last_x, last_y);
+                                    if save_server_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        match SavedServer::new(server_name_input.text.as_str(), server_address_input.text.as_str()) {
+                                            Ok(server) => {
+                                                let saved = match editing_server {
+                                                    Some(index) => server_list.edit(index, server),
+                                                    None => server_list.add(server)
+                                                };
+                                                server_list_status = match saved {
+                                                    Ok(()) => String::new(),
+                                                    Err(e) => format!("Failed to save server list: {}", e)
+                                                };
+                                                pending_server_remove = None;
+                                                window_mode = WindowMode::ServerList;
+                                            },
+                                            Err(e) => server_list_status = e
+                                        }
+                                    }
+
+                                    if back_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        server_list_status = String::new();
+                                        window_mode = WindowMode::ServerList;
+                                    }
+                                },
+                                WindowMode::ConnectToServer => {
+                                    connect_to_server_input.update_focus(last_x, last_y);
+                                    if connect_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        address_to_join = Some(connect_to_server_input.text.clone());
+                                    }
+
+                                    if back_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        window_mode = WindowMode::ServerList;
+                                        connect_error = None;
+                                    }
+                                },
+                                WindowMode::Disconnected => {
+                                    if reconnect_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        reconnect.as_mut().unwrap().retry_now();
+                                    }
+
+                                    if back_button.is_hovered(last_x, last_y, screen_width, screen_height) {
+                                        window_mode = WindowMode::ServerList;
+                                        server_list.refresh();
+                                        reconnect = None;
                                     }
                                 },
                                 _ => ()
                             }
+
+                            if let Some(address) = address_to_join {
+                                let joined = ServerConnection::new(address.clone())
+                                    .and_then(|connection| join_server(connection, server_player_name_input.text.as_str(), &settings));
+                                match joined {
+                                    Err(e) => {
+                                        connect_error = Some(e.to_string());
+                                    },
+                                    Ok((connection, state)) => {
+                                        server_state = Some(state);
+                                        server_connection = Some(connection);
+                                        last_ping = Instant::now();
+                                        mouse_captured = true;
+                                        set_cursor_captured(windowed_context.window(), true);
+                                        window_mode = WindowMode::InServer;
+                                        fs::write("game_data/last_server", address.clone())
+                                            .expect("Failed to write world input text to file");
+                                        fs::write("game_data/player_name", server_player_name_input.text.clone())
+                                            .expect("Failed to write world input text to file");
+                                        connect_error = None;
+                                        server_list_status = String::new();
+                                        current_block_index = 0;
+                                    }
+                                }
+                            }
+                        },
+                        WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(_, y_offset), .. } if window_mode == WindowMode::OpenWorld => {
+                            world_list.scroll(-y_offset.round() as i32);
+                        },
+                        WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(_, y_offset), .. } if window_mode == WindowMode::ServerList => {
+                            server_list.scroll(-y_offset.round() as i32);
                         },
                         WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(keycode), .. }, .. } => {
                             match keycode {
@@ -318,9 +581,15 @@ unsafe fn start() {
                                         WindowMode::OpenWorld => {
                                             open_world_input.type_key(keycode, shift_pressed, &text_renderer);
                                         },
+                                        WindowMode::ServerList => {
+                                            server_player_name_input.type_key(keycode, shift_pressed, &text_renderer);
+                                        },
+                                        WindowMode::EditServer => {
+                                            server_name_input.type_key(keycode, shift_pressed, &text_renderer);
+                                            server_address_input.type_key(keycode, shift_pressed, &text_renderer);
+                                        },
                                         WindowMode::ConnectToServer => {
                                             connect_to_server_input.type_key(keycode, shift_pressed, &text_renderer);
-                                            server_player_name_input.type_key(keycode, shift_pressed, &text_renderer);
                                         },
                                         _ => ()
                                     } 
@@ -328,13 +597,37 @@ unsafe fn start() {
                             }
                         },
                         _ => ()
-                    },
-                    _ => ()
+                    }
+                }
+
+                // rejoin once a reconnect attempt gets through
+                if let Some(connection) = reconnect.as_mut().and_then(|reconnect| reconnect.poll()) {
+                    match join_server(connection, server_player_name_input.text.as_str(), &settings) {
+                        Ok((connection, state)) => {
+                            server_state = Some(state);
+                            server_connection = Some(connection);
+                            last_ping = Instant::now();
+                            reconnect = None;
+                            mouse_captured = true;
+                            set_cursor_captured(windowed_context.window(), true);
+                            window_mode = WindowMode::InServer;
+                            current_block_index = 0;
+                        },
+                        Err(e) => reconnect.as_mut().unwrap().reason = format!("Failed to rejoin: {}", e)
+                    }
+                }
+
+                // statuses and LAN servers for the server list
+                if window_mode == WindowMode::ServerList {
+                    if lan_discovery.poll() {
+                        server_list.set_lan_servers(&lan_discovery.servers);
+                    }
+                    server_list.poll();
                 }
 
                 // if window mode was changed to InWorld:
                 if window_mode == WindowMode::InWorld || window_mode == WindowMode::InServer {
-                    continue;
+                    return;
                 }
     
                 // text_renderer.render_text("Create World", x + 20.0, 200.0, 1.0, Vector3::new(1.0, 0.0, 0.0));
@@ -344,6 +637,9 @@ unsafe fn start() {
                 
                 gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
+                skybox.draw(&menu_camera, &lighting);
+                sun_moon.draw(&menu_camera, &lighting);
+
                 // shader uniforms
                 shader.use_program();
                 shader.set_mat4("view", menu_camera.get_view());
@@ -352,6 +648,9 @@ unsafe fn start() {
                 shader.set_vec3("light_pos", Vector3::new(menu_camera.position.x, menu_camera.position.y - 2.0, menu_camera.position.z));
                 shader.set_vec3("view_pos", menu_camera.position);
                 shader.set_float("time", time);
+                set_lighting_uniforms(&shader, &lighting);
+                Fog::new(menu_world.render_distance(), lighting.fog_color, false).set_uniforms(&shader);
+                shadow_map.set_uniforms(&shader, false);
 
                 menu_camera.mouse_callback(0.15, 0.0);
     
@@ -360,29 +659,18 @@ unsafe fn start() {
                 shader.set_uint("texture_map", 0);
                 
                 // draw
-                vao.bind();
-                vbo.bind();
-    
                 let meshes = menu_world.get_world_mesh_from_perspective(0, 0, false);
-                // opaque block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.0, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.0.len() / 10) as GLint);
-                }
-    
-                // transparent block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.1, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.1.len() / 10) as GLint);
+                chunk_renderer.draw(&shader, &vao, &vbo, meshes, menu_camera.position);
+
+                // text (world list takes up the space of the logo)
+                if window_mode != WindowMode::OpenWorld && window_mode != WindowMode::ServerList {
+                    let x = (SCR_WIDTH / 2) as f32;
+                    let y = SCR_HEIGHT as f32 - 220.0;
+                    let subtitle_size = 0.7 + (yellow_text_size.sin() + 1.0) / 30.0;
+                    text_renderer.render_text_with_mat(subtitle_text.as_str(), 870.0 - subtitle_size * 100.0, y - 130.0, subtitle_size, Vector3::new(1.0, 1.0, 0.0), Matrix4::<f32>::from_angle_z(Deg(10.0)), TextJustification::Center);
+                    text_renderer.render_text("RustyCraft", x, y, 3.5, Vector3::new(1.0, 0.0, 0.0), TextJustification::Center);
+                    text_renderer.render_text("RustyCraft", x + 3.0, y - 3.0, 3.5, Vector3::new(173.0 / 255.0, 24.0 / 255.0, 24.0 / 255.0), TextJustification::Center);
                 }
-
-                // text
-                let x = (SCR_WIDTH / 2) as f32;
-                let y = SCR_HEIGHT as f32 - 220.0;
-                let subtitle_size = 0.7 + (yellow_text_size.sin() + 1.0) / 30.0;
-                text_renderer.render_text_with_mat(subtitle_text.as_str(), 870.0 - subtitle_size * 100.0, y - 130.0, subtitle_size, Vector3::new(1.0, 1.0, 0.0), Matrix4::<f32>::from_angle_z(Deg(10.0)), TextJustification::Center);
-                text_renderer.render_text("RustyCraft", x, y, 3.5, Vector3::new(1.0, 0.0, 0.0), TextJustification::Center);
-                text_renderer.render_text("RustyCraft", x + 3.0, y - 3.0, 3.5, Vector3::new(173.0 / 255.0, 24.0 / 255.0, 24.0 / 255.0), TextJustification::Center);
                 text_renderer.render_text("v1.0", SCR_WIDTH as f32 - 60.0, 10.0, 0.9, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
 
                 let last_y = SCR_HEIGHT as f32 - last_y;
@@ -392,63 +680,130 @@ unsafe fn start() {
                         connect_to_server_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
                     },
                     WindowMode::OpenWorld => {
-                        back_button.set_y(140.0);
-                        open_world_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        world_list.draw(&text_renderer);
+                        if !world_list_status.is_empty() {
+                            text_renderer.render_text(world_list_status.as_str(), button_x, 185.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Center);
+                        }
                         open_world_input.draw(&text_renderer);
+                        play_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        create_world_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        world_list_back_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        rename_world_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        duplicate_world_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        delete_world_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                    },
+                    WindowMode::ServerList => {
+                        server_list.draw(&text_renderer);
+                        let status = match &connect_error {
+                            Some(error) => format!("Failed to connect: {}", error),
+                            None => server_list_status.clone()
+                        };
+                        if !status.is_empty() {
+                            text_renderer.render_text(status.as_str(), button_x, 185.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Center);
+                        }
+                        server_player_name_input.draw(&text_renderer);
+                        join_server_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        direct_connect_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        world_list_back_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        add_server_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        edit_server_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        remove_server_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                    },
+                    WindowMode::EditServer => {
+                        back_button.set_y(70.0);
+                        text_renderer.render_text("Server Name", button_x - 210.0, 310.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                        text_renderer.render_text("Server Address", button_x - 210.0, 240.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                        if !server_list_status.is_empty() {
+                            text_renderer.render_text(server_list_status.as_str(), button_x, 345.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Center);
+                        }
+                        server_name_input.draw(&text_renderer);
+                        server_address_input.draw(&text_renderer);
+                        save_server_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
                         back_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
                     },
                     WindowMode::ConnectToServer => {
                         back_button.set_y(70.0);
                         connect_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
                         connect_to_server_input.draw(&text_renderer);
-                        server_player_name_input.draw(&text_renderer);
                         back_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
-                        if did_just_fail_to_connect {
+                        if let Some(error) = &connect_error {
                             text_renderer.render_text("Failed to Connect", button_x - 210.0, 310.0, 1.0, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                            text_renderer.render_text(error.as_str(), button_x, 345.0, 0.5, Vector3::new(1.0, 1.0, 1.0), TextJustification::Center);
                         }
                     },
+                    WindowMode::Disconnected => {
+                        let reconnect = reconnect.as_ref().unwrap();
+                        back_button.set_y(70.0);
+                        text_renderer.render_text(format!("Disconnected: {}", reconnect.reason).as_str(), button_x, 280.0, 0.9, Vector3::new(1.0, 1.0, 1.0), TextJustification::Center);
+                        text_renderer.render_text(reconnect.status().as_str(), button_x, 230.0, 0.6, Vector3::new(0.8, 0.8, 0.8), TextJustification::Center);
+                        reconnect_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                        back_button.draw(&text_renderer, last_x, last_y, screen_width, screen_height);
+                    },
                     _ => panic!("Attempted to display window mode that was not a title menu")
                 };
             },
             WindowMode::InWorld => {
-                let mut world = world.as_mut().unwrap();
+                let world = world.as_mut().unwrap();
 
                 // events
                 process_events(
-                    &mut window, 
-                    &events, 
+                    windowed_context.window(), 
+                    &mut events, 
                     &mut show_gui,
                     &mut mouse_captured,
                     &selected_coords,
-                    &mut world,
+                    world,
                     &mut player, 
                     &mut last_x, 
                     &mut last_y, 
-                    &mut first_mouse,
                     &mut force_recalculation,
                     &mut current_block_index,
-                    &mut window_mode
+                    &mut window_mode,
+                    &mut chat_opened,
+                    &mut chat_input,
+                    &mut shift_pressed,
+                    &text_renderer,
+                    &command_dispatcher,
+                    &mut local_chat
                 );
 
+                if mouse_captured {
+                    player.camera.mouse_callback(mouse_dx as f32, -mouse_dy as f32);
+                }
+
                 player.update_position(world, deltatime);
 
                 // update player altitude
                 player.update_alt(world);
 
+                // fixed tick world simulation
+                world.update(deltatime);
+
+                underwater = player.underwater(world);
+                let fog = Fog::new(settings.view_distance(), lighting.fog_color, underwater);
+
+                skybox.draw(&player.camera, &lighting);
+                sun_moon.draw(&player.camera, &lighting);
+
                 // draw clouds
-                render_clouds(&cloud, &player.camera, &player.camera.position, cloud_z_offset, cloud_simplex);
+                cloud.draw(&player.camera, &fog, &lighting, world.render_distance());
 
-                if show_gui {
-                    // draw text
-                    text_renderer.render_text(format!("FPS: {}", (1000.0 / deltatime).round()).as_str(), 10.0, (SCR_HEIGHT as f32) - 30.0, 1.0, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
-                    text_renderer.render_text(format!("x: {:.2}", player.camera.position.x).as_str(), 10.0, (SCR_HEIGHT as f32) - 50.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
-                    text_renderer.render_text(format!("y: {:.2}", player.camera.position.y).as_str(), 10.0, (SCR_HEIGHT as f32) - 70.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
-                    text_renderer.render_text(format!("z: {:.2}", player.camera.position.z).as_str(), 10.0, (SCR_HEIGHT as f32) - 90.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                // simplified terrain past the render distance, drawn before
+                // the chunks as water doesn't write depth to hide it behind
+                lod_terrain.update(world);
+                lod_terrain.draw(&player.camera, &fog, &lighting, &texture_map);
 
-                    let block = index_to_block(current_block_index).unwrap(); 
-                    text_renderer.render_text(format!("Selected block: {:?}", block).as_str(), 10.0, (SCR_HEIGHT as f32) - 110.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                // entities
+                for entity in world.entities.iter() {
+                    entity_renderer.draw(&player.camera, &fog, entity);
                 }
                 
+                let meshes = world.get_world_mesh_from_perspective(player.camera.position.x as i32, player.camera.position.z as i32, force_recalculation);
+                force_recalculation = false;
+
+                // shadow pass
+                shadow_map.render(&player.camera, lighting.light_direction, meshes, &vao, &vbo, screen_width, screen_height);
+
                 // shader uniforms
                 shader.use_program();
                 // transforms
@@ -456,28 +811,24 @@ unsafe fn start() {
                 shader.set_mat4("projection", player.camera.get_projection());
                 shader.set_mat4("model", Matrix4::<f32>::from_scale(1.0));
                 shader.set_vec3("light_pos", player.camera.position);
+                shader.set_vec3("view_pos", player.camera.position);
                 shader.set_float("time", time);
+                set_lighting_uniforms(&shader, &lighting);
+                fog.set_uniforms(&shader);
+                shadow_map.set_uniforms(&shader, true);
 
                 // bind texture
                 texture_map.bind(); 
                 shader.set_texture("texture_map", &texture_map);
 
                 // draw
-                vao.bind();
-                vbo.bind();
+                chunk_renderer.draw(&shader, &vao, &vbo, meshes, player.camera.position);
 
-                let meshes = world.get_world_mesh_from_perspective(player.camera.position.x as i32, player.camera.position.z as i32, force_recalculation);
-                force_recalculation = false;
-                // opaque block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.0, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.0.len() / 10) as GLint);
-                }
-                
-                // transparent block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.1, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.1.len() / 10) as GLint);
+                // left the world this frame, its thumbnail is
+                // taken once the frame is finished
+                if window_mode != WindowMode::InWorld {
+                    world.save_entities();
+                    thumbnail_for = Some(world.save_dir.clone());
                 }
         
                 selected_coords = world.raymarch_block(&player.camera.position, &player.camera.front);
@@ -487,131 +838,153 @@ unsafe fn start() {
                     }
                 }
 
-                // couldn't get framebuffer to work for post-processing
-                // so draw a blue textured transparent quad for underwater
-                // effect now
-                if player.underwater(world) {
-                    player.camera.speed = 0.003;
-                    water_tint_quad.draw(0.0, 0.0, SCR_WIDTH as f32, SCR_HEIGHT as f32, 0.7);
-                } else {
-                    if player.camera.speed < 0.008 {
-                        player.camera.speed = 0.008;
-                    }
-                }
             },
             WindowMode::InServer => {
                 // assume server connection must be Some
                 let connection = server_connection.as_mut().unwrap();
                 let state = server_state.clone().unwrap();
-                let server_world = state.world;
+                let server_world = state.world.clone();
 
-                for (_, event) in glfw::flush_messages(&events) {
+                for event in events.drain(..) {
                     match event {
-                        WindowEvent::FramebufferSize(width, height) => {
-                            gl::Viewport(0, 0, width, height);
+                        WindowEvent::Resized(PhysicalSize { width, height }) => {
+                            gl::Viewport(0, 0, width as i32, height as i32);
                         },
-                        WindowEvent::Scroll(_, y_offset) => {
-                            player.camera.scroll_callback(y_offset as f32);
+                        WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(_, y_offset), .. } if chat_opened => {
+                            state.chat.lock().unwrap().scroll((y_offset * 3.0).round() as i32);
                         },
-                        WindowEvent::CursorPos(xpos, ypos) => {
-                            let (x_pos, y_pos) = (xpos as f32, ypos as f32);
-                            let x_offset = x_pos - last_x;
-                            let y_offset = last_y - y_pos;
-                            last_x = x_pos;
-                            last_y = y_pos;
-            
-                            if mouse_captured {
-                                player.camera.mouse_callback(x_offset, y_offset);
-                                connection.send_message(RustyCraftMessage::PlayerDirection {
-                                    yaw: player.camera.yaw,
-                                    pitch: player.camera.pitch
-                                }).expect("Failed to send movement packet"); 
-                            }
+                        WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(_, y_offset), .. } => {
+                            player.camera.scroll_callback(y_offset);
                         },
-                        WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
-                            if mouse_captured {
-                                if let Some(((x, y, z), _)) = selected_coords {
-                                    connection.send_message(RustyCraftMessage::SetBlock { world_x: x, world_y: y, world_z: z, block: BlockType::Air })
-                                        .expect("Failed to send SetBlock packets");
-                                }
+                        WindowEvent::CursorMoved { position: PhysicalPosition { x, y }, .. } => {
+                            last_x = x as f32;
+                            last_y = y as f32;
+                        },
+                        WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } if mouse_captured => {
+                            if let Some(((x, y, z), _)) = selected_coords {
+                                edit_server_block(connection, &state, x, y, z, BlockType::Air);
+                                force_recalculation = true;
                             }
                         },
-                        WindowEvent::MouseButton(MouseButton::Button2, Action::Press, _) => {
-                            if mouse_captured {
-                                if let Some(((x, y, z), Some(face))) = selected_coords {
-                                    let place_position = get_block_on_face(x, y, z, &face);
-                                    if can_place_block_at_loc(player.camera.position, place_position.0, place_position.1, place_position.2) {
-                                        let block = index_to_block(current_block_index);
-                                        connection.send_message(RustyCraftMessage::SetBlock { world_x: place_position.0, world_y: place_position.1, world_z: place_position.2, block: block.unwrap() })
-                                            .expect("Failed to send SetBlock packets");
-                                    }
+                        WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Right, .. } if mouse_captured => {
+                            if let Some(((x, y, z), Some(face))) = selected_coords {
+                                let place_position = get_block_on_face(x, y, z, &face);
+                                if can_place_block_at_loc(player.camera.position, place_position.0, place_position.1, place_position.2) {
+                                    let block = index_to_block(current_block_index);
+                                    edit_server_block(connection, &state, place_position.0, place_position.1, place_position.2, block.unwrap());
+                                    force_recalculation = true;
                                 }
                             }
                         }, 
-                        WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                            if server_chat_opened {
-                                server_chat_opened = false;
-                                shift_pressed = false;
-                                mouse_captured = true;
-                                window.set_cursor_mode(CursorMode::Disabled);
-                                chat_input.set_focus(false);
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Escape), .. }, .. } => {
+                            if chat_opened {
+                                close_chat(windowed_context.window(), &mut chat_opened, &mut chat_input, &mut shift_pressed, &mut mouse_captured, &mut state.chat.lock().unwrap());
                             } else {
                                 window_mode = WindowMode::Title;
-                                window.set_cursor_mode(CursorMode::Normal);
-                                connection.send_message(RustyCraftMessage::Disconnect)
-                                    .expect("Failed to send disconnect message");
+                                set_cursor_captured(windowed_context.window(), false);
+                                // leaving anyway, so a failed send doesn't matter
+                                connection.send_message(RustyCraftMessage::Disconnect { reason: String::from("Left the server") }).ok();
+                                connection.close();
+                                state.chat.lock().unwrap().flush();
                             }
                         },
-                        WindowEvent::Key(Key::F3, _, Action::Press, _) => player.toggle_camera(),
-                        WindowEvent::Key(Key::F1, _, Action::Press, _) => show_gui = !show_gui,
-                        WindowEvent::Key(Key::LeftShift, _, Action::Press, _) if server_chat_opened => shift_pressed = true,
-                        WindowEvent::Key(Key::LeftShift, _, Action::Release, _) if server_chat_opened => shift_pressed = false,
-                        WindowEvent::Key(Key::Enter, _, Action::Press, _) if server_chat_opened => {
-                            connection.send_message(RustyCraftMessage::ChatMessage { content: chat_input.text.clone() })
-                                .expect("Failed to send chat message");
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::F3), .. }, .. } => player.toggle_camera(),
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::F1), .. }, .. } => show_gui = !show_gui,
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::LShift), .. }, .. } if chat_opened => shift_pressed = true,
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Released, virtual_keycode: Some(VirtualKeyCode::LShift), .. }, .. } if chat_opened => shift_pressed = false,
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Return), .. }, .. } if chat_opened => {
+                            let line = chat_input.text.clone();
+                            state.chat.lock().unwrap().sent(line.as_str());
+                            // the server runs commands itself, only
+                            // those it doesn't need are answered here
+                            let output = match line.starts_with('/') {
+                                true => command_dispatcher.dispatch_remote(line.as_str()),
+                                false => Ok(None)
+                            };
+                            match output {
+                                Ok(None) => send_to_server(connection, &state, RustyCraftMessage::ChatMessage { content: line }),
+                                Ok(Some(output)) => push_command_output(&mut state.chat.lock().unwrap(), Ok(output)),
+                                Err(error) => push_command_output(&mut state.chat.lock().unwrap(), Err(error))
+                            }
                             chat_input.text = String::new();
-                            shift_pressed = false;
-                            server_chat_opened = false;
-                            window.set_cursor_mode(CursorMode::Disabled);
-                            mouse_captured = true;
+                            close_chat(windowed_context.window(), &mut chat_opened, &mut chat_input, &mut shift_pressed, &mut mouse_captured, &mut state.chat.lock().unwrap());
                         },
-                        WindowEvent::Key(key, _, Action::Press, _) if server_chat_opened => {
-                            chat_input.type_key(key, shift_pressed, &text_renderer);
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Tab), .. }, .. } if chat_opened => {
+                            chat_input.complete(|text| command_dispatcher.complete(text));
+                        },
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Up), .. }, .. } if chat_opened => {
+                            if let Some(text) = state.chat.lock().unwrap().recall_previous(chat_input.text.as_str()) {
+                                chat_input.text = text;
+                            }
                         },
-                        WindowEvent::Key(Key::T, _, Action::Press, _) => {
-                            mouse_captured = false;
-                            server_chat_opened = true;
-                            window.set_cursor_mode(CursorMode::Normal);
-                            chat_input.set_focus(true);
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Down), .. }, .. } if chat_opened => {
+                            if let Some(text) = state.chat.lock().unwrap().recall_next() {
+                                chat_input.text = text;
+                            }
                         },
-                        WindowEvent::Key(Key::LeftSuper, _, Action::Press, _) => {
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::PageUp), .. }, .. } if chat_opened => state.chat.lock().unwrap().scroll(CHAT_PAGE),
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::PageDown), .. }, .. } if chat_opened => state.chat.lock().unwrap().scroll(-CHAT_PAGE),
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(key), .. }, .. } if chat_opened => {
+                            chat_input.type_key(key, shift_pressed, &text_renderer);
+                        },
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::T), .. }, .. } => open_chat(windowed_context.window(), &mut chat_opened, &mut chat_input, &mut mouse_captured, &mut state.chat.lock().unwrap(), ""),
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Slash), .. }, .. } => open_chat(windowed_context.window(), &mut chat_opened, &mut chat_input, &mut mouse_captured, &mut state.chat.lock().unwrap(), "/"),
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::LWin), .. }, .. } => {
                             mouse_captured = !mouse_captured;
-                            window.set_cursor_mode(match mouse_captured {
-                                true => CursorMode::Disabled,
-                                false => CursorMode::Normal
-                            });
+                            set_cursor_captured(windowed_context.window(), mouse_captured);
                         },
-                        WindowEvent::Key(Key::Up, _, Action::Press, _) => {
-                            if index_to_block(current_block_index + 1).is_some() {
-                                current_block_index += 1
-                            }
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Up), .. }, .. } if index_to_block(current_block_index + 1).is_some() => {
+                            current_block_index += 1
                         },
-                        WindowEvent::Key(Key::Down, _, Action::Press, _) => {
-                            if current_block_index > 0 { 
-                                current_block_index -= 1 
-                            }
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Down), .. }, .. } => {
+                            current_block_index = current_block_index.saturating_sub(1);
                         },
-                        WindowEvent::Key(Key::Space, _, Action::Press, _) => player.jump(),
-                        WindowEvent::Key(Key::LeftShift, _, Action::Press, _) => player.camera.speed = 0.05,
-                        WindowEvent::Key(Key::LeftShift, _, Action::Release, _) => player.camera.speed = 0.008,
-                        WindowEvent::Key(key, _, action, _) => player.camera.process_keyboard(key, action),
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Space), .. }, .. } => player.jump(),
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Released, virtual_keycode: Some(VirtualKeyCode::Space), .. }, .. } => player.release_jump(),
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::LShift), .. }, .. } => player.camera.speed = 0.05,
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Released, virtual_keycode: Some(VirtualKeyCode::LShift), .. }, .. } => player.camera.speed = 0.008,
+                        WindowEvent::KeyboardInput { input: KeyboardInput { state, virtual_keycode: Some(key), .. }, .. } => player.camera.process_keyboard(key, state),
                         _ => ()
                     }
                 }
 
+                if mouse_captured && (mouse_dx != 0.0 || mouse_dy != 0.0) {
+                    player.camera.mouse_callback(mouse_dx as f32, -mouse_dy as f32);
+                    send_to_server(connection, &state, RustyCraftMessage::PlayerDirection {
+                        yaw: player.camera.yaw,
+                        pitch: player.camera.pitch,
+                        time: state.millis()
+                    });
+                }
+
+                // keepalive, servers that don't answer pings
+                // are only dropped once the connection fails
+                if connection.protocol.keepalive() {
+                    if last_ping.elapsed() > KEEPALIVE_INTERVAL {
+                        send_to_server(connection, &state, RustyCraftMessage::Ping { time: state.millis() });
+                        last_ping = Instant::now();
+                    }
+
+                    if state.timed_out() {
+                        state.disconnect("Timed out", false);
+                    }
+                }
+
+                if let Some(disconnection) = state.disconnection() {
+                    if window_mode == WindowMode::InServer {
+                        connection.close();
+                        reconnect = Some(Reconnect::new(connection.address.clone(), disconnection));
+                        window_mode = WindowMode::Disconnected;
+                        chat_opened = false;
+                        chat_input.set_focus(false);
+                        mouse_captured = false;
+                        set_cursor_captured(windowed_context.window(), false);
+                    }
+                }
+
                 // continue if window mode was changed
-                if window_mode == WindowMode::Title {
-                    continue;
+                if window_mode != WindowMode::InServer {
+                    return;
                 }
                 let position = player.camera.position;
 
@@ -620,39 +993,56 @@ unsafe fn start() {
                 // update player altitude
                 player.update_alt(&*server_world.lock().unwrap());
 
-                // draw clouds
-                render_clouds(&cloud, &player.camera, &player.camera.position, cloud_z_offset, cloud_simplex);
+                // time keeps moving between syncs from the server
+                state.time.lock().unwrap().advance(deltatime);
 
-                if show_gui {
-                    // draw text
-                    text_renderer.render_text(format!("Connected to {}", connection.address).as_str(), 10.0, (SCR_HEIGHT as f32) - 30.0, 1.0, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
-                    text_renderer.render_text(format!("FPS: {}", (1000.0 / deltatime).round()).as_str(), 10.0, (SCR_HEIGHT as f32) - 60.0, 1.0, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
-                    text_renderer.render_text(format!("x: {:.2}", player.camera.position.x).as_str(), 10.0, (SCR_HEIGHT as f32) - 80.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
-                    text_renderer.render_text(format!("y: {:.2}", player.camera.position.y).as_str(), 10.0, (SCR_HEIGHT as f32) - 100.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
-                    text_renderer.render_text(format!("z: {:.2}", player.camera.position.z).as_str(), 10.0, (SCR_HEIGHT as f32) - 120.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                let fog = {
+                    let server_world = server_world.lock().unwrap();
+                    underwater = player.underwater(&*server_world);
+                    Fog::new(server_world.render_distance(), lighting.fog_color, underwater)
+                };
 
-                    let block = index_to_block(current_block_index).unwrap(); 
-                    text_renderer.render_text(format!("Selected block: {:?}", block).as_str(), 10.0, (SCR_HEIGHT as f32) - 140.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                skybox.draw(&player.camera, &lighting);
+                sun_moon.draw(&player.camera, &lighting);
 
-                    // chat
-                    let chat = state.chat_stack.lock().unwrap();
-                    for i in 0..chat.len().min(10) {
-                        let message = chat[chat.len() - 1 - i].as_str();
-                        text_renderer.render_text(message, 10.0, (i as f32) * 20.0 + 60.0, 0.65, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                // draw clouds
+                cloud.draw(&player.camera, &fog, &lighting, settings.server_render_distance);
+
+                // entities, falling blocks are simulated locally
+                // until the server says where they landed and
+                // players are moved between their snapshots
+                {
+                    let client_id = state.client_id.lock().unwrap().clone();
+                    let now = state.millis();
+                    let server_world = server_world.lock().unwrap();
+                    for entity in state.entities.lock().unwrap().iter_mut() {
+                        match entity.kind {
+                            EntityKind::FallingBlock { .. } => {
+                                step_falling_block(entity, &*server_world, deltatime);
+                            },
+                            EntityKind::Player { .. } => step_remote_player(entity, now, deltatime)
+                        }
+
+                        // skip own player model
+                        if entity.remote_id.as_deref() != Some(client_id.as_str()) {
+                            entity_renderer.draw(&player.camera, &fog, entity);
+                        }
                     }
                 }
 
-                if server_chat_opened {
-                    chat_input.draw(&text_renderer);
+                let x = position.x.round() as i32;
+                let z = position.z.round() as i32;
+ 
+                let mut server_world = server_world.lock().unwrap();
+                server_world.request_chunks();
+                for (x, y, z) in server_world.expire_edits() {
+                    state.chat.lock().unwrap().push(ChatKind::Error, format!("Server didn't answer block edit at {}, {}, {}", x, y, z).as_str());
                 }
+                let meshes = server_world.get_world_mesh_from_perspective(x, z, force_recalculation);
+                force_recalculation = false;
 
-                // player models
-                let client_id = state.client_id.lock().unwrap().clone();
-                for (_, p) in state.players.lock().unwrap().iter() {
-                    if p.id != client_id {
-                        player_model.draw(&player.camera, p.position, p.pitch, p.yaw);
-                    }
-                }
+                // shadow pass
+                shadow_map.render(&player.camera, lighting.light_direction, meshes, &vao, &vbo, screen_width, screen_height);
 
                 // shader uniforms
                 shader.use_program();
@@ -660,32 +1050,18 @@ unsafe fn start() {
                 shader.set_mat4("view", player.camera.get_view());
                 shader.set_mat4("projection", player.camera.get_projection());
                 shader.set_mat4("model", Matrix4::<f32>::from_scale(1.0));
+                shader.set_vec3("view_pos", player.camera.position);
+                shader.set_float("time", time);
+                set_lighting_uniforms(&shader, &lighting);
+                fog.set_uniforms(&shader);
+                shadow_map.set_uniforms(&shader, true);
 
                 // bind texture
                 texture_map.bind();
                 shader.set_texture("texture_map", &texture_map);
 
                 // draw
-                vao.bind();
-                vbo.bind();
-
-                let x = position.x.round() as i32;
-                let z = position.z.round() as i32;
- 
-                let mut server_world = server_world.lock().unwrap();
-                let meshes = server_world.get_world_mesh_from_perspective(x, z, force_recalculation);
-                force_recalculation = false;
-                // opaque block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.0, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.0.len() / 10) as GLint);
-                }
-                
-                // transparent block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.1, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.1.len() / 10) as GLint);
-                }
+                chunk_renderer.draw(&shader, &vao, &vbo, meshes, player.camera.position);
 
                 selected_coords = server_world.raymarch_block(&player.camera.position, &player.camera.front);
                 if let Some(((x, y, z), Some(face))) = selected_coords {
@@ -694,27 +1070,16 @@ unsafe fn start() {
                     }
                 } 
 
-                // couldn't get framebuffer to work for post-processing
-                // so draw a blue textured transparent quad for underwater
-                // effect now
-                if player.underwater(&*server_world) {
-                    player.camera.speed = 0.003;
-                    water_tint_quad.draw(0.0, 0.0, SCR_WIDTH as f32, SCR_HEIGHT as f32, 0.7);
-                } else {
-                    if player.camera.speed < 0.008 {
-                        player.camera.speed = 0.008;
-                    }
-                } 
-
                 // send position update packet at 20FPS if position changed
                 if (update_position_packet.elapsed().as_millis() as f32) > (1000.0 / 20.0) {
                     if player.camera.position != last_position_before_update_packet {
                         let position = player.camera.position;
-                        connection.send_message(RustyCraftMessage::PlayerPosition { 
+                        send_to_server(connection, &state, RustyCraftMessage::PlayerPosition { 
                             x: position.x, 
                             y: position.y, 
-                            z: position.z
-                        }).expect("Failed to send movement packet");
+                            z: position.z,
+                            time: state.millis()
+                        });
                         last_position_before_update_packet = player.camera.position;
                     }
                     update_position_packet = Instant::now();
@@ -722,11 +1087,77 @@ unsafe fn start() {
             }
         }
         
-        // second pass, draw framebuffer quad
-        // FrameBuffer::unbind();
-        // gl::ClearColor(1.0, 1.0, 1.0, 1.0);
-        // gl::Clear(gl::COLOR_BUFFER_BIT);
-        // framebuffer.draw();
+        // second pass, run the scene through post effects onto the screen
+        let post_effects = PostProcessing::effects_for(&window_mode, underwater, &settings, time);
+        post_processing.draw(&post_effects);
+
+        // the world list thumbnail is the finished image, before
+        // the GUI (which isn't drawn after leaving anyway)
+        if let Some(save_dir) = thumbnail_for {
+            save_screenshot(format!("{}/thumbnail.png", save_dir).as_str(), screen_width, screen_height, 160, 90);
+            write_last_played(save_dir.as_str());
+            world_list.refresh();
+        }
+
+        // the GUI is drawn over the finished image so
+        // the post effects don't distort or tint it
+        gl::Clear(gl::DEPTH_BUFFER_BIT);
+        match window_mode {
+            WindowMode::InWorld => {
+                if show_gui {
+                    // draw text
+                    text_renderer.render_text(format!("FPS: {}", (1000.0 / deltatime).round()).as_str(), 10.0, (SCR_HEIGHT as f32) - 30.0, 1.0, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                    text_renderer.render_text(format!("x: {:.2}", player.camera.position.x).as_str(), 10.0, (SCR_HEIGHT as f32) - 50.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                    text_renderer.render_text(format!("y: {:.2}", player.camera.position.y).as_str(), 10.0, (SCR_HEIGHT as f32) - 70.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                    text_renderer.render_text(format!("z: {:.2}", player.camera.position.z).as_str(), 10.0, (SCR_HEIGHT as f32) - 90.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+
+                    let block = index_to_block(current_block_index).unwrap(); 
+                    text_renderer.render_text(format!("Selected block: {:?}", block).as_str(), 10.0, (SCR_HEIGHT as f32) - 110.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                }
+
+                // the chat is shown while open even with the GUI hidden
+                if show_gui || chat_opened {
+                    chat_renderer.draw(&local_chat, chat_opened, &text_renderer);
+                }
+
+                if chat_opened {
+                    draw_command_usage(&command_dispatcher, &chat_input, &text_renderer);
+                    chat_input.draw(&text_renderer);
+                }
+            },
+            WindowMode::InServer => {
+                let connection = server_connection.as_ref().unwrap();
+                let state = server_state.as_ref().unwrap();
+                if show_gui {
+                    // draw text
+                    text_renderer.render_text(format!("Connected to {}", connection.address).as_str(), 10.0, (SCR_HEIGHT as f32) - 30.0, 1.0, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                    text_renderer.render_text(format!("FPS: {}", (1000.0 / deltatime).round()).as_str(), 10.0, (SCR_HEIGHT as f32) - 60.0, 1.0, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                    text_renderer.render_text(format!("x: {:.2}", player.camera.position.x).as_str(), 10.0, (SCR_HEIGHT as f32) - 80.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                    text_renderer.render_text(format!("y: {:.2}", player.camera.position.y).as_str(), 10.0, (SCR_HEIGHT as f32) - 100.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                    text_renderer.render_text(format!("z: {:.2}", player.camera.position.z).as_str(), 10.0, (SCR_HEIGHT as f32) - 120.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+
+                    let block = index_to_block(current_block_index).unwrap(); 
+                    text_renderer.render_text(format!("Selected block: {:?}", block).as_str(), 10.0, (SCR_HEIGHT as f32) - 140.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+
+                    let latency = match *state.latency.lock().unwrap() {
+                        Some(latency) => format!("Ping: {} ms", latency.round()),
+                        None => String::from("Ping: -")
+                    };
+                    text_renderer.render_text(latency.as_str(), 10.0, (SCR_HEIGHT as f32) - 160.0, 0.6, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                }
+
+                // the chat is shown while open even with the GUI hidden
+                if show_gui || chat_opened {
+                    chat_renderer.draw(&state.chat.lock().unwrap(), chat_opened, &text_renderer);
+                }
+
+                if chat_opened {
+                    draw_command_usage(&command_dispatcher, &chat_input, &text_renderer);
+                    chat_input.draw(&text_renderer);
+                }
+            },
+            _ => ()
+        }
 
         windowed_context.swap_buffers().unwrap();
 
@@ -735,35 +1166,63 @@ unsafe fn start() {
     });
 }
 
-fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>, show_gui: &mut bool, mouse_captured: &mut bool, selected_coords: &Option<((i32, i32, i32), Option<Face>)>, world: &mut World, player: &mut Player, last_x: &mut f32, last_y: &mut f32, first_mouse: &mut bool, force_recalculation: &mut bool, current_block_index: &mut usize, window_mode: &mut WindowMode) {
-    for (_, event) in glfw::flush_messages(events) {
+fn process_events(window: &Window, events: &mut Vec<WindowEvent<'static>>, show_gui: &mut bool, mouse_captured: &mut bool, selected_coords: &Option<((i32, i32, i32), Option<Face>)>, world: &mut World, player: &mut Player, last_x: &mut f32, last_y: &mut f32, force_recalculation: &mut bool, current_block_index: &mut usize, window_mode: &mut WindowMode, chat_opened: &mut bool, chat_input: &mut Input, shift_pressed: &mut bool, text_renderer: &TextRenderer, command_dispatcher: &CommandDispatcher, chat: &mut ChatLog) {
+    for event in events.drain(..) {
         match event {
-            WindowEvent::FramebufferSize(width, height) => {
-                unsafe { gl::Viewport(0, 0, width, height) }
+            // chat takes the keyboard while open
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Escape), .. }, .. } if *chat_opened => {
+                close_chat(window, chat_opened, chat_input, shift_pressed, mouse_captured, chat);
             },
-            WindowEvent::Scroll(_, y_offset) => {
-                player.camera.scroll_callback(y_offset as f32);
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::LShift), .. }, .. } if *chat_opened => *shift_pressed = true,
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Released, virtual_keycode: Some(VirtualKeyCode::LShift), .. }, .. } if *chat_opened => *shift_pressed = false,
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Return), .. }, .. } if *chat_opened => {
+                let line = chat_input.text.clone();
+                chat.sent(line.as_str());
+                if line.starts_with('/') {
+                    let mut context = WorldCommands { world: &mut *world, player: &mut *player, current_block_index: &mut *current_block_index, force_recalculation: &mut *force_recalculation };
+                    push_command_output(chat, command_dispatcher.dispatch(line.as_str(), &mut context));
+                } else if !line.is_empty() {
+                    chat.push(ChatKind::Player, format!("<You> {}", line).as_str());
+                }
+                chat_input.text = String::new();
+                close_chat(window, chat_opened, chat_input, shift_pressed, mouse_captured, chat);
             },
-            WindowEvent::CursorPos(xpos, ypos) => {
-                let (x_pos, y_pos) = (xpos as f32, ypos as f32);
-                let x_offset = x_pos - *last_x;
-                let y_offset = *last_y - y_pos;
-                *last_x = x_pos;
-                *last_y = y_pos;
-                if *first_mouse {
-                    *first_mouse = false;
-                    return;
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Tab), .. }, .. } if *chat_opened => {
+                chat_input.complete(|text| command_dispatcher.complete(text));
+            },
+            // recall what was sent before, or scroll back through the chat
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Up), .. }, .. } if *chat_opened => {
+                if let Some(text) = chat.recall_previous(chat_input.text.as_str()) {
+                    chat_input.text = text;
                 }
-
-                if !*mouse_captured {
-                    return;
+            },
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Down), .. }, .. } if *chat_opened => {
+                if let Some(text) = chat.recall_next() {
+                    chat_input.text = text;
                 }
-
-                player.camera.mouse_callback(x_offset, y_offset);
             },
-            WindowEvent::Key(Key::F3, _, Action::Press, _) => player.toggle_camera(),
-            WindowEvent::Key(Key::F1, _, Action::Press, _) => *show_gui = !*show_gui,
-            // WindowEvent::Key(Key::F2, _, Action::Press, _) => {
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::PageUp), .. }, .. } if *chat_opened => chat.scroll(CHAT_PAGE),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::PageDown), .. }, .. } if *chat_opened => chat.scroll(-CHAT_PAGE),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(key), .. }, .. } if *chat_opened => {
+                chat_input.type_key(key, *shift_pressed, text_renderer);
+            },
+            WindowEvent::KeyboardInput { .. } if *chat_opened => (),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::T), .. }, .. } => open_chat(window, chat_opened, chat_input, mouse_captured, chat, ""),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Slash), .. }, .. } => open_chat(window, chat_opened, chat_input, mouse_captured, chat, "/"),
+            WindowEvent::Resized(PhysicalSize { width, height }) => {
+                unsafe { gl::Viewport(0, 0, width as i32, height as i32) }
+            },
+            WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(_, y_offset), .. } if *chat_opened => chat.scroll((y_offset * 3.0).round() as i32),
+            WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(_, y_offset), .. } => {
+                player.camera.scroll_callback(y_offset);
+            },
+            WindowEvent::CursorMoved { position: PhysicalPosition { x, y }, .. } => {
+                *last_x = x as f32;
+                *last_y = y as f32;
+            },
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::F3), .. }, .. } => player.toggle_camera(),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::F1), .. }, .. } => *show_gui = !*show_gui,
+            // WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::F2), .. }, .. } => {
             //     let width = SCR_WIDTH;
             //     let height = SCR_HEIGHT;
             //     let mut data = vec![0u8; (width * height * 4) as usize].into_boxed_slice();
@@ -774,14 +1233,14 @@ fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::Windo
             //     image.save("screenshot.png").expect("Unable to write image to file");
             //     println!("Saved screenshot");
             // },
-            WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
+            WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => {
                 if let Some(((x, y, z), _)) = selected_coords {
                     world.set_block(*x, *y, *z, BlockType::Air);
                     *force_recalculation = true;
                     //world.recalculate_mesh_from_perspective((camera.position.x as i32) % 16, (camera.position.z as i32) % 16);
                 }
             },
-            WindowEvent::MouseButton(MouseButton::Button2, Action::Press, _) => {
+            WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Right, .. } => {
                 if let Some(((x, y, z), Some(face))) = selected_coords {
                     let x = *x;
                     let y = *y;
@@ -791,49 +1250,94 @@ fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::Windo
                     let block = index_to_block(*current_block_index);
 
                     if !can_place_block_at_loc(player.camera.position, place_position.0, place_position.1, place_position.2) {
-                        return;
+                        continue;
                     }
                     world.set_block(place_position.0, place_position.1, place_position.2, block.unwrap());
                     *force_recalculation = true;
                 }
             }, 
-            WindowEvent::Key(Key::Space, _, Action::Press, _) => player.jump(),
-            WindowEvent::Key(Key::Up, _, Action::Press, _) => {
-                if index_to_block(*current_block_index + 1).is_some() {
-                    *current_block_index += 1
-                }
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Space), .. }, .. } => player.jump(),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Released, virtual_keycode: Some(VirtualKeyCode::Space), .. }, .. } => player.release_jump(),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Up), .. }, .. } if index_to_block(*current_block_index + 1).is_some() => {
+                *current_block_index += 1
             },
-            WindowEvent::Key(Key::Down, _, Action::Press, _) => if *current_block_index > 0 { *current_block_index -= 1 },
-            WindowEvent::Key(Key::LeftShift, _, Action::Press, _) => player.camera.speed = 0.05,
-            WindowEvent::Key(Key::LeftShift, _, Action::Release, _) => player.camera.speed = 0.008,
-            WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Down), .. }, .. } => *current_block_index = current_block_index.saturating_sub(1),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::LShift), .. }, .. } => player.camera.speed = 0.05,
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Released, virtual_keycode: Some(VirtualKeyCode::LShift), .. }, .. } => player.camera.speed = 0.008,
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::Escape), .. }, .. } => {
                 *current_block_index = 0;
                 *window_mode = WindowMode::Title;
-                window.set_cursor_mode(CursorMode::Normal);
+                set_cursor_captured(window, false);
                 fs::write(format!("{}/player_pos", world.save_dir).as_str(), format!("{} {} {}", player.camera.position.x, player.camera.position.y, player.camera.position.z))
                     .expect("Failed to write player position to file");
+                world.time.save(world.save_dir.as_str());
             },
-            WindowEvent::Key(Key::LeftSuper, _, Action::Press, _) => {
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::LWin), .. }, .. } => {
                 *mouse_captured = !*mouse_captured;
-                window.set_cursor_mode(match *mouse_captured {
-                    true => CursorMode::Disabled,
-                    false => CursorMode::Normal
-                });
+                set_cursor_captured(window, *mouse_captured);
             },
-            WindowEvent::Key(key, _, action, _) => player.camera.process_keyboard(key, action),
+            WindowEvent::KeyboardInput { input: KeyboardInput { state, virtual_keycode: Some(key), .. }, .. } => player.camera.process_keyboard(key, state),
             _ => ()
         }
     }
 }
 
-unsafe fn draw_block_selector(x: i32, y: i32, z: i32, face: Face, shader: &Shader, vbo: &VertexBuffer) {
-    let mut mesh = Vec::new();
-    mesh.push(x as f32);
-    mesh.push(y as f32);
-    mesh.push(z as f32);
-    for _ in 0..6 {
-        mesh.push(7.0);
+// usage of the command being typed, just above the chat input
+unsafe fn draw_command_usage(command_dispatcher: &CommandDispatcher, chat_input: &Input, text_renderer: &TextRenderer) {
+    if let Some(usage) = command_dispatcher.usage(chat_input.text.as_str()) {
+        text_renderer.render_text(usage.as_str(), 10.0, 52.0, 0.6, Vector3::new(0.8, 0.8, 0.8), TextJustification::Left);
+    }
+}
+
+// opens the chat input starting with the given text, freeing the cursor
+fn open_chat(window: &Window, chat_opened: &mut bool, chat_input: &mut Input, mouse_captured: &mut bool, chat: &mut ChatLog, text: &str) {
+    *mouse_captured = false;
+    *chat_opened = true;
+    set_cursor_captured(window, false);
+    chat_input.text = String::from(text);
+    chat_input.set_focus(true);
+    chat.scroll_to_bottom();
+    chat.stop_recalling();
+}
+
+fn close_chat(window: &Window, chat_opened: &mut bool, chat_input: &mut Input, shift_pressed: &mut bool, mouse_captured: &mut bool, chat: &mut ChatLog) {
+    *chat_opened = false;
+    *shift_pressed = false;
+    *mouse_captured = true;
+    set_cursor_captured(window, true);
+    chat_input.set_focus(false);
+    chat.scroll_to_bottom();
+}
+
+// grabs and hides the cursor for looking around, or frees it for
+// menus and chat. not every platform can grab it, so it's just hidden then
+fn set_cursor_captured(window: &Window, captured: bool) {
+    window.set_cursor_grab(captured).ok();
+    window.set_cursor_visible(!captured);
+}
+
+// what a command answered (or why it failed), a chat line per line
+fn push_command_output(chat: &mut ChatLog, output: Result<String, String>) {
+    let (kind, output) = match output {
+        Ok(output) => (ChatKind::System, output),
+        Err(error) => (ChatKind::Error, error)
     };
+    for line in output.lines() {
+        chat.push(kind, line);
+    }
+}
+
+// sun/moon light and sky colours for the voxel shader
+unsafe fn set_lighting_uniforms(shader: &Shader, lighting: &DayLighting) {
+    shader.set_vec3("light_dir", lighting.light_direction);
+    shader.set_vec3("light_color", lighting.light_color);
+    shader.set_float("ambient_strength", lighting.ambient_strength);
+    shader.set_vec3("sky_color", lighting.sky_color);
+}
+
+unsafe fn draw_block_selector(x: i32, y: i32, z: i32, face: Face, shader: &Shader, vbo: &VertexBuffer) {
+    let mut mesh = vec![x as f32, y as f32, z as f32];
+    mesh.extend_from_slice(&[7.0; 6]);
 
     let face_to_draw = match face {
         Face::Front     => 0b10000000,
@@ -844,12 +1348,94 @@ unsafe fn draw_block_selector(x: i32, y: i32, z: i32, face: Face, shader: &Shade
         Face::Top       => 0b00000100,
     };
     mesh.push(face_to_draw as f32);
+    mesh.push(1.0);
+    mesh.push(0.0);
+    mesh.push(0.0);
     vbo.set_data(&mesh, gl::DYNAMIC_DRAW);
 
     shader.set_mat4("model", Matrix4::from_scale(1.01));
     gl::DrawArrays(gl::POINTS, 0, 1);
 }
 
+// loads (or creates) a local world and moves the player
+// to their last saved position in it
+fn open_world(name: &str, player: &mut Player, settings: &Settings) -> World {
+    let mut world = World::new(settings.render_distance, name);
+    world.recalculate_mesh_from_perspective(0, 0);
+    let last_player_pos = fs::read_to_string(format!("game_data/worlds/{}/player_pos", name));
+    if let Ok(player_pos_str) = last_player_pos {
+        let words: Vec<&str> = player_pos_str.split(" ").collect();
+        let x = words[0].parse::<f32>();
+        let y = words[1].parse::<f32>();
+        let z = words[2].parse::<f32>();
+
+        if let Ok(x) = x {
+            player.camera.position.x = x;
+        }
+
+        if let Ok(y) = y {
+            player.camera.position.y = y;
+        }
+
+        if let Ok(z) = z {
+            player.camera.position.z = z;
+        }
+    } else {
+        player.camera.position = world.spawn_point();
+    }
+    world
+}
+
+// joins the server on a new connection, returning
+// it along with the state its listen thread updates
+fn join_server(mut connection: ServerConnection, player_name: &str, settings: &Settings) -> io::Result<(ServerConnection, ServerState)> {
+    // a trusted server has to prove it's the same one, which it
+    // can't do if authentication was left out of the handshake
+    if !connection.protocol.authentication() && TrustedServers::load(TRUSTED_SERVERS_PATH).knows(connection.address.as_str()) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} was trusted before but didn't ask to authenticate, remove it from {} if this is expected", connection.address, TRUSTED_SERVERS_PATH)
+        ))
+    }
+
+    let mut world = ServerWorld::new(settings.server_render_distance, connection.clone());
+    world.recalculate_mesh_from_perspective(0, 0);
+    let state = ServerState::new(Arc::new(Mutex::new(world)), connection.address.as_str());
+
+    // servers that authenticate players get the player's
+    // key before the name, see multiplayer::identity
+    let authentication = match connection.protocol.authentication() {
+        true => Some(Authentication::new(Identity::load_or_create()?)),
+        false => None
+    };
+    if let Some(authentication) = &authentication {
+        connection.send_message(authentication.hello())?;
+    }
+
+    connection.send_message(RustyCraftMessage::PlayerJoin { name: String::from(player_name) })?;
+    connection.clone().create_listen_thread(state.clone(), authentication);
+    Ok((connection, state))
+}
+
+// a failed send means the connection is gone, which
+// takes the player to the disconnected screen
+fn send_to_server(connection: &mut ServerConnection, state: &ServerState, message: RustyCraftMessage) {
+    if let Err(e) = connection.send_message(message) {
+        state.disconnect(format!("Failed to send to server ({})", e).as_str(), false);
+    }
+}
+
+// edits a block, shown straight away on servers that acknowledge
+// edits and otherwise once the server sends it back
+fn edit_server_block(connection: &mut ServerConnection, state: &ServerState, world_x: i32, world_y: i32, world_z: i32, block: BlockType) {
+    if connection.protocol.block_acks() {
+        let sequence = state.world.lock().unwrap().predict_block(world_x, world_y, world_z, block);
+        send_to_server(connection, state, RustyCraftMessage::BlockEdit { sequence, block, world_x, world_y, world_z });
+    } else {
+        send_to_server(connection, state, RustyCraftMessage::SetBlock { world_x, world_y, world_z, block });
+    }
+}
+
 fn can_place_block_at_loc(player_position: Vector3<f32>, x: i32, y: i32, z: i32) -> bool {
     x != player_position.x.round() as i32
         || (y != player_position.y.round() as i32
@@ -883,21 +1469,3 @@ fn get_subtitle_text() -> String {
     String::from(s)
 }
 
-unsafe fn render_clouds(cloud: &Cloud, camera: &Camera, player_position: &Vector3<f32>, z_offset: f32, simplex: OpenSimplex) {
-    let cloud_x = (player_position.x as i32) / 10;
-    let cloud_z = (player_position.z as i32) / 10;
-    for x in 0..LOCAL_RENDER_DISTANCE * 4 {
-        for z in 0..LOCAL_RENDER_DISTANCE * 4 {
-            let local_x = LOCAL_RENDER_DISTANCE as i32 * 2 - x as i32;
-            let local_z = LOCAL_RENDER_DISTANCE as i32 * 2 - z as i32;
-            let x = LOCAL_RENDER_DISTANCE as i32 * 2 - x as i32 + cloud_x;
-            let z = LOCAL_RENDER_DISTANCE as i32 * 2 - z as i32 + cloud_z - z_offset as i32 / 10;
-            if distance(0, 0, local_x, local_z) < 2.0 * LOCAL_RENDER_DISTANCE as f32 {
-                let noise = sample(x as f32 / 4.0, z as f32 / 4.0, simplex);
-                if noise > 0.5 {
-                    cloud.draw(camera, x as i32, 100, z as i32, z_offset);
-                }
-            }
-        } 
-    }
-}
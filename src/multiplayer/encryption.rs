@@ -0,0 +1,159 @@
+use std::{io::{self, ErrorKind, Read, Write}, net::TcpStream, sync::{Arc, Mutex}};
+
+use snow::{Builder, TransportState};
+
+use super::identity::{Trust, TrustedServers, fingerprint};
+
+// the server sends its static key during the handshake (the NX pattern),
+// clients have no static key of their own since players are identified
+// by multiplayer::identity instead
+const NOISE_PARAMS: &str = "Noise_NX_25519_ChaChaPoly_BLAKE2s";
+
+// Noise messages are at most 65535 bytes including the 16 byte tag
+const MAX_MESSAGE_SIZE: usize = 65535;
+const TAG_SIZE: usize = 16;
+const MAX_PLAINTEXT_SIZE: usize = MAX_MESSAGE_SIZE - TAG_SIZE;
+
+// static keys of servers connected to with encryption, one "address key"
+// per line, pinned the first time they're seen
+pub const PINNED_KEYS_PATH: &str = "game_data/pinned_keys";
+
+// the session's keys, shared by every clone of a connection
+// so messages are numbered in the order they're sent
+pub type Cipher = Arc<Mutex<TransportState>>;
+
+// runs the Noise handshake straight after the server agreed to encryption,
+// refusing the connection if the server's key isn't the one pinned for it.
+// The prologue is the handshake and ack lines exactly as they were sent,
+// so the handshake fails if either was changed on the way
+pub fn client_handshake(stream: &TcpStream, address: &str, prologue: &[u8]) -> io::Result<Cipher> {
+    let params = NOISE_PARAMS.parse().map_err(noise_error)?;
+    let mut noise = Builder::new(params).prologue(prologue).build_initiator().map_err(noise_error)?;
+    let mut buffer = vec![0; MAX_MESSAGE_SIZE];
+
+    // -> e
+    let length = noise.write_message(&[], &mut buffer).map_err(noise_error)?;
+    write_noise_message(stream, &buffer[..length])?;
+
+    // <- e, ee, s, es
+    let message = read_noise_message(stream)?
+        .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "server closed the connection during the encryption handshake"))?;
+    noise.read_message(&message, &mut buffer).map_err(noise_error)?;
+
+    let server_key = noise.get_remote_static()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "server sent no static key"))?
+        .to_vec();
+    let mut pinned_keys = TrustedServers::load(PINNED_KEYS_PATH);
+    match pinned_keys.check(address, &server_key) {
+        Trust::Known => (),
+        Trust::New => pinned_keys.trust(address, &server_key)?,
+        Trust::Changed => return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!("encryption key changed to {}, remove {} from {} if this is expected", fingerprint(&server_key), address, PINNED_KEYS_PATH)
+        ))
+    }
+
+    let transport = noise.into_transport_mode().map_err(noise_error)?;
+    Ok(Arc::new(Mutex::new(transport)))
+}
+
+// reads from the stream, decrypting it if the connection is encrypted
+pub struct TransportReader {
+    stream: TcpStream,
+    cipher: Option<Cipher>,
+    // decrypted bytes not read yet
+    plaintext: Vec<u8>,
+    position: usize
+}
+
+impl TransportReader {
+    pub fn new(stream: TcpStream, cipher: Option<Cipher>) -> TransportReader {
+        TransportReader { stream, cipher, plaintext: Vec::new(), position: 0 }
+    }
+}
+
+impl Read for TransportReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let cipher = match &self.cipher {
+            Some(cipher) => cipher,
+            None => return self.stream.read(buf)
+        };
+
+        if self.position == self.plaintext.len() {
+            let message = match read_noise_message(&self.stream)? {
+                Some(message) => message,
+                None => return Ok(0)
+            };
+
+            self.plaintext.resize(MAX_MESSAGE_SIZE, 0);
+            let length = cipher.lock().unwrap().read_message(&message, &mut self.plaintext).map_err(noise_error)?;
+            self.plaintext.truncate(length);
+            self.position = 0;
+        }
+
+        let length = buf.len().min(self.plaintext.len() - self.position);
+        buf[..length].copy_from_slice(&self.plaintext[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+// writes to the stream, encrypting it if the connection is encrypted
+pub struct TransportWriter {
+    stream: TcpStream,
+    cipher: Option<Cipher>
+}
+
+impl TransportWriter {
+    pub fn new(stream: TcpStream, cipher: Option<Cipher>) -> TransportWriter {
+        TransportWriter { stream, cipher }
+    }
+}
+
+impl Write for TransportWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let cipher = match &self.cipher {
+            Some(cipher) => cipher,
+            None => return self.stream.write(buf)
+        };
+
+        let length = buf.len().min(MAX_PLAINTEXT_SIZE);
+        let mut message = vec![0; length + TAG_SIZE];
+
+        // encrypted and written under the lock so messages
+        // reach the server in the order they were numbered
+        let mut cipher = cipher.lock().unwrap();
+        let message_length = cipher.write_message(&buf[..length], &mut message).map_err(noise_error)?;
+        write_noise_message(&self.stream, &message[..message_length])?;
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+// messages are sent with a 2 byte big endian length
+fn write_noise_message(mut stream: &TcpStream, message: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(message.len() + 2);
+    frame.extend_from_slice(&(message.len() as u16).to_be_bytes());
+    frame.extend_from_slice(message);
+    stream.write_all(&frame)
+}
+
+// None if the connection was closed between messages
+fn read_noise_message(mut stream: &TcpStream) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0; 2];
+    match stream.read(&mut length[..1])? {
+        0 => return Ok(None),
+        _ => stream.read_exact(&mut length[1..])?
+    }
+
+    let mut message = vec![0; u16::from_be_bytes(length) as usize];
+    stream.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+fn noise_error(error: impl ToString) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, error.to_string())
+}
@@ -0,0 +1,258 @@
+use std::{fs::{self, OpenOptions}, io::{self, ErrorKind, Write}, path::Path};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+
+use crate::utils::hex_utils::{from_hex, to_hex};
+
+use super::rc_message::RustyCraftMessage;
+
+const IDENTITY_PATH: &str = "game_data/identity";
+
+// identity keys of servers connected to before
+pub const TRUSTED_SERVERS_PATH: &str = "game_data/trusted_servers";
+
+// put before everything signed during a join so a signature
+// from one side can't be passed off as the other's
+const CLIENT_CONTEXT: &[u8] = b"rustycraft client auth";
+const SERVER_CONTEXT: &[u8] = b"rustycraft server auth";
+
+// the player's keypair, its public key is what servers recognise
+// returning players by, whatever name they join with
+pub struct Identity {
+    key: SigningKey
+}
+
+impl Identity {
+    // the secret key is kept as hex in game_data/identity,
+    // created the first time it's needed
+    pub fn load_or_create() -> io::Result<Identity> {
+        if let Ok(contents) = fs::read_to_string(IDENTITY_PATH) {
+            let bytes = from_hex(contents.as_str())
+                .filter(|bytes| bytes.len() == 32)
+                .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "game_data/identity is not a valid key"))?;
+            let mut secret = [0; 32];
+            secret.copy_from_slice(&bytes);
+            return Ok(Identity::from_secret(secret))
+        }
+
+        let mut secret = [0; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        fs::create_dir_all("game_data")?;
+
+        // written in full next to it before taking its place, so
+        // a failed write doesn't leave a broken key behind
+        let temp_path = format!("{}.tmp", IDENTITY_PATH);
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // only readable by the player, it's all it takes to join as them
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(temp_path.as_str())?;
+        file.write_all(to_hex(&secret).as_bytes())?;
+        file.sync_all()?;
+        fs::rename(temp_path.as_str(), IDENTITY_PATH)?;
+        Ok(Identity::from_secret(secret))
+    }
+
+    fn from_secret(secret: [u8; 32]) -> Identity {
+        Identity { key: SigningKey::from_bytes(&secret) }
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.key.verifying_key().to_bytes()
+    }
+}
+
+// the client's side of proving who it is to a server, and
+// checking the server is who it was last time
+pub struct Authentication {
+    identity: Identity,
+    nonce: [u8; 32]
+}
+
+impl Authentication {
+    pub fn new(identity: Identity) -> Authentication {
+        let mut nonce = [0; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        Authentication { identity, nonce }
+    }
+
+    // sent before PlayerJoin
+    pub fn hello(&self) -> RustyCraftMessage {
+        RustyCraftMessage::AuthHello { public_key: to_hex(&self.identity.public_key()), nonce: to_hex(&self.nonce) }
+    }
+
+    // checks the server signed the nonce from the hello with the key it
+    // sent, then signs its challenge in turn, giving the answer to send
+    pub fn answer(&self, server_key: &[u8], signature: &str, challenge: &str) -> Result<RustyCraftMessage, String> {
+        let key = key_from_bytes(server_key).ok_or("Server sent an invalid key")?;
+        let signature = from_hex(signature)
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or("Server sent an invalid signature")?;
+        let signed = [SERVER_CONTEXT, &self.nonce, &self.identity.public_key()].concat();
+        key.verify(&signed, &signature).map_err(|_| "Server failed to prove its identity")?;
+
+        let challenge = from_hex(challenge).ok_or("Server sent an invalid challenge")?;
+        let signed = [CLIENT_CONTEXT, &challenge, server_key].concat();
+        let signature = self.identity.key.sign(&signed);
+        Ok(RustyCraftMessage::AuthResponse { signature: to_hex(&signature.to_bytes()) })
+    }
+}
+
+fn key_from_bytes(bytes: &[u8]) -> Option<VerifyingKey> {
+    if bytes.len() != 32 {
+        return None
+    }
+
+    let mut key = [0; 32];
+    key.copy_from_slice(bytes);
+    VerifyingKey::from_bytes(&key).ok()
+}
+
+// short form of a key to show players
+pub fn fingerprint(key: &[u8]) -> String {
+    to_hex(&key[..key.len().min(8)])
+}
+
+pub enum Trust {
+    Known,
+    // first time connecting to the address
+    New,
+    // the address answered with a different key than last time
+    Changed
+}
+
+// keys of servers connected to before, one "address key" per line
+// (see TRUSTED_SERVERS_PATH and encryption::PINNED_KEYS_PATH),
+// trusted the first time they're seen
+pub struct TrustedServers {
+    path: String,
+    servers: Vec<(String, String)>
+}
+
+impl TrustedServers {
+    pub fn load(path: &str) -> TrustedServers {
+        let mut servers = Vec::new();
+        if let Ok(contents) = fs::read_to_string(path) {
+            for line in contents.lines() {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                if words.len() == 2 {
+                    servers.push((String::from(words[0]), String::from(words[1])));
+                }
+            }
+        }
+        TrustedServers { path: String::from(path), servers }
+    }
+
+    // whether a key has been trusted for the address
+    pub fn knows(&self, address: &str) -> bool {
+        self.servers.iter().any(|(server, _)| server == address)
+    }
+
+    pub fn check(&self, address: &str, key: &[u8]) -> Trust {
+        match self.servers.iter().find(|(server, _)| server == address) {
+            Some((_, trusted)) if *trusted == to_hex(key) => Trust::Known,
+            Some(_) => Trust::Changed,
+            None => Trust::New
+        }
+    }
+
+    pub fn trust(&mut self, address: &str, key: &[u8]) -> io::Result<()> {
+        self.servers.retain(|(server, _)| server != address);
+        self.servers.push((String::from(address), to_hex(key)));
+
+        let mut contents = String::new();
+        for (server, key) in self.servers.iter() {
+            contents.push_str(format!("{} {}\n", server, key).as_str());
+        }
+        if let Some(parent) = Path::new(self.path.as_str()).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(self.path.as_str(), contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+
+    use crate::{multiplayer::rc_message::RustyCraftMessage, utils::hex_utils::{from_hex, to_hex}};
+
+    use super::{Authentication, CLIENT_CONTEXT, Identity, SERVER_CONTEXT, Trust, TrustedServers};
+
+    // what a server does with the hello, signing the nonce
+    // and client's key with its own key
+    fn server_signature(server: &SigningKey, authentication: &Authentication) -> String {
+        let signed = [SERVER_CONTEXT, &authentication.nonce, &authentication.identity.public_key()].concat();
+        to_hex(&server.sign(&signed).to_bytes())
+    }
+
+    fn answer_signature(answer: RustyCraftMessage) -> Signature {
+        match answer {
+            RustyCraftMessage::AuthResponse { signature } => Signature::from_slice(&from_hex(signature.as_str()).unwrap()).unwrap(),
+            _ => panic!("expected an AuthResponse")
+        }
+    }
+
+    #[test]
+    fn answers_a_server_that_proves_its_identity() {
+        let server = SigningKey::from_bytes(&[1; 32]);
+        let server_key = server.verifying_key().to_bytes();
+        let authentication = Authentication::new(Identity::from_secret([2; 32]));
+        let challenge = [3; 32];
+
+        let answer = authentication.answer(&server_key, server_signature(&server, &authentication).as_str(), to_hex(&challenge).as_str()).unwrap();
+        let signature = answer_signature(answer);
+        let client_key = authentication.identity.key.verifying_key();
+        assert!(client_key.verify(&[CLIENT_CONTEXT, &challenge, &server_key].concat(), &signature).is_ok());
+        // only good for the challenge it answered
+        assert!(client_key.verify(&[CLIENT_CONTEXT, &[4; 32], &server_key].concat(), &signature).is_err());
+    }
+
+    #[test]
+    fn refuses_servers_signing_with_another_key() {
+        let server = SigningKey::from_bytes(&[1; 32]);
+        let impostor = SigningKey::from_bytes(&[5; 32]);
+        let authentication = Authentication::new(Identity::from_secret([2; 32]));
+
+        let signature = server_signature(&impostor, &authentication);
+        let answer = authentication.answer(&server.verifying_key().to_bytes(), signature.as_str(), to_hex(&[3; 32]).as_str());
+        assert!(answer.is_err());
+    }
+
+    #[test]
+    fn refuses_tampered_signatures() {
+        let server = SigningKey::from_bytes(&[1; 32]);
+        let authentication = Authentication::new(Identity::from_secret([2; 32]));
+
+        let mut signature = from_hex(server_signature(&server, &authentication).as_str()).unwrap();
+        signature[0] ^= 1;
+        let answer = authentication.answer(&server.verifying_key().to_bytes(), to_hex(&signature).as_str(), to_hex(&[3; 32]).as_str());
+        assert!(answer.is_err());
+    }
+
+    #[test]
+    fn trusts_on_first_use_then_spots_changed_keys() {
+        let path = env::temp_dir().join(format!("rustycraft_trusted_servers_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::remove_file(path).ok();
+
+        let mut servers = TrustedServers::load(path);
+        assert!(!servers.knows("localhost:25565"));
+        assert!(matches!(servers.check("localhost:25565", &[1; 32]), Trust::New));
+        servers.trust("localhost:25565", &[1; 32]).unwrap();
+
+        let servers = TrustedServers::load(path);
+        fs::remove_file(path).ok();
+        assert!(servers.knows("localhost:25565"));
+        assert!(matches!(servers.check("localhost:25565", &[1; 32]), Trust::Known));
+        assert!(matches!(servers.check("localhost:25565", &[2; 32]), Trust::Changed));
+        assert!(matches!(servers.check("otherhost:25565", &[1; 32]), Trust::New));
+    }
+}
@@ -0,0 +1,93 @@
+use std::{net::{SocketAddr, UdpSocket}, time::{Duration, Instant}};
+
+use serde::{Deserialize, Serialize};
+
+// servers on the local network listen for the query on this port
+// and answer the sender with a LanAnnouncement
+pub const LAN_DISCOVERY_PORT: u16 = 25567;
+const DISCOVERY_QUERY: &[u8] = b"rustycraft discover";
+
+const QUERY_INTERVAL: Duration = Duration::from_secs(3);
+
+// servers that stop answering are dropped from the list after this long
+const FORGET_AFTER: Duration = Duration::from_secs(10);
+
+// a server's answer to the query as JSON, the address
+// is taken from where the answer came from
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LanAnnouncement {
+    pub motd: String,
+    pub port: u16
+}
+
+#[derive(Debug, Clone)]
+pub struct LanServer {
+    pub motd: String,
+    pub address: String,
+    last_seen: Instant
+}
+
+// finds servers on the local network by broadcasting a query over UDP
+pub struct LanDiscovery {
+    // None if the socket couldn't be opened, in which
+    // case no LAN servers are ever found
+    socket: Option<UdpSocket>,
+    last_query: Option<Instant>,
+    pub servers: Vec<LanServer>
+}
+
+impl LanDiscovery {
+    pub fn new() -> LanDiscovery {
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .and_then(|socket| {
+                socket.set_broadcast(true)?;
+                socket.set_nonblocking(true)?;
+                Ok(socket)
+            })
+            .map_err(|error| println!("LAN discovery unavailable: {}", error))
+            .ok();
+        LanDiscovery { socket, last_query: None, servers: Vec::new() }
+    }
+
+    // queries again every few seconds and reads any answers,
+    // returns true if a server was found, changed or lost
+    pub fn poll(&mut self) -> bool {
+        let socket = match &self.socket {
+            Some(socket) => socket,
+            None => return false
+        };
+
+        if self.last_query.is_none_or(|last_query| last_query.elapsed() >= QUERY_INTERVAL) {
+            socket.send_to(DISCOVERY_QUERY, ("255.255.255.255", LAN_DISCOVERY_PORT)).ok();
+            self.last_query = Some(Instant::now());
+        }
+
+        let mut changed = false;
+        let mut buffer = [0; 1024];
+        while let Ok((length, from)) = socket.recv_from(&mut buffer) {
+            let announcement: LanAnnouncement = match serde_json::from_slice(&buffer[..length]) {
+                Ok(announcement) => announcement,
+                Err(_) => continue
+            };
+
+            let address = SocketAddr::new(from.ip(), announcement.port).to_string();
+            match self.servers.iter_mut().find(|server| server.address == address) {
+                Some(server) => {
+                    server.last_seen = Instant::now();
+                    if server.motd != announcement.motd {
+                        server.motd = announcement.motd;
+                        changed = true;
+                    }
+                },
+                None => {
+                    self.servers.push(LanServer { motd: announcement.motd, address, last_seen: Instant::now() });
+                    changed = true;
+                }
+            }
+        }
+
+        let found = self.servers.len();
+        self.servers.retain(|server| server.last_seen.elapsed() < FORGET_AFTER);
+        changed || self.servers.len() != found
+    }
+}
@@ -5,4 +5,9 @@ pub mod server_connection;
 pub mod server_world;
 pub mod server_chunk;
 pub mod server_state;
-pub mod server_player;
\ No newline at end of file
+pub mod protocol;
+pub mod reconnect;
+pub mod identity;
+pub mod encryption;
+pub mod server_info;
+pub mod lan_discovery;
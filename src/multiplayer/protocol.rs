@@ -0,0 +1,616 @@
+use std::io::{self, BufRead, Error, ErrorKind, Read};
+
+use crate::{core::block_type::{BlockType, index_to_block}, utils::chunk_utils::{run_length_decode, run_length_encode}};
+
+use super::{direction::Direction, event::RustyCraftEvent, rc_message::RustyCraftMessage};
+
+// version of the binary protocol, exchanged in the handshake
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// capabilities a client or server can advertise in the handshake,
+// only those both sides have are used
+pub const CAPABILITY_COMPRESSION: u32 = 1;
+pub const CAPABILITY_KEEPALIVE: u32 = 2;
+pub const CAPABILITY_BLOCK_ACKS: u32 = 4;
+pub const CAPABILITY_AUTHENTICATION: u32 = 8;
+pub const CAPABILITY_ENCRYPTION: u32 = 16;
+pub const CAPABILITY_STATUS: u32 = 32;
+pub const CAPABILITIES: u32 = CAPABILITY_COMPRESSION | CAPABILITY_KEEPALIVE | CAPABILITY_BLOCK_ACKS | CAPABILITY_AUTHENTICATION | CAPABILITY_ENCRYPTION | CAPABILITY_STATUS;
+
+// frames bigger than this are refused rather than allocated
+const MAX_FRAME_SIZE: u64 = 16 * 1024 * 1024;
+
+// how messages are written to and read from a server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    // a serde_json RustyCraftMessage/RustyCraftEvent per line, spoken
+    // by servers from before the handshake
+    Json,
+    // frames of a varint length then the encoded message, with
+    // serialized chunks run length encoded if compression is on,
+    // pings only sent to servers that know to answer them, block
+    // edits only predicted on servers that acknowledge them and
+    // players only authenticated by servers that ask, everything
+    // after the handshake encrypted if both sides can (see
+    // multiplayer::encryption) and the server list only asking
+    // for the status of servers that answer it
+    Binary { compression: bool, keepalive: bool, block_acks: bool, authentication: bool, encryption: bool, status: bool }
+}
+
+impl Protocol {
+    // protocol to use from the server's answer to the handshake
+    pub fn negotiate(version: u32, capabilities: u32) -> Protocol {
+        if version.min(PROTOCOL_VERSION) < 1 {
+            return Protocol::Json
+        }
+
+        let shared = capabilities & CAPABILITIES;
+        Protocol::Binary {
+            compression: shared & CAPABILITY_COMPRESSION != 0,
+            keepalive: shared & CAPABILITY_KEEPALIVE != 0,
+            block_acks: shared & CAPABILITY_BLOCK_ACKS != 0,
+            authentication: shared & CAPABILITY_AUTHENTICATION != 0,
+            encryption: shared & CAPABILITY_ENCRYPTION != 0,
+            status: shared & CAPABILITY_STATUS != 0
+        }
+    }
+
+    pub fn keepalive(&self) -> bool {
+        match self {
+            Protocol::Json => false,
+            Protocol::Binary { keepalive, .. } => *keepalive
+        }
+    }
+
+    pub fn block_acks(&self) -> bool {
+        match self {
+            Protocol::Json => false,
+            Protocol::Binary { block_acks, .. } => *block_acks
+        }
+    }
+
+    pub fn authentication(&self) -> bool {
+        match self {
+            Protocol::Json => false,
+            Protocol::Binary { authentication, .. } => *authentication
+        }
+    }
+
+    pub fn encryption(&self) -> bool {
+        match self {
+            Protocol::Json => false,
+            Protocol::Binary { encryption, .. } => *encryption
+        }
+    }
+
+    pub fn status(&self) -> bool {
+        match self {
+            Protocol::Json => false,
+            Protocol::Binary { status, .. } => *status
+        }
+    }
+}
+
+// a message as a JSON line (without the newline), a Disconnect
+// is sent as the bare variant servers from before reasons expect
+pub fn to_json_line(message: &RustyCraftMessage) -> String {
+    match message {
+        RustyCraftMessage::Disconnect { .. } => String::from("\"Disconnect\""),
+        message => serde_json::to_string(message).unwrap()
+    }
+}
+
+// an event from a JSON line, accepting the bare Disconnect
+// sent by servers from before reasons
+pub fn event_from_json_line(line: &str) -> serde_json::Result<RustyCraftEvent> {
+    let mut value: serde_json::Value = serde_json::from_str(line)?;
+    if value["message"] == "Disconnect" {
+        value["message"] = serde_json::json!({ "Disconnect": {} });
+    }
+    serde_json::from_value(value)
+}
+
+// a message as a binary frame, ready to be written
+pub fn encode_frame(message: &RustyCraftMessage, compression: bool) -> Vec<u8> {
+    let mut payload = Vec::new();
+    write_message(&mut payload, message, compression);
+
+    let mut frame = Vec::with_capacity(payload.len() + 4);
+    write_varint(&mut frame, payload.len() as u64);
+    frame.extend_from_slice(&payload);
+    frame
+}
+
+// reads the next binary frame from the server, None if
+// the connection was closed between frames
+pub fn read_event_frame<R: BufRead>(reader: &mut R, compression: bool) -> io::Result<Option<RustyCraftEvent>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None)
+    }
+
+    let length = read_varint(reader)?;
+    if length > MAX_FRAME_SIZE {
+        return Err(invalid_data("frame too large"))
+    }
+
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload)?;
+
+    let mut cursor = payload.as_slice();
+    let sender = read_string(&mut cursor)?;
+    let message = read_message(&mut cursor, compression)?;
+    Ok(Some(RustyCraftEvent { sender, message }))
+}
+
+// each message is a tag byte followed by its fields in order
+fn write_message(out: &mut Vec<u8>, message: &RustyCraftMessage, compression: bool) {
+    match message {
+        RustyCraftMessage::Movement { direction } => {
+            out.push(0);
+            out.push(match direction {
+                Direction::Right => 0,
+                Direction::Left => 1,
+                Direction::Forward => 2,
+                Direction::Backward => 3
+            });
+        },
+        RustyCraftMessage::PlayerJoin { name } => {
+            out.push(1);
+            write_string(out, name);
+        },
+        RustyCraftMessage::PlayerInit { name, x, y, z } => {
+            out.push(2);
+            write_string(out, name);
+            write_f32s(out, &[*x, *y, *z]);
+        },
+        RustyCraftMessage::PlayerDirection { yaw, pitch, time } => {
+            out.push(3);
+            write_f32s(out, &[*yaw, *pitch]);
+            write_varint(out, *time);
+        },
+        RustyCraftMessage::PlayerPosition { x, y, z, time } => {
+            out.push(4);
+            write_f32s(out, &[*x, *y, *z]);
+            write_varint(out, *time);
+        },
+        RustyCraftMessage::SetBlock { block, world_x, world_y, world_z } => {
+            out.push(5);
+            out.push(*block as u8);
+            write_coords(out, &[*world_x, *world_y, *world_z]);
+        },
+        RustyCraftMessage::GetChunks { coords } => {
+            out.push(6);
+            write_varint(out, coords.len() as u64);
+            for (x, z) in coords.iter() {
+                write_coords(out, &[*x, *z]);
+            }
+        },
+        RustyCraftMessage::ChatMessage { content } => {
+            out.push(7);
+            write_string(out, content);
+        },
+        RustyCraftMessage::TimeSync { time } => {
+            out.push(8);
+            write_varint(out, *time);
+        },
+        RustyCraftMessage::FallingBlockSpawn { id, block, world_x, world_y, world_z } => {
+            out.push(9);
+            write_varint(out, *id as u64);
+            out.push(*block as u8);
+            write_coords(out, &[*world_x, *world_y, *world_z]);
+        },
+        RustyCraftMessage::FallingBlockLand { id, block, world_x, world_y, world_z } => {
+            out.push(10);
+            write_varint(out, *id as u64);
+            out.push(*block as u8);
+            write_coords(out, &[*world_x, *world_y, *world_z]);
+        },
+        RustyCraftMessage::ConnectionData { id, players } => {
+            out.push(11);
+            write_string(out, id);
+            write_varint(out, players.len() as u64);
+            for (id, name, x, y, z, yaw, pitch) in players.iter() {
+                write_string(out, id);
+                write_string(out, name);
+                write_f32s(out, &[*x, *y, *z, *yaw, *pitch]);
+            }
+        },
+        RustyCraftMessage::ChunkData { chunks } => {
+            out.push(12);
+            write_varint(out, chunks.len() as u64);
+            for (x, z, serialized) in chunks.iter() {
+                write_coords(out, &[*x, *z]);
+                if compression {
+                    write_bytes(out, &run_length_encode(serialized.as_bytes()));
+                } else {
+                    write_bytes(out, serialized.as_bytes());
+                }
+            }
+        },
+        RustyCraftMessage::Handshake { version, capabilities } => {
+            out.push(13);
+            write_varint(out, *version as u64);
+            write_varint(out, *capabilities as u64);
+        },
+        RustyCraftMessage::HandshakeAck { version, capabilities } => {
+            out.push(14);
+            write_varint(out, *version as u64);
+            write_varint(out, *capabilities as u64);
+        },
+        RustyCraftMessage::Disconnect { reason } => {
+            out.push(15);
+            write_string(out, reason);
+        },
+        RustyCraftMessage::Ping { time } => {
+            out.push(16);
+            write_varint(out, *time);
+        },
+        RustyCraftMessage::Pong { time } => {
+            out.push(17);
+            write_varint(out, *time);
+        },
+        RustyCraftMessage::BlockEdit { sequence, block, world_x, world_y, world_z } => {
+            out.push(18);
+            write_varint(out, *sequence as u64);
+            out.push(*block as u8);
+            write_coords(out, &[*world_x, *world_y, *world_z]);
+        },
+        RustyCraftMessage::BlockEditResult { sequence, accepted } => {
+            out.push(19);
+            write_varint(out, *sequence as u64);
+            out.push(*accepted as u8);
+        },
+        RustyCraftMessage::AuthHello { public_key, nonce } => {
+            out.push(20);
+            write_string(out, public_key);
+            write_string(out, nonce);
+        },
+        RustyCraftMessage::AuthChallenge { server_key, signature, challenge } => {
+            out.push(21);
+            write_string(out, server_key);
+            write_string(out, signature);
+            write_string(out, challenge);
+        },
+        RustyCraftMessage::AuthResponse { signature } => {
+            out.push(22);
+            write_string(out, signature);
+        },
+        RustyCraftMessage::StatusRequest => out.push(23),
+        RustyCraftMessage::StatusResponse { motd, players, max_players, version } => {
+            out.push(24);
+            write_string(out, motd);
+            write_varint(out, *players as u64);
+            write_varint(out, *max_players as u64);
+            write_string(out, version);
+        }
+    }
+}
+
+fn read_message(input: &mut &[u8], compression: bool) -> io::Result<RustyCraftMessage> {
+    let message = match read_u8(input)? {
+        0 => RustyCraftMessage::Movement {
+            direction: match read_u8(input)? {
+                0 => Direction::Right,
+                1 => Direction::Left,
+                2 => Direction::Forward,
+                3 => Direction::Backward,
+                _ => return Err(invalid_data("invalid direction"))
+            }
+        },
+        1 => RustyCraftMessage::PlayerJoin { name: read_string(input)? },
+        2 => {
+            let name = read_string(input)?;
+            RustyCraftMessage::PlayerInit { name, x: read_f32(input)?, y: read_f32(input)?, z: read_f32(input)? }
+        },
+        3 => {
+            let (yaw, pitch) = (read_f32(input)?, read_f32(input)?);
+            RustyCraftMessage::PlayerDirection { yaw, pitch, time: read_time(input)? }
+        },
+        4 => {
+            let (x, y, z) = (read_f32(input)?, read_f32(input)?, read_f32(input)?);
+            RustyCraftMessage::PlayerPosition { x, y, z, time: read_time(input)? }
+        },
+        5 => {
+            let block = read_block(input)?;
+            RustyCraftMessage::SetBlock { block, world_x: read_coord(input)?, world_y: read_coord(input)?, world_z: read_coord(input)? }
+        },
+        6 => {
+            let count = read_varint(input)?;
+            let mut coords = Vec::new();
+            for _ in 0..count {
+                coords.push((read_coord(input)?, read_coord(input)?));
+            }
+            RustyCraftMessage::GetChunks { coords }
+        },
+        7 => RustyCraftMessage::ChatMessage { content: read_string(input)? },
+        8 => RustyCraftMessage::TimeSync { time: read_varint(input)? },
+        tag @ 9 | tag @ 10 => {
+            let id = read_varint(input)? as u32;
+            let block = read_block(input)?;
+            let (world_x, world_y, world_z) = (read_coord(input)?, read_coord(input)?, read_coord(input)?);
+            if tag == 9 {
+                RustyCraftMessage::FallingBlockSpawn { id, block, world_x, world_y, world_z }
+            } else {
+                RustyCraftMessage::FallingBlockLand { id, block, world_x, world_y, world_z }
+            }
+        },
+        11 => {
+            let id = read_string(input)?;
+            let count = read_varint(input)?;
+            let mut players = Vec::new();
+            for _ in 0..count {
+                let id = read_string(input)?;
+                let name = read_string(input)?;
+                let (x, y, z) = (read_f32(input)?, read_f32(input)?, read_f32(input)?);
+                let (yaw, pitch) = (read_f32(input)?, read_f32(input)?);
+                players.push((id, name, x, y, z, yaw, pitch));
+            }
+            RustyCraftMessage::ConnectionData { id, players }
+        },
+        12 => {
+            let count = read_varint(input)?;
+            let mut chunks = Vec::new();
+            for _ in 0..count {
+                let (x, z) = (read_coord(input)?, read_coord(input)?);
+                let bytes = read_bytes(input)?;
+                let bytes = if compression {
+                    run_length_decode(&bytes)?
+                } else {
+                    bytes
+                };
+                let serialized = String::from_utf8(bytes).map_err(|_| invalid_data("invalid chunk"))?;
+                chunks.push((x, z, serialized));
+            }
+            RustyCraftMessage::ChunkData { chunks }
+        },
+        tag @ 13 | tag @ 14 => {
+            let version = read_varint(input)? as u32;
+            let capabilities = read_varint(input)? as u32;
+            if tag == 13 {
+                RustyCraftMessage::Handshake { version, capabilities }
+            } else {
+                RustyCraftMessage::HandshakeAck { version, capabilities }
+            }
+        },
+        // servers from before reasons send nothing after the tag
+        15 => RustyCraftMessage::Disconnect { reason: if !input.is_empty() { read_string(input)? } else { String::new() } },
+        16 => RustyCraftMessage::Ping { time: read_varint(input)? },
+        17 => RustyCraftMessage::Pong { time: read_varint(input)? },
+        18 => {
+            let sequence = read_varint(input)? as u32;
+            let block = read_block(input)?;
+            RustyCraftMessage::BlockEdit { sequence, block, world_x: read_coord(input)?, world_y: read_coord(input)?, world_z: read_coord(input)? }
+        },
+        19 => RustyCraftMessage::BlockEditResult { sequence: read_varint(input)? as u32, accepted: read_u8(input)? != 0 },
+        20 => RustyCraftMessage::AuthHello { public_key: read_string(input)?, nonce: read_string(input)? },
+        21 => RustyCraftMessage::AuthChallenge { server_key: read_string(input)?, signature: read_string(input)?, challenge: read_string(input)? },
+        22 => RustyCraftMessage::AuthResponse { signature: read_string(input)? },
+        23 => RustyCraftMessage::StatusRequest,
+        24 => RustyCraftMessage::StatusResponse {
+            motd: read_string(input)?,
+            players: read_varint(input)? as u32,
+            max_players: read_varint(input)? as u32,
+            version: read_string(input)?
+        },
+        _ => return Err(invalid_data("unknown message"))
+    };
+    Ok(message)
+}
+
+// unsigned LEB128, 7 bits per byte with the high bit set on all but the last
+pub fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub fn read_varint<R: Read>(input: &mut R) -> io::Result<u64> {
+    let mut value = 0;
+    for i in 0..10 {
+        let mut byte = [0];
+        input.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << (i * 7);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value)
+        }
+    }
+    Err(invalid_data("varint too long"))
+}
+
+// signed coords are zigzag encoded so small negatives stay small
+fn write_coords(out: &mut Vec<u8>, coords: &[i32]) {
+    for coord in coords.iter() {
+        write_varint(out, ((coord << 1) ^ (coord >> 31)) as u32 as u64);
+    }
+}
+
+fn read_coord(input: &mut &[u8]) -> io::Result<i32> {
+    let zigzag = read_varint(input)? as u32;
+    Ok((zigzag >> 1) as i32 ^ -((zigzag & 1) as i32))
+}
+
+fn write_f32s(out: &mut Vec<u8>, values: &[f32]) {
+    for value in values.iter() {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn read_f32(input: &mut &[u8]) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn read_bytes(input: &mut &[u8]) -> io::Result<Vec<u8>> {
+    let length = read_varint(input)? as usize;
+    if length > input.len() {
+        return Err(invalid_data("length past end of frame"))
+    }
+
+    let (bytes, rest) = input.split_at(length);
+    *input = rest;
+    Ok(bytes.to_vec())
+}
+
+fn write_string(out: &mut Vec<u8>, string: &str) {
+    write_bytes(out, string.as_bytes());
+}
+
+fn read_string(input: &mut &[u8]) -> io::Result<String> {
+    String::from_utf8(read_bytes(input)?).map_err(|_| invalid_data("invalid string"))
+}
+
+// timestamp at the end of a movement message, missing
+// from those sent by clients from before timestamps
+fn read_time(input: &mut &[u8]) -> io::Result<u64> {
+    match input.len() {
+        0 => Ok(0),
+        _ => read_varint(input)
+    }
+}
+
+fn read_u8(input: &mut &[u8]) -> io::Result<u8> {
+    let mut byte = [0];
+    input.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_block(input: &mut &[u8]) -> io::Result<BlockType> {
+    index_to_block(read_u8(input)? as usize).ok_or_else(|| invalid_data("invalid block"))
+}
+
+fn invalid_data(reason: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::{core::block_type::BlockType, multiplayer::rc_message::RustyCraftMessage};
+
+    use super::{MAX_FRAME_SIZE, read_event_frame, read_varint, write_bytes, write_coords, write_message, write_string, write_varint};
+
+    // a frame as the server sends it, with the sender before the message
+    fn event_frame(sender: &str, message: &RustyCraftMessage, compression: bool) -> Vec<u8> {
+        let mut payload = Vec::new();
+        write_string(&mut payload, sender);
+        write_message(&mut payload, message, compression);
+        frame_of(&payload)
+    }
+
+    fn frame_of(payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        write_varint(&mut frame, payload.len() as u64);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn frame_error(frame: &[u8], compression: bool) -> ErrorKind {
+        let mut reader = frame;
+        read_event_frame(&mut reader, compression).unwrap_err().kind()
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX].iter() {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, *value);
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn varints_use_seven_bits_per_byte() {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, 300);
+        assert_eq!(bytes, vec![0xac, 0x02]);
+    }
+
+    #[test]
+    fn refuses_varints_too_long() {
+        let bytes = [0x80; 11];
+        assert_eq!(read_varint(&mut &bytes[..]).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn refuses_truncated_varints() {
+        let bytes = [0x80, 0x80];
+        assert_eq!(read_varint(&mut &bytes[..]).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let messages = [
+            RustyCraftMessage::ChatMessage { content: String::from("hello") },
+            RustyCraftMessage::SetBlock { block: BlockType::Stone, world_x: -5, world_y: 64, world_z: 1 << 20 },
+            RustyCraftMessage::ChunkData { chunks: vec![(-1, 2, String::from_utf8(vec![1; 600]).unwrap())] },
+            RustyCraftMessage::Ping { time: 123456789 }
+        ];
+        for compression in [false, true].iter() {
+            for message in messages.iter() {
+                let frame = event_frame("player", message, *compression);
+                let event = read_event_frame(&mut frame.as_slice(), *compression).unwrap().unwrap();
+                assert_eq!(event.sender, "player");
+                assert_eq!(format!("{:?}", event.message), format!("{:?}", message));
+            }
+        }
+    }
+
+    #[test]
+    fn reads_nothing_once_closed() {
+        let mut empty: &[u8] = &[];
+        assert!(read_event_frame(&mut empty, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn refuses_frames_too_large() {
+        let mut frame = Vec::new();
+        write_varint(&mut frame, MAX_FRAME_SIZE + 1);
+        assert_eq!(frame_error(&frame, false), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn refuses_truncated_frames() {
+        let frame = event_frame("player", &RustyCraftMessage::Ping { time: 1 }, false);
+        assert_eq!(frame_error(&frame[..frame.len() - 1], false), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn refuses_unknown_messages() {
+        let mut payload = Vec::new();
+        write_string(&mut payload, "player");
+        payload.push(200);
+        assert_eq!(frame_error(&frame_of(&payload), false), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn refuses_lengths_past_the_frame() {
+        let mut payload = Vec::new();
+        write_varint(&mut payload, 100);
+        payload.extend_from_slice(b"pl");
+        assert_eq!(frame_error(&frame_of(&payload), false), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn refuses_chunks_decoding_past_a_chunk() {
+        let mut payload = Vec::new();
+        write_string(&mut payload, "player");
+        payload.push(12);
+        write_varint(&mut payload, 1);
+        write_coords(&mut payload, &[0, 0]);
+        write_bytes(&mut payload, &[255, 1].repeat(100_000));
+        assert_eq!(frame_error(&frame_of(&payload), true), ErrorKind::InvalidData);
+    }
+}
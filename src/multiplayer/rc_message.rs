@@ -7,12 +7,23 @@ pub enum RustyCraftMessage {
     Movement { direction: Direction },
     PlayerJoin { name: String },
     PlayerInit { name: String, x: f32, y: f32, z: f32 },
-    PlayerDirection { yaw: f32, pitch: f32 },
-    PlayerPosition { x: f32, y: f32, z: f32 },
+    // time is when the sender sent it in its own ms, so other
+    // clients can space out the updates they interpolate between
+    PlayerDirection { yaw: f32, pitch: f32, #[serde(default)] time: u64 },
+    PlayerPosition { x: f32, y: f32, z: f32, #[serde(default)] time: u64 },
     SetBlock { block: BlockType, world_x: i32, world_y: i32, world_z: i32 },
     GetChunks { coords: Vec<(i32, i32)> },
     ChatMessage { content: String },
 
+    // server's world time in ticks, sent on join and periodically
+    // after, clients advance time themselves between syncs
+    TimeSync { time: u64 },
+
+    // a block lost its support and started falling, clients remove
+    // it and simulate the fall locally until the server says it landed
+    FallingBlockSpawn { id: u32, block: BlockType, world_x: i32, world_y: i32, world_z: i32 },
+    FallingBlockLand { id: u32, block: BlockType, world_x: i32, world_y: i32, world_z: i32 },
+
     // echo connection and players id to client
     // to avoid rendering own model and get data
     // for all players currently on the server
@@ -21,5 +32,40 @@ pub enum RustyCraftMessage {
     // serialized chunk_blocks in the form of Vec<(usize, usize, usize, usize)>
     // stored as string so serialized chunk blocks can be memoized
     ChunkData { chunks: Vec<(i32, i32, String)> },
-    Disconnect
-}
\ No newline at end of file
+
+    // always sent as JSON lines, before anything else, servers that
+    // answer with an ack switch to binary frames (see multiplayer::protocol)
+    Handshake { version: u32, capabilities: u32 },
+    HandshakeAck { version: u32, capabilities: u32 },
+
+    // sent by a player leaving, or by the server (with no sender or the
+    // client's own id) to a client it's dropping, with the reason shown
+    // on the disconnected screen
+    Disconnect { #[serde(default)] reason: String },
+
+    // keepalive, answered with a Pong carrying the same time so the
+    // sender can work out the round trip
+    Ping { time: u64 },
+    Pong { time: u64 },
+
+    // a SetBlock the client has already applied locally, only sent to
+    // servers that answer with a result for the same sequence number,
+    // after which the client keeps or rolls back the edit
+    BlockEdit { sequence: u32, block: BlockType, world_x: i32, world_y: i32, world_z: i32 },
+    BlockEditResult { sequence: u32, accepted: bool },
+
+    // join handshake on servers that authenticate players (see
+    // multiplayer::identity), keys, nonces and signatures are hex:
+    // the client sends its public key and a nonce before PlayerJoin,
+    // the server answers with its key, a signature of the nonce and a
+    // challenge, which the client signs back
+    AuthHello { public_key: String, nonce: String },
+    AuthChallenge { server_key: String, signature: String, challenge: String },
+    AuthResponse { signature: String },
+
+    // asked on a fresh connection instead of PlayerJoin by the server
+    // list, answered without joining, only sent to servers that
+    // advertise it in the handshake
+    StatusRequest,
+    StatusResponse { motd: String, players: u32, max_players: u32, version: String }
+}
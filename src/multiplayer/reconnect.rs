@@ -0,0 +1,105 @@
+use std::{io, sync::mpsc::{self, Receiver, TryRecvError}, thread, time::{Duration, Instant}};
+
+use super::{server_connection::ServerConnection, server_state::Disconnection};
+
+// wait before the first attempt, doubled after every failed one
+const FIRST_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+// reconnects to a server after losing the connection, each attempt
+// is made on its own thread so the disconnected screen keeps drawing
+pub struct Reconnect {
+    pub address: String,
+    pub reason: String,
+    // only connections that were lost are retried on their own,
+    // otherwise it waits for the player to ask
+    automatic: bool,
+    attempts: u32,
+    next_attempt: Option<Instant>,
+    pending: Option<Receiver<io::Result<ServerConnection>>>,
+    last_error: Option<String>
+}
+
+impl Reconnect {
+    pub fn new(address: String, disconnection: Disconnection) -> Reconnect {
+        let automatic = !disconnection.by_server;
+        Reconnect {
+            address,
+            reason: disconnection.reason,
+            automatic,
+            attempts: 0,
+            next_attempt: if automatic { Some(Instant::now() + FIRST_DELAY) } else { None },
+            pending: None,
+            last_error: None
+        }
+    }
+
+    // tries again straight away, from the Reconnect button
+    pub fn retry_now(&mut self) {
+        if self.pending.is_none() {
+            self.next_attempt = Some(Instant::now());
+        }
+    }
+
+    // starts an attempt when one is due, returning
+    // the connection once one succeeds
+    pub fn poll(&mut self) -> Option<ServerConnection> {
+        if let Some(pending) = &self.pending {
+            let result = match pending.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => Err(io::Error::other("connection attempt failed"))
+            };
+            self.pending = None;
+
+            match result {
+                Ok(connection) => return Some(connection),
+                Err(error) => {
+                    self.last_error = Some(error.to_string());
+                    self.attempts += 1;
+                    self.next_attempt = if self.automatic { Some(Instant::now() + self.delay()) } else { None };
+                }
+            }
+        }
+
+        match self.next_attempt {
+            Some(next_attempt) if Instant::now() >= next_attempt => {
+                let (sender, receiver) = mpsc::channel();
+                let address = self.address.clone();
+                thread::spawn(move || {
+                    sender.send(ServerConnection::new(address)).ok();
+                });
+                self.pending = Some(receiver);
+                self.next_attempt = None;
+            },
+            _ => ()
+        }
+        None
+    }
+
+    // line shown under the reason on the disconnected screen
+    pub fn status(&self) -> String {
+        let last_error = match &self.last_error {
+            Some(error) => format!(" (last attempt: {})", error),
+            None => String::new()
+        };
+
+        if self.pending.is_some() {
+            return format!("Reconnecting to {}...", self.address)
+        }
+
+        match self.next_attempt {
+            Some(next_attempt) => {
+                let seconds = next_attempt.saturating_duration_since(Instant::now()).as_secs() + 1;
+                format!("Reconnecting in {}s{}", seconds, last_error)
+            },
+            None if self.attempts > 0 => format!("Failed to reconnect{}", last_error),
+            None => String::new()
+        }
+    }
+
+    fn delay(&self) -> Duration {
+        let delay = FIRST_DELAY * 2u32.saturating_pow(self.attempts.min(16));
+        delay.min(MAX_DELAY)
+    }
+}
@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 use std::sync::Arc;
-use crate::{core::{block_map::BlockMap, block_type::{BlockType, block_to_uv}, face::Face}, traits::game_chunk::GameChunk, utils::chunk_utils::from_serialized};
+use crate::{core::{ambient_occlusion::block_occlusion, block_map::BlockMap, block_type::{BlockType, block_to_uv}, chunk::CHUNK_HEIGHT, chunk_mesh::ChunkMesh, face::Face, fluid::WATER_SURFACE_HEIGHT}, traits::game_chunk::GameChunk, utils::chunk_utils::from_serialized};
 
 #[derive(Clone)]
 pub struct ServerChunk {
@@ -8,7 +8,7 @@ pub struct ServerChunk {
     blocks_in_mesh: Vec<(usize, usize, usize)>,
     x: i32,
     z: i32,
-    pub mesh: Arc<(Vec<f32>, Vec<f32>)> // cache mesh (Arc to be thread-safe)
+    pub mesh: Arc<ChunkMesh> // cache mesh (Arc to be thread-safe)
 }
 
 impl GameChunk for ServerChunk {
@@ -24,14 +24,11 @@ impl ServerChunk {
         // [x1] [y1] [z1] [block_index1] 
         // ...
         let (blocks_in_mesh, blocks) = from_serialized(&chunk_data);
-        ServerChunk { blocks, blocks_in_mesh, x: x * 16, z: z * 16, mesh: Arc::new((vec![], vec![])) }
+        ServerChunk { blocks, blocks_in_mesh, x: x * 16, z: z * 16, mesh: Arc::new(ChunkMesh::new(x * 16, z * 16)) }
     }
 
-    pub fn gen_mesh(&self, right_chunk: &ServerChunk, left_chunk: &ServerChunk, front_chunk: &ServerChunk, back_chunk: &ServerChunk) -> Arc<(Vec<f32>, Vec<f32>)> {
-        let mut vertices = Vec::new();
-        // water is transparent so is in separate
-        // vector to draw after opaque blocks
-        let mut water_vertices = Vec::new();
+    pub fn gen_mesh(&self, right_chunk: &ServerChunk, left_chunk: &ServerChunk, front_chunk: &ServerChunk, back_chunk: &ServerChunk) -> Arc<ChunkMesh> {
+        let mut mesh = ChunkMesh::new(self.x, self.z);
         for (x, y, z) in self.blocks_in_mesh.iter() {
             // let instant = std::time::Instant::now();
             let x = *x;
@@ -47,8 +44,7 @@ impl ServerChunk {
             let y = y as i32;
             let z = z as i32;
             let faces =
-                0
-                | if self.can_place_mesh_face_at_block(x, y, z - 1, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b10000000 } else { 0 }
+                if self.can_place_mesh_face_at_block(x, y, z - 1, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b10000000 } else { 0 }
                 | if self.can_place_mesh_face_at_block(x + 1, y, z, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b01000000 } else { 0 }
                 | if self.can_place_mesh_face_at_block(x, y, z + 1, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b00100000 } else { 0 }
                 | if self.can_place_mesh_face_at_block(x, y - 1, z, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b00010000 } else { 0 }
@@ -64,11 +60,7 @@ impl ServerChunk {
             let world_y = y as f32;
             let world_z = (z + self.z) as f32;
             
-            let vertices_to_push_to = if block == BlockType::Water {
-                &mut water_vertices
-            }else {
-                &mut vertices
-            };
+            let vertices_to_push_to = mesh.points_for(block);
             vertices_to_push_to.push(world_x);
             vertices_to_push_to.push(world_y);
             vertices_to_push_to.push(world_z);
@@ -87,11 +79,29 @@ impl ServerChunk {
             }
 
             vertices_to_push_to.push(faces as f32);
+
+            // fluid levels aren't sent by the server so water
+            // surfaces are all drawn as if they were sources
+            let height = if block == BlockType::Water && (y + 1 >= CHUNK_HEIGHT as i32 || self.blocks.get(x as usize, y as usize + 1, z as usize) != BlockType::Water) {
+                WATER_SURFACE_HEIGHT
+            } else {
+                1.0
+            };
+            vertices_to_push_to.push(height);
+
+            // water is see-through so isn't shaded by its surroundings
+            let (occlusion0, occlusion1) = if block == BlockType::Water {
+                (0.0, 0.0)
+            } else {
+                block_occlusion(self, x, y, z, faces, right_chunk, left_chunk, front_chunk, back_chunk)
+            };
+            vertices_to_push_to.push(occlusion0);
+            vertices_to_push_to.push(occlusion1);
             // println!("Took {:?} to add a block", instant.elapsed());
         }
         //self.mesh = Rc::new(vertices);
 
-        Arc::new((vertices, water_vertices))
+        Arc::new(mesh)
     }
 
     pub fn block_at(&self, x: usize, y: usize, z: usize) -> BlockType {
@@ -1,58 +1,112 @@
-use std::{io::{self, BufRead, BufReader, LineWriter, Write}, net::TcpStream, thread};
+use std::{io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write}, net::{Shutdown, TcpStream}, sync::{Arc, Mutex}, thread, time::Duration};
 
 use cgmath::Vector3;
-use serde_json::Result;
 
-use crate::multiplayer::{rc_message::RustyCraftMessage, server_player::ServerPlayer};
+use crate::{core::{block_type::BlockType, chat_log::ChatKind, entity::{Entity, EntityKind}, interpolation::Interpolation, world_time::WorldTime}, multiplayer::rc_message::RustyCraftMessage};
 
-use super::{event::RustyCraftEvent, server_state::ServerState};
+use crate::utils::hex_utils::from_hex;
+
+use super::{encryption::{Cipher, PINNED_KEYS_PATH, TransportReader, TransportWriter, client_handshake}, event::RustyCraftEvent, identity::{Authentication, TRUSTED_SERVERS_PATH, Trust, TrustedServers, fingerprint}, protocol::{CAPABILITIES, CAPABILITY_ENCRYPTION, PROTOCOL_VERSION, Protocol, encode_frame, event_from_json_line, read_event_frame, to_json_line}, server_state::ServerState};
+
+// how long to wait for the server to answer the handshake
+// before assuming it only speaks JSON lines
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
 
 // struct that abstracts reading and writing to a server
 pub struct ServerConnection {
-    reader: BufReader<TcpStream>,
-    writer: LineWriter<TcpStream>,
+    reader: BufReader<TransportReader>,
+    // shared by every clone so messages sent from different
+    // threads are written whole, one after the other
+    writer: Arc<Mutex<BufWriter<TransportWriter>>>,
     stream: TcpStream,
-    pub address: String
+    pub address: String,
+    pub protocol: Protocol,
+    // session keys if the connection is encrypted
+    cipher: Option<Cipher>,
+    // first line from a server that didn't answer the handshake,
+    // handled as a normal event once listening
+    pending_line: Option<String>
 }
 
 impl Clone for ServerConnection {
     fn clone(&self) -> Self {
-        let writer = LineWriter::new(self.stream.try_clone().unwrap());
-        let reader = BufReader::new(self.stream.try_clone().unwrap());
-        ServerConnection { reader, writer, stream: self.stream.try_clone().unwrap(), address: self.address.clone() }
+        let writer = self.writer.clone();
+        let reader = BufReader::new(TransportReader::new(self.stream.try_clone().unwrap(), self.cipher.clone()));
+        ServerConnection { 
+            reader, 
+            writer, 
+            stream: self.stream.try_clone().unwrap(), 
+            address: self.address.clone(), 
+            protocol: self.protocol, 
+            cipher: self.cipher.clone(),
+            pending_line: self.pending_line.clone() 
+        }
     }
 }
 
 impl ServerConnection {
-    pub fn new(mut address: String) -> io::Result<ServerConnection> {
+    pub fn new(address: String) -> io::Result<ServerConnection> {
+        ServerConnection::connect(address, CAPABILITIES)
+    }
+
+    // a connection only for asking the server's status, left unencrypted
+    // so pinging servers (including any LAN host that answers) never
+    // pins or checks their keys, see server_info::query_status
+    pub fn for_status(address: String) -> io::Result<ServerConnection> {
+        ServerConnection::connect(address, CAPABILITIES & !CAPABILITY_ENCRYPTION)
+    }
+
+    fn connect(mut address: String, capabilities: u32) -> io::Result<ServerConnection> {
         // default port
         if !address.contains(":") {
             address.push_str(":25566");
         }
 
-        let stream = TcpStream::connect(address.clone());
-        if stream.is_err() {
-            return Err(stream.unwrap_err());
-        }
+        let stream = TcpStream::connect(address.clone())?;
+        let (stream, protocol, pending_line, prologue) = match handshake(&stream, capabilities)? {
+            Some((protocol, pending_line, prologue)) => (stream, protocol, pending_line, prologue),
+            // servers from before the handshake may drop the connection
+            // over the message they don't know, so start over without it
+            None => (TcpStream::connect(address.clone())?, Protocol::Json, None, Vec::new())
+        };
 
-        let stream = stream.unwrap();
-        let writer = LineWriter::new(stream.try_clone()?);
-        let reader = BufReader::new(stream.try_clone()?);
-        Ok(ServerConnection { reader, writer, address, stream })
+        // the capabilities aren't authenticated, so a server that was
+        // encrypted before and now isn't may have had it stripped on
+        // the way, whether it fell back to JSON or just left it out
+        let cipher = match protocol.encryption() {
+            true => Some(client_handshake(&stream, address.as_str(), &prologue)?),
+            false if capabilities & CAPABILITY_ENCRYPTION != 0 && TrustedServers::load(PINNED_KEYS_PATH).knows(address.as_str()) => return Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!("{} was encrypted before but didn't agree to encryption, remove it from {} if this is expected", address, PINNED_KEYS_PATH)
+            )),
+            false => None
+        };
+
+        let writer = Arc::new(Mutex::new(BufWriter::new(TransportWriter::new(stream.try_clone()?, cipher.clone()))));
+        let reader = BufReader::new(TransportReader::new(stream.try_clone()?, cipher.clone()));
+        Ok(ServerConnection { reader, writer, address, stream, protocol, cipher, pending_line })
     }
 
-    fn send(&mut self, message: &str) -> io::Result<()> {
-        self.writer.write(&message.as_bytes())?;
-        // send breakline to flush writer
-        self.writer.write(&[b'\n'])?;
-        Ok(())
+    // the whole message is written and flushed under the writer's
+    // lock so it can't be split by a message from another clone
+    fn send(&mut self, message: &[u8]) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(message)?;
+        writer.flush()
     }
 
     pub fn send_message(&mut self, event: RustyCraftMessage) -> io::Result<()> {
-        self.send(serde_json::to_string(&event).unwrap().as_str())
+        match self.protocol {
+            Protocol::Json => self.send(format!("{}\n", to_json_line(&event)).as_bytes()),
+            Protocol::Binary { compression, .. } => self.send(&encode_frame(&event, compression))
+        }
     }
 
     pub fn read(&mut self) -> io::Result<Option<String>> {
+        if let Some(line) = self.pending_line.take() {
+            return Ok(Some(line))
+        }
+
         let mut line = String::new();
         let bytes_read = self.reader.read_line(&mut line)?;
         match bytes_read {
@@ -64,82 +118,286 @@ impl ServerConnection {
         }
     }
 
-    pub fn create_listen_thread(mut self, state: ServerState) {
+    // next event from the server in whichever protocol was negotiated,
+    // None once the server closes the connection, events that can't be
+    // parsed are errors of kind InvalidData
+    pub fn read_event(&mut self) -> io::Result<Option<RustyCraftEvent>> {
+        match self.protocol {
+            Protocol::Json => match self.read()? {
+                None => Ok(None),
+                Some(line) => event_from_json_line(line.as_str())
+                    .map(Some)
+                    .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))
+            },
+            Protocol::Binary { compression, .. } => read_event_frame(&mut self.reader, compression)
+        }
+    }
+
+    // for reads that shouldn't wait on the server forever,
+    // timed out reads are errors of kind WouldBlock or TimedOut
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    // closes the connection, ending the listen thread
+    pub fn close(&self) {
+        self.stream.shutdown(Shutdown::Both).ok();
+    }
+
+    // authentication is the join handshake started with the
+    // server, if it authenticates players
+    pub fn create_listen_thread(mut self, state: ServerState, authentication: Option<Authentication>) {
         thread::spawn(move || {
+            // nothing from a trusted server is believed until it has
+            // proven it has the same key as last time
+            let mut awaiting_challenge = authentication.is_some() && TrustedServers::load(TRUSTED_SERVERS_PATH).knows(self.address.as_str());
             loop {
-                let result = self.read().unwrap();
-                match result {
-                    None => break,
-                    Some(data) => {
-                        let event: Result<RustyCraftEvent> = serde_json::from_str(data.as_str());
-                        if event.is_err() {
-                            println!("Received invalid event");
-                            continue;
+                let event = match self.read_event() {
+                    Ok(Some(event)) => event,
+                    Ok(None) => {
+                        state.disconnect("Server closed the connection", false);
+                        break;
+                    },
+                    Err(error) if error.kind() == ErrorKind::InvalidData => {
+                        println!("Received invalid event");
+                        continue;
+                    },
+                    Err(error) => {
+                        state.disconnect(format!("Lost connection to server ({})", error).as_str(), false);
+                        break;
+                    }
+                };
+                state.heard_from_server();
+
+                if awaiting_challenge {
+                    match event.message {
+                        RustyCraftMessage::AuthChallenge { .. } | RustyCraftMessage::Ping { .. } | RustyCraftMessage::Pong { .. } => (),
+                        _ => {
+                            state.disconnect("Server didn't prove its identity before joining", true);
+                            break;
                         }
+                    }
+                }
 
-                        match event.unwrap() {
-                            RustyCraftEvent { sender: _, message: RustyCraftMessage::ChunkData { chunks } } => {
-                                let mut world = state.world.lock().unwrap();
-                                for (chunk_x, chunk_z, serialized_chunk) in chunks.into_iter() {
-                                    world.insert_serialized_chunk(chunk_x, chunk_z, serialized_chunk);
-                                }
-                            },
-                            RustyCraftEvent { sender: _, message: RustyCraftMessage::SetBlock { world_x, world_y, world_z, block } } => {
-                                let mut server_world = state.world.lock().unwrap();
-                                server_world.set_block(world_x, world_y, world_z, block);
-                                server_world.recalculate_mesh_from_player_perspective();
-                            },
-                            // set name can only be done once after player joins, so use it to broadcast
-                            // join message
-                            RustyCraftEvent { sender, message: RustyCraftMessage::PlayerInit { name, x, y, z } } => {
-                                state.players.lock().unwrap().insert(sender.clone(), ServerPlayer::new(sender, name.clone(), x, y, z, 0.0, -90.0));
-                                state.chat_stack.lock().unwrap().push(format!("{} joined the server", name));
-                            },
-                            RustyCraftEvent { sender, message: RustyCraftMessage::PlayerPosition { x, y, z } } => {
-                                if let Some(player) = state.players.lock().unwrap().get_mut(&sender) {
-                                    player.position = Vector3::new(x, y, z);
-                                }
-                            },
-                            RustyCraftEvent { sender, message: RustyCraftMessage::PlayerDirection { yaw, pitch } } => {
-                                if let Some(player) = state.players.lock().unwrap().get_mut(&sender) {
-                                    player.yaw = yaw;
-                                    player.pitch = pitch;
-                                }
-                            },
-                            RustyCraftEvent { sender, message: RustyCraftMessage::ChatMessage { content } } => {
-                                let message = match state.players.lock().unwrap().get(&sender) {
-                                    Some(player) => format!("<{}> {}", player.name, content),
-                                    None => format!("<Unnamed Player> {}", content)
-                                };
-                                state.chat_stack.lock().unwrap().push(message);
-                            },
-                            RustyCraftEvent { sender: _, message: RustyCraftMessage::ConnectionData { id, players } } => {
-                                *state.client_id.lock().unwrap() = id;
-                                for (id, name, x, y, z, yaw, pitch) in players.iter() {
-                                    state.players.lock().unwrap().insert(id.clone(), ServerPlayer::new(id.clone(), name.clone(), *x, *y, *z, *pitch, *yaw));
-                                }
-                            },
-                            RustyCraftEvent { sender, message: RustyCraftMessage::Disconnect } => {
-                                let mut players = state.players.lock().unwrap();
-                                let player = players.get(&sender);
-                                // handle if peer never sent SetName packet
-                                let message = match player {
-                                    Some(player) => format!("{} left the server", player.name),
-                                    None => String::from("[Unnamed Player] left the server")
-                                };
-
-                                if player.is_some() {
-                                    players.remove(&sender);
-                                }
-                                state.chat_stack.lock().unwrap().push(message);
-                            }
-                            event => {
-                                println!("Received unhandled event: {:?}", event);
+                match event {
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::Ping { time } } => {
+                        // answered through the writer shared with the main
+                        // thread, so the pong is never written in the middle
+                        // of (or encrypted out of order with) its messages
+                        if self.send_message(RustyCraftMessage::Pong { time }).is_err() {
+                            state.disconnect("Failed to answer keepalive", false);
+                        }
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::Pong { time } } => {
+                        let latency = state.millis().saturating_sub(time);
+                        *state.latency.lock().unwrap() = Some(latency as f32);
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::ChunkData { chunks } } => {
+                        let mut world = state.world.lock().unwrap();
+                        for (chunk_x, chunk_z, serialized_chunk) in chunks.into_iter() {
+                            world.insert_serialized_chunk(chunk_x, chunk_z, serialized_chunk);
+                        }
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::SetBlock { world_x, world_y, world_z, block } } => {
+                        let mut server_world = state.world.lock().unwrap();
+                        server_world.apply_server_block(world_x, world_y, world_z, block);
+                        server_world.recalculate_mesh_from_player_perspective();
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::FallingBlockSpawn { id, block, world_x, world_y, world_z } } => {
+                        {
+                            let mut server_world = state.world.lock().unwrap();
+                            server_world.apply_server_block(world_x, world_y, world_z, BlockType::Air);
+                            server_world.recalculate_mesh_from_player_perspective();
+                        }
+                        let position = Vector3::new(world_x as f32, world_y as f32, world_z as f32);
+                        state.entities.lock().unwrap().spawn_remote(falling_block_remote_id(id), EntityKind::FallingBlock { block }, position);
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::FallingBlockLand { id, block, world_x, world_y, world_z } } => {
+                        state.entities.lock().unwrap().remove_remote(falling_block_remote_id(id).as_str());
+                        let mut server_world = state.world.lock().unwrap();
+                        server_world.apply_server_block(world_x, world_y, world_z, block);
+                        server_world.recalculate_mesh_from_player_perspective();
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::AuthChallenge { server_key, signature, challenge } } => {
+                        let authentication = match &authentication {
+                            Some(authentication) => authentication,
+                            None => {
+                                println!("Received auth challenge without having sent a hello");
+                                continue;
                             }
+                        };
+
+                        if let Err(reason) = self.answer_challenge(authentication, &state, server_key.as_str(), signature.as_str(), challenge.as_str()) {
+                            state.disconnect(reason.as_str(), true);
+                            break;
+                        }
+                        awaiting_challenge = false;
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::BlockEditResult { sequence, accepted } } => {
+                        let mut server_world = state.world.lock().unwrap();
+                        let coords = server_world.resolve_edit(sequence, accepted);
+                        server_world.recalculate_mesh_from_player_perspective();
+                        if let (false, Some((x, y, z))) = (accepted, coords) {
+                            state.chat.lock().unwrap().push(ChatKind::Error, format!("Server rejected block edit at {}, {}, {}", x, y, z).as_str());
                         }
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::TimeSync { time } } => {
+                        *state.time.lock().unwrap() = WorldTime::new(time);
+                    },
+                    // set name can only be done once after player joins, so use it to broadcast
+                    // join message
+                    RustyCraftEvent { sender, message: RustyCraftMessage::PlayerInit { name, x, y, z } } => {
+                        state.entities.lock().unwrap().spawn_remote(sender, EntityKind::Player { name: name.clone() }, Vector3::new(x, y, z));
+                        state.chat.lock().unwrap().push(ChatKind::System, format!("{} joined the server", name).as_str());
+                    },
+                    // drawn from the buffered snapshots, see core::interpolation
+                    RustyCraftEvent { sender, message: RustyCraftMessage::PlayerPosition { x, y, z, time } } => {
+                        let received = state.millis();
+                        if let Some(player) = state.entities.lock().unwrap().find_remote_mut(sender.as_str()) {
+                            player.interpolation.get_or_insert_with(Interpolation::new).push_position(time, received, Vector3::new(x, y, z));
+                        }
+                    },
+                    RustyCraftEvent { sender, message: RustyCraftMessage::PlayerDirection { yaw, pitch, time } } => {
+                        let received = state.millis();
+                        if let Some(player) = state.entities.lock().unwrap().find_remote_mut(sender.as_str()) {
+                            player.interpolation.get_or_insert_with(Interpolation::new).push_direction(time, received, yaw, pitch);
+                        }
+                    },
+                    RustyCraftEvent { sender, message: RustyCraftMessage::ChatMessage { content } } => {
+                        // messages with no sender are from the server
+                        // itself, e.g. the answer to a command
+                        let (kind, message) = match player_name(&state, sender.as_str()) {
+                            _ if sender.is_empty() => (ChatKind::System, content),
+                            Some(name) => (ChatKind::Player, format!("<{}> {}", name, content)),
+                            None => (ChatKind::Player, format!("<Unnamed Player> {}", content))
+                        };
+                        state.chat.lock().unwrap().push(kind, message.as_str());
+                    },
+                    RustyCraftEvent { sender: _, message: RustyCraftMessage::ConnectionData { id, players } } => {
+                        *state.client_id.lock().unwrap() = id;
+                        let mut entities = state.entities.lock().unwrap();
+                        for (id, name, x, y, z, yaw, pitch) in players.into_iter() {
+                            let player = entities.spawn_remote(id, EntityKind::Player { name }, Vector3::new(x, y, z));
+                            player.yaw = yaw;
+                            player.pitch = pitch;
+                        }
+                    },
+                    // the server dropping this client rather than another player leaving
+                    RustyCraftEvent { sender, message: RustyCraftMessage::Disconnect { reason } } if is_this_client(&state, sender.as_str()) => {
+                        match reason.len() {
+                            0 => state.disconnect("Disconnected by server", true),
+                            _ => state.disconnect(reason.as_str(), true)
+                        }
+                        break;
+                    },
+                    RustyCraftEvent { sender, message: RustyCraftMessage::Disconnect { .. } } => {
+                        // handle if peer never sent SetName packet
+                        let message = match player_name(&state, sender.as_str()) {
+                            Some(name) => format!("{} left the server", name),
+                            None => String::from("[Unnamed Player] left the server")
+                        };
+
+                        state.entities.lock().unwrap().remove_remote(sender.as_str());
+                        state.chat.lock().unwrap().push(ChatKind::System, message.as_str());
+                    }
+                    event => {
+                        println!("Received unhandled event: {:?}", event);
                     }
                 }
             }
         });
     }
-}
\ No newline at end of file
+}
+
+impl ServerConnection {
+    // checks the server is who it claims and, if it has been connected to
+    // before, who it was last time, then signs its challenge
+    fn answer_challenge(&mut self, authentication: &Authentication, state: &ServerState, server_key: &str, signature: &str, challenge: &str) -> Result<(), String> {
+        let key = from_hex(server_key).ok_or("Server sent an invalid key")?;
+        let answer = authentication.answer(&key, signature, challenge)?;
+
+        let mut trusted_servers = TrustedServers::load(TRUSTED_SERVERS_PATH);
+        match trusted_servers.check(self.address.as_str(), &key) {
+            Trust::Known => (),
+            Trust::New => {
+                trusted_servers.trust(self.address.as_str(), &key)
+                    .map_err(|e| format!("Failed to save server key: {}", e))?;
+                state.chat.lock().unwrap().push(ChatKind::System, format!("Trusting {} with key {} from now on", self.address, fingerprint(&key)).as_str());
+            },
+            Trust::Changed => {
+                return Err(format!("{} now has key {}, remove it from game_data/trusted_servers if this is expected", self.address, fingerprint(&key)))
+            }
+        }
+
+        self.send_message(answer).map_err(|e| format!("Failed to answer challenge: {}", e))
+    }
+}
+
+// falling block ids from the server are kept
+// apart from player ids as entity remote ids
+fn falling_block_remote_id(id: u32) -> String {
+    format!("falling_block_{}", id)
+}
+
+// events about this client are sent either
+// with its own id or with no sender at all
+fn is_this_client(state: &ServerState, sender: &str) -> bool {
+    sender.is_empty() || *state.client_id.lock().unwrap() == sender
+}
+
+fn player_name(state: &ServerState, remote_id: &str) -> Option<String> {
+    match state.entities.lock().unwrap().find_remote(remote_id) {
+        Some(Entity { kind: EntityKind::Player { name }, .. }) => Some(name.clone()),
+        _ => None
+    }
+}
+
+// asks the server to switch to the binary protocol, returning the protocol
+// to use, any line the server sent other than an answer and the lines that
+// were exchanged (the prologue of the encryption handshake), or None if it
+// closed the connection instead. The answer is read a byte at a time so
+// nothing sent after it ends up buffered here instead of in the reader
+fn handshake(stream: &TcpStream, capabilities: u32) -> io::Result<Option<(Protocol, Option<String>, Vec<u8>)>> {
+    let hello = RustyCraftMessage::Handshake { version: PROTOCOL_VERSION, capabilities };
+    let hello_line = format!("{}\n", serde_json::to_string(&hello).unwrap());
+    let mut writer = stream;
+    writer.write_all(hello_line.as_bytes())?;
+
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut reader = stream;
+    let mut line = Vec::new();
+    let mut byte = [0];
+    let answer = loop {
+        match reader.read(&mut byte) {
+            Ok(0) => break None,
+            Ok(_) if byte[0] == b'\n' => break Some(String::from_utf8_lossy(&line).to_string()),
+            Ok(_) => line.push(byte[0]),
+            // old servers don't answer at all
+            Err(error) if (error.kind() == ErrorKind::WouldBlock || error.kind() == ErrorKind::TimedOut) && line.is_empty() => {
+                stream.set_read_timeout(None)?;
+                return Ok(Some((Protocol::Json, None, Vec::new())))
+            },
+            // but a server that stalls partway through one is gone
+            Err(error) if error.kind() == ErrorKind::WouldBlock || error.kind() == ErrorKind::TimedOut => {
+                return Err(io::Error::new(ErrorKind::TimedOut, "Server stopped answering the handshake"))
+            },
+            Err(error) if error.kind() == ErrorKind::ConnectionReset || error.kind() == ErrorKind::ConnectionAborted => break None,
+            Err(error) => return Err(error)
+        }
+    };
+    stream.set_read_timeout(None)?;
+
+    let line = match answer {
+        Some(line) => line,
+        None => return Ok(None)
+    };
+
+    match serde_json::from_str::<RustyCraftEvent>(line.as_str()) {
+        Ok(RustyCraftEvent { message: RustyCraftMessage::HandshakeAck { version, capabilities: server_capabilities }, .. }) => {
+            let prologue = format!("{}{}\n", hello_line, line).into_bytes();
+            // only what was asked for, whatever the server answers
+            Ok(Some((Protocol::negotiate(version, server_capabilities & capabilities), None, prologue)))
+        },
+        _ => Ok(Some((Protocol::Json, Some(line), Vec::new())))
+    }
+}
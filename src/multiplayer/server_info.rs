@@ -0,0 +1,137 @@
+use std::{fs, io::{self, ErrorKind}, sync::mpsc::{self, Receiver, TryRecvError}, thread, time::{Duration, Instant}};
+
+use super::{event::RustyCraftEvent, rc_message::RustyCraftMessage, server_connection::ServerConnection};
+
+// saved servers, one "address name" per line
+const SERVERS_PATH: &str = "game_data/servers";
+
+// address last typed into direct connect, the list starts
+// with it for players from before the server list
+const LAST_SERVER_PATH: &str = "game_data/last_server";
+
+// how long a server has to answer a status request
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedServer {
+    pub name: String,
+    pub address: String
+}
+
+impl SavedServer {
+    // the name falls back to the address when left empty
+    pub fn new(name: &str, address: &str) -> Result<SavedServer, String> {
+        let address = address.trim();
+        if address.is_empty() {
+            return Err(String::from("Server address can't be empty"))
+        }
+
+        if address.contains(char::is_whitespace) {
+            return Err(String::from("Server address can't contain spaces"))
+        }
+
+        let name = match name.trim() {
+            "" => address,
+            name => name
+        };
+        Ok(SavedServer { name: String::from(name), address: String::from(address) })
+    }
+}
+
+pub fn load_servers() -> Vec<SavedServer> {
+    let contents = match fs::read_to_string(SERVERS_PATH) {
+        Ok(contents) => contents,
+        Err(_) => return match fs::read_to_string(LAST_SERVER_PATH) {
+            Ok(address) => SavedServer::new("", address.as_str()).into_iter().collect(),
+            Err(_) => Vec::new()
+        }
+    };
+
+    contents.lines()
+        .filter_map(|line| {
+            let mut words = line.splitn(2, ' ');
+            let address = words.next().unwrap_or("");
+            let name = words.next().unwrap_or("");
+            SavedServer::new(name, address).ok()
+        })
+        .collect()
+}
+
+pub fn save_servers(servers: &[SavedServer]) -> io::Result<()> {
+    let mut contents = String::new();
+    for server in servers.iter() {
+        contents.push_str(format!("{} {}\n", server.address, server.name).as_str());
+    }
+    fs::create_dir_all("game_data")?;
+    fs::write(SERVERS_PATH, contents)
+}
+
+#[derive(Debug, Clone)]
+pub enum ServerStatus {
+    Pinging,
+    // latency is the round trip of the status request in milliseconds
+    Online { motd: String, players: u32, max_players: u32, version: String, latency: u32 },
+    // servers that don't answer status requests can only
+    // be shown to be accepting connections
+    Reachable,
+    Offline(String)
+}
+
+// connects to a server and asks for its status without joining
+pub fn query_status(address: String) -> ServerStatus {
+    let mut connection = match ServerConnection::for_status(address) {
+        Ok(connection) => connection,
+        Err(error) => return ServerStatus::Offline(error.to_string())
+    };
+
+    let status = match connection.protocol.status() {
+        true => request_status(&mut connection).unwrap_or_else(|error| ServerStatus::Offline(error.to_string())),
+        false => ServerStatus::Reachable
+    };
+    connection.close();
+    status
+}
+
+fn request_status(connection: &mut ServerConnection) -> io::Result<ServerStatus> {
+    connection.set_read_timeout(Some(STATUS_TIMEOUT))?;
+    let sent = Instant::now();
+    connection.send_message(RustyCraftMessage::StatusRequest)?;
+    loop {
+        match connection.read_event()? {
+            Some(RustyCraftEvent { sender: _, message: RustyCraftMessage::StatusResponse { motd, players, max_players, version } }) => {
+                let latency = sent.elapsed().as_millis() as u32;
+                return Ok(ServerStatus::Online { motd, players, max_players, version, latency })
+            },
+            Some(_) => continue,
+            None => return Err(io::Error::new(ErrorKind::UnexpectedEof, "server closed the connection"))
+        }
+    }
+}
+
+// a status query made on its own thread so the server list keeps drawing
+pub struct StatusQuery {
+    pub status: ServerStatus,
+    pending: Option<Receiver<ServerStatus>>
+}
+
+impl StatusQuery {
+    pub fn start(address: &str) -> StatusQuery {
+        let (sender, receiver) = mpsc::channel();
+        let address = String::from(address);
+        thread::spawn(move || {
+            sender.send(query_status(address)).ok();
+        });
+        StatusQuery { status: ServerStatus::Pinging, pending: Some(receiver) }
+    }
+
+    pub fn poll(&mut self) {
+        if let Some(pending) = &self.pending {
+            self.status = match pending.try_recv() {
+                Ok(status) => status,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => ServerStatus::Offline(String::from("status query failed"))
+            };
+            self.pending = None;
+        }
+    }
+}
@@ -1,24 +1,78 @@
-use std::{collections::HashMap, sync::{Arc, Mutex}};
+use std::{sync::{Arc, Mutex}, time::{Duration, Instant}};
 
-use crate::multiplayer::server_world::ServerWorld;
+use crate::{core::{chat_log::ChatLog, entity::Entities, world_time::WorldTime}, multiplayer::server_world::ServerWorld};
 
-use super::server_player::ServerPlayer;
+// how long the server can go quiet before the connection
+// is given up on, only checked on servers answering pings
+pub const TIMEOUT: Duration = Duration::from_secs(20);
+
+// why a connection ended
+#[derive(Clone)]
+pub struct Disconnection {
+    pub reason: String,
+    // the server dropped the client on purpose (kicked, full etc.),
+    // so there's no point reconnecting straight away
+    pub by_server: bool
+}
 
 #[derive(Clone)]
 pub struct ServerState {
     pub client_id: Arc<Mutex<String>>,
     pub world: Arc<Mutex<ServerWorld>>,
-    pub players: Arc<Mutex<HashMap<String, ServerPlayer>>>,
-    pub chat_stack: Arc<Mutex<Vec<String>>>,
+    // other players, falling blocks etc. mirrored from the server
+    pub entities: Arc<Mutex<Entities>>,
+    pub chat: Arc<Mutex<ChatLog>>,
+    pub time: Arc<Mutex<WorldTime>>,
+    // set once the connection is lost
+    pub disconnected: Arc<Mutex<Option<Disconnection>>>,
+    // round trip of the last answered ping in milliseconds
+    pub latency: Arc<Mutex<Option<f32>>>,
+    // when anything was last received from the server
+    pub last_heard: Arc<Mutex<Instant>>,
+    started: Instant
 }
 
 impl ServerState {
-    pub fn new(world: Arc<Mutex<ServerWorld>>) -> ServerState {
-        ServerState { 
+    pub fn new(world: Arc<Mutex<ServerWorld>>, address: &str) -> ServerState {
+        ServerState {
             client_id: Arc::new(Mutex::new(String::new())),
-            world, 
-            players: Arc::new(Mutex::new(HashMap::new())), 
-            chat_stack: Arc::new(Mutex::new(Vec::new())) 
+            world,
+            entities: Arc::new(Mutex::new(Entities::new())),
+            chat: Arc::new(Mutex::new(ChatLog::for_server(address))),
+            time: Arc::new(Mutex::new(WorldTime::new(0))),
+            disconnected: Arc::new(Mutex::new(None)),
+            latency: Arc::new(Mutex::new(None)),
+            last_heard: Arc::new(Mutex::new(Instant::now())),
+            started: Instant::now()
         }
     }
-}
\ No newline at end of file
+
+    // milliseconds since joining, sent in pings
+    pub fn millis(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    pub fn heard_from_server(&self) {
+        *self.last_heard.lock().unwrap() = Instant::now();
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.last_heard.lock().unwrap().elapsed() > TIMEOUT
+    }
+
+    // marks the connection as lost, keeping the first reason given
+    // as anything after is usually a consequence of it
+    pub fn disconnect(&self, reason: &str, by_server: bool) {
+        {
+            let mut disconnected = self.disconnected.lock().unwrap();
+            if disconnected.is_none() {
+                *disconnected = Some(Disconnection { reason: String::from(reason), by_server });
+            }
+        }
+        self.chat.lock().unwrap().flush();
+    }
+
+    pub fn disconnection(&self) -> Option<Disconnection> {
+        self.disconnected.lock().unwrap().clone()
+    }
+}
@@ -1,13 +1,36 @@
 // world struct for managing and
 // parsing world data from a server
-use std::{collections::HashSet, sync::Arc};
+use std::{collections::{HashMap, HashSet}, sync::Arc, time::{Duration, Instant}};
 use cgmath::{Vector3, InnerSpace};
-use crate::{core::{block_type::BlockType, coord_map::CoordMap, face::Face}, multiplayer::{rc_message::RustyCraftMessage, server_chunk::ServerChunk}, traits::{game_chunk::GameChunk, game_world::GameWorld}};
+use crate::{core::{block_type::BlockType, chunk_mesh::ChunkMesh, coord_map::CoordMap, face::Face}, multiplayer::{rc_message::RustyCraftMessage, server_chunk::ServerChunk}, traits::{game_chunk::GameChunk, game_world::GameWorld}};
 use super::server_connection::ServerConnection;
 
+// most chunks asked for at once, and most waiting on the server
+// at any time, so the nearest ones aren't stuck behind the rest
+const CHUNKS_PER_REQUEST: usize = 16;
+const MAX_CHUNKS_IN_FLIGHT: usize = 64;
+
+// chunks the server hasn't sent by now are asked for again
+const CHUNK_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// predicted edits the server hasn't answered by now are rolled back
+const EDIT_RESULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// chunks this many chunks further than the render distance are dropped
+const EVICTION_MARGIN: u32 = 4;
+
 // Vector of Arc (to be thread-safe with server connection listen thread)
 // of a tuple of opaque and then transparent block point vertices
-type WorldMesh = Vec<Arc<(Vec<f32>, Vec<f32>)>>; 
+type WorldMesh = Vec<Arc<ChunkMesh>>; 
+
+// a block edit shown before the server has confirmed it
+#[derive(Clone)]
+struct PredictedEdit {
+    sequence: u32,
+    coords: (i32, i32, i32),
+    block: BlockType,
+    predicted: Instant
+}
 
 #[derive(Clone)]
 pub struct ServerWorld {
@@ -16,8 +39,19 @@ pub struct ServerWorld {
     player_chunk_x: i32,
     player_chunk_z: i32,
     mesh: WorldMesh,
+    // chunks needed for the current view that haven't arrived
     chunk_fetch_queue: HashSet<(i32, i32)>,
-    server_connection: ServerConnection
+    // chunks asked for and when, until they arrive or time out
+    chunks_in_flight: HashMap<(i32, i32), Instant>,
+    // chunks that arrived since the mesh was last recalculated
+    chunks_received: usize,
+    server_connection: ServerConnection,
+    // edits waiting on a BlockEditResult, oldest first
+    predicted_edits: Vec<PredictedEdit>,
+    // what the server last said is at each position with predicted
+    // edits, shown again if they're all rejected
+    server_blocks: HashMap<(i32, i32, i32), BlockType>,
+    next_sequence: u32
 }
 
 impl GameWorld for ServerWorld {
@@ -28,8 +62,11 @@ impl GameWorld for ServerWorld {
             return None
         }
 
-        let result = Some(chunk.unwrap().block_at(local_x, world_y as usize, local_z));
-        result
+        Some(chunk.unwrap().block_at(local_x, world_y as usize, local_z))
+    }
+
+    fn render_distance(&self) -> u32 {
+        self.render_distance
     }
 
     fn get_game_chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&dyn GameChunk> {
@@ -53,7 +90,12 @@ impl ServerWorld {
             player_chunk_z: 0, 
             mesh: WorldMesh::new(), 
             chunk_fetch_queue: HashSet::new(), 
-            server_connection 
+            chunks_in_flight: HashMap::new(),
+            chunks_received: 0,
+            server_connection,
+            predicted_edits: Vec::new(),
+            server_blocks: HashMap::new(),
+            next_sequence: 0
         }
     }
 
@@ -67,10 +109,6 @@ impl ServerWorld {
         }
 
         self.recalculate_mesh_from_perspective(player_chunk_x, player_chunk_z);
-
-        self.player_chunk_x = player_chunk_x;
-        self.player_chunk_z = player_chunk_z;
-        
         &self.mesh
     }
 
@@ -130,39 +168,84 @@ impl ServerWorld {
             let x = *x;
             let z = *z;
 
-            let mesh;
             let chunk = self.get_chunk(x, z).unwrap();
-            if chunk.mesh.0.len() != 0 {
-                mesh = chunk.mesh.clone();
+            let mesh = if !chunk.mesh.is_empty() {
+                chunk.mesh.clone()
             } else {
                 let right_chunk = self.get_chunk(x + 1, z).unwrap();
                 let left_chunk = self.get_chunk(x - 1, z).unwrap();
                 let front_chunk = self.get_chunk(x, z + 1).unwrap();
                 let back_chunk = self.get_chunk(x, z - 1).unwrap();
-                mesh = chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk);
-            }
+                chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk)
+            };
 
             let chunk = self.get_chunk_mut(x, z).unwrap();
             chunk.mesh = mesh;
             meshes.push(chunk.mesh.clone());
         }
 
-        // fetch chunks
-        if self.chunk_fetch_queue.len() > 0 {
-            self.server_connection.send_message(RustyCraftMessage::GetChunks { coords: self.chunk_fetch_queue.clone().into_iter().collect() })
-                .expect("Failed to request batch of chunks")
+        self.evict_chunks(player_chunk_x, player_chunk_z);
+        self.mesh = meshes;
+        self.chunks_received = 0;
+        self.player_chunk_x = player_chunk_x;
+        self.player_chunk_z = player_chunk_z;
+        self.request_chunks();
+    }
+
+    // asks for the nearest missing chunks not already asked for, along
+    // with any that timed out, called every frame so requests keep
+    // going out while the server is slow to answer
+    pub fn request_chunks(&mut self) {
+        self.chunks_in_flight.retain(|_, requested| requested.elapsed() < CHUNK_REQUEST_TIMEOUT);
+
+        let room = MAX_CHUNKS_IN_FLIGHT.saturating_sub(self.chunks_in_flight.len()).min(CHUNKS_PER_REQUEST);
+        if room == 0 {
+            return
         }
 
-        self.mesh = meshes;
+        let (player_chunk_x, player_chunk_z) = (self.player_chunk_x, self.player_chunk_z);
+        let mut coords: Vec<(i32, i32)> = self.chunk_fetch_queue.iter()
+            .filter(|coords| !self.chunks_in_flight.contains_key(coords))
+            .cloned()
+            .collect();
+        if coords.is_empty() {
+            return
+        }
+
+        coords.sort_by_key(|(x, z)| (x - player_chunk_x).pow(2) + (z - player_chunk_z).pow(2));
+        coords.truncate(room);
+
+        let now = Instant::now();
+        for chunk in coords.iter() {
+            self.chunks_in_flight.insert(*chunk, now);
+        }
+
+        // a failed request means the connection is gone,
+        // which the listen thread reports
+        self.server_connection.send_message(RustyCraftMessage::GetChunks { coords }).ok();
+    }
+
+    // drops chunks well out of view so a long session
+    // doesn't keep every chunk it has ever been sent
+    fn evict_chunks(&mut self, player_chunk_x: i32, player_chunk_z: i32) {
+        let range = (self.render_distance + EVICTION_MARGIN) as i32;
+        for (x, z) in self.chunks.coords() {
+            if (x - player_chunk_x).pow(2) + (z - player_chunk_z).pow(2) > range * range {
+                self.chunks.remove(x, z);
+            }
+        }
     }
 
     pub fn insert_serialized_chunk(&mut self, chunk_x: i32, chunk_z: i32, serialized_chunk: String) {
         let chunk = ServerChunk::from_serialized(serialized_chunk, chunk_x, chunk_z);
         self.chunks.insert(chunk_x, chunk_z, chunk);
         self.chunk_fetch_queue.remove(&(chunk_x, chunk_z));
+        self.chunks_in_flight.remove(&(chunk_x, chunk_z));
+        self.chunks_received += 1;
 
-        // when all chunks requested have been fetched re-render mesh
-        if self.chunk_fetch_queue.len() == 0 {
+        // re-render mesh every batch's worth of chunks, or once
+        // everything asked for has arrived
+        if self.chunks_received >= CHUNKS_PER_REQUEST || self.chunks_in_flight.is_empty() {
             self.recalculate_mesh_from_perspective(self.player_chunk_x, self.player_chunk_z);
         }
     }
@@ -190,21 +273,101 @@ impl ServerWorld {
             }
         }
 
-        // update chunk mesh
+        // update chunk mesh, along with any neighbours
+        // sharing an edge (or both at a corner)
         self.update_chunk_mesh(chunk_x, chunk_z);
         if local_x == 0 {
             self.update_chunk_mesh(chunk_x - 1, chunk_z)
         } else if local_x == 15 {
             self.update_chunk_mesh(chunk_x + 1, chunk_z)
-        } else if local_z == 0 {
+        }
+
+        if local_z == 0 {
             self.update_chunk_mesh(chunk_x, chunk_z - 1)
         } else if local_z == 15 {
             self.update_chunk_mesh(chunk_x, chunk_z + 1)
         }
     }
 
+    // applies an edit straight away, returning the sequence number
+    // to send with it so the server's result can be matched up
+    pub fn predict_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) -> u32 {
+        let coords = (world_x, world_y, world_z);
+        if !self.server_blocks.contains_key(&coords) {
+            let current = self.get_block(world_x, world_y, world_z).unwrap_or(BlockType::Air);
+            self.server_blocks.insert(coords, current);
+        }
+
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        let sequence = self.next_sequence;
+        self.predicted_edits.push(PredictedEdit { sequence, coords, block, predicted: Instant::now() });
+        self.set_block(world_x, world_y, world_z, block);
+        sequence
+    }
+
+    // a block set by the server, positions with edits still waiting
+    // on a result keep showing the edit until it's resolved
+    pub fn apply_server_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) {
+        let coords = (world_x, world_y, world_z);
+        match self.server_blocks.get_mut(&coords) {
+            Some(server_block) => *server_block = block,
+            None => self.set_block(world_x, world_y, world_z, block)
+        }
+    }
+
+    // keeps or rolls back a predicted edit once the server has answered,
+    // returning where it was or None if the sequence wasn't pending
+    pub fn resolve_edit(&mut self, sequence: u32, accepted: bool) -> Option<(i32, i32, i32)> {
+        let index = self.predicted_edits.iter().position(|edit| edit.sequence == sequence)?;
+        let edit = self.predicted_edits.remove(index);
+
+        // anything the server sent for the position before the
+        // result was applied before the edit
+        if accepted {
+            self.server_blocks.insert(edit.coords, edit.block);
+        }
+
+        // show the newest edit still waiting, or what the server has
+        let (x, y, z) = edit.coords;
+        let newest = self.predicted_edits.iter().rev().find(|other| other.coords == edit.coords).map(|other| other.block);
+        let block = match newest {
+            Some(block) => block,
+            None => self.server_blocks.remove(&edit.coords).unwrap_or(edit.block)
+        };
+        if self.get_block(x, y, z) != Some(block) {
+            self.set_block(x, y, z, block);
+        }
+        Some(edit.coords)
+    }
+
+    // rolls back edits the server never answered, e.g. when the result
+    // was lost, so they don't stay shown (and pending) forever, returning
+    // where each one was
+    pub fn expire_edits(&mut self) -> Vec<(i32, i32, i32)> {
+        let expired: Vec<u32> = self.predicted_edits.iter()
+            .filter(|edit| edit.predicted.elapsed() >= EDIT_RESULT_TIMEOUT)
+            .map(|edit| edit.sequence)
+            .collect();
+        if expired.is_empty() {
+            return Vec::new()
+        }
+
+        let coords = expired.into_iter().filter_map(|sequence| self.resolve_edit(sequence, false)).collect();
+        self.recalculate_mesh_from_player_perspective();
+        coords
+    }
+
     fn update_chunk_mesh(&mut self, chunk_x: i32, chunk_z: i32) {
-        // assume adjacent chunks exist
+        // chunks on the edge of what the server has sent
+        // have no mesh until their neighbours arrive
+        if self.get_chunk(chunk_x, chunk_z).is_none()
+            || self.get_chunk(chunk_x + 1, chunk_z).is_none()
+            || self.get_chunk(chunk_x - 1, chunk_z).is_none()
+            || self.get_chunk(chunk_x, chunk_z + 1).is_none()
+            || self.get_chunk(chunk_x, chunk_z - 1).is_none() {
+            return
+        }
+
         let right_chunk = self.get_chunk(chunk_x + 1, chunk_z).unwrap();
         let left_chunk = self.get_chunk(chunk_x - 1, chunk_z).unwrap();
         let front_chunk = self.get_chunk(chunk_x, chunk_z + 1).unwrap();
@@ -230,8 +393,8 @@ impl ServerWorld {
             chunk_z -= 1;
         }
 
-        let local_x = ((chunk_x.abs() * 16 + world_x) % 16).abs() as usize;
-        let local_z = ((chunk_z.abs() * 16 + world_z) % 16).abs() as usize;
+        let local_x = ((chunk_x.abs() * 16 + world_x) % 16).unsigned_abs() as usize;
+        let local_z = ((chunk_z.abs() * 16 + world_z) % 16).unsigned_abs() as usize;
         (chunk_x, chunk_z, local_x, local_z)
     }
 
@@ -242,7 +405,7 @@ impl ServerWorld {
 
         let mut result = Vec::new();
         loop {
-            check_position = check_position + dir;
+            check_position += dir;
             let x = check_position.x.round() as i32;
             let y = check_position.y.round() as i32;
             let z = check_position.z.round() as i32;
@@ -303,7 +466,7 @@ impl ServerWorld {
             if range == 0 {
                 return None;
             }
-            range = range - 1;
+            range -= 1;
         }
     }
 }
@@ -0,0 +1,57 @@
+use cgmath::{Matrix4, Vector3};
+
+use crate::core::{block_type::{BlockType, block_to_uv, index_to_block}, face::Face};
+
+use super::{camera::Camera, cube::Cube, face_uvs::FaceUVs, fog::Fog, texture::Texture};
+
+// size in pixels of the block texture atlas and each tile in it
+const ATLAS_SIZE: f32 = 96.0;
+const TILE_SIZE: f32 = 16.0;
+
+// single blocks drawn outside of chunk meshes (falling blocks etc.)
+// as cubes textured from the block texture atlas
+pub struct BlockModel {
+    cubes: Vec<(BlockType, Cube)>
+}
+
+impl BlockModel {
+    pub unsafe fn new() -> BlockModel {
+        // flipped unlike the atlas used for chunks
+        // as FaceUVs expects flipped textures
+        let texture = Texture::new("assets/textures/textures.png", gl::TEXTURE0, true);
+        let mut cubes = Vec::new();
+        let mut i = 0;
+        while let Some(block) = index_to_block(i) {
+            i += 1;
+            if block == BlockType::Air || block == BlockType::Water {
+                continue;
+            }
+
+            let cube = Cube::new(
+                texture,
+                face_uvs(block, Face::Front),
+                face_uvs(block, Face::Back),
+                face_uvs(block, Face::Left),
+                face_uvs(block, Face::Right),
+                face_uvs(block, Face::Bottom),
+                face_uvs(block, Face::Top)
+            );
+            cubes.push((block, cube));
+        }
+        BlockModel { cubes }
+    }
+
+    // draws a block centered at the given position
+    pub unsafe fn draw(&self, camera: &Camera, fog: &Fog, block: BlockType, position: Vector3<f32>) {
+        if let Some((_, cube)) = self.cubes.iter().find(|(cube_block, _)| *cube_block == block) {
+            cube.draw(camera, fog, Matrix4::from_translation(position));
+        }
+    }
+}
+
+fn face_uvs(block: BlockType, face: Face) -> FaceUVs {
+    let index = block_to_uv(block, face) as usize;
+    let left = (index % 6) as f32 * TILE_SIZE;
+    let top = (index / 6) as f32 * TILE_SIZE;
+    FaceUVs::new(left, top + TILE_SIZE, left + TILE_SIZE, top, ATLAS_SIZE, ATLAS_SIZE)
+}
@@ -1,5 +1,5 @@
 use cgmath::{Deg, InnerSpace, Matrix4, Point3, Vector3, perspective, vec3};
-use glfw::{Action, Key};
+use glutin::event::{ElementState, VirtualKeyCode};
 
 use crate::utils::vector_utils::get_direction_from_mouse_move;
 
@@ -20,6 +20,8 @@ pub struct Camera {
     pub yaw: f32,
     pub pitch: f32,
     pub fov: f32,
+    // far clipping plane in blocks
+    pub far: f32,
     pub screen_width: u32,
     pub screen_height: u32,
     pub speed: f32
@@ -38,23 +40,20 @@ impl Camera {
             pitch: 0.0,
             yaw: -90.0,
             fov: 45.0,
+            far: 1000.0,
             screen_width,
             screen_height,
             speed
         }
     }
 
-    pub fn process_keyboard(&mut self, key: Key, action: Action) {
-        let pressed = match action {
-            Action::Press => true,
-            Action::Release => false,
-            _ => return
-        };
+    pub fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) {
+        let pressed = state == ElementState::Pressed;
         match key {
-            Key::W => self.moving_forward = pressed,
-            Key::A => self.moving_left = pressed,
-            Key::S => self.moving_backward = pressed,
-            Key::D => self.moving_right = pressed,
+            VirtualKeyCode::W => self.moving_forward = pressed,
+            VirtualKeyCode::A => self.moving_left = pressed,
+            VirtualKeyCode::S => self.moving_backward = pressed,
+            VirtualKeyCode::D => self.moving_right = pressed,
             _ => ()
         }
     }
@@ -96,17 +95,12 @@ impl Camera {
     }
 
     pub fn scroll_callback(&mut self, y_offset: f32) {
-        self.fov -= y_offset;
         // clamp fov
-        if self.fov < 1.0 {
-            self.fov = 1.0
-        } else if self.fov > 150.0 {
-            self.fov = 150.0
-        }
+        self.fov = (self.fov - y_offset).clamp(1.0, 150.0);
     }
 
     pub fn get_projection(&self) -> Matrix4<f32> {
-        perspective(Deg(self.fov), (self.screen_width as f32) / (self.screen_height as f32), 0.01, 1000.0)
+        perspective(Deg(self.fov), (self.screen_width as f32) / (self.screen_height as f32), 0.01, self.far)
     }    
 
     pub fn get_view(&self) -> Matrix4<f32> {
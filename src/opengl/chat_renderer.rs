@@ -0,0 +1,55 @@
+use cgmath::Vector3;
+
+use crate::core::chat_log::ChatLog;
+
+use super::{tex_quad::TexQuad, text_renderer::{TextJustification, TextRenderer}};
+
+// lines shown with chat closed (while they fade) and with it open
+const CLOSED_LINES: usize = 10;
+const OPEN_LINES: usize = 20;
+
+const LINE_HEIGHT: f32 = 20.0;
+const LEFT_X: f32 = 10.0;
+// bottom of the newest line, just above the chat input
+const BOTTOM_Y: f32 = 60.0;
+const WIDTH: f32 = 620.0;
+
+// chat in the bottom left of the screen, the newest lines fading
+// out while closed and the full history scrollable while open
+pub struct ChatRenderer {
+    background: TexQuad
+}
+
+impl ChatRenderer {
+    pub unsafe fn new(screen_width: u32, screen_height: u32) -> ChatRenderer {
+        let background = TexQuad::new("assets/textures/input.png", gl::TEXTURE0, true, screen_width, screen_height);
+        ChatRenderer { background }
+    }
+
+    pub unsafe fn draw(&self, log: &ChatLog, opened: bool, text_renderer: &TextRenderer) {
+        let max_lines = if opened { OPEN_LINES } else { CLOSED_LINES };
+        let grey = Vector3::new(0.65, 0.65, 0.65);
+        let mut shown = 0;
+        for (i, line) in log.newest().take(max_lines).enumerate() {
+            let opacity = if opened { 1.0 } else { line.opacity() };
+            // older lines have faded for longer
+            if opacity <= 0.0 {
+                break;
+            }
+
+            let y = BOTTOM_Y + i as f32 * LINE_HEIGHT;
+            let timestamp = format!("[{}]", line.timestamp());
+            let text_x = LEFT_X + text_renderer.calc_width(timestamp.as_str(), 0.65) + 6.0;
+            text_renderer.render_text_with_opacity(timestamp.as_str(), LEFT_X, y, 0.65, grey, opacity, TextJustification::Left);
+            text_renderer.render_text_with_opacity(line.text.as_str(), text_x, y, 0.65, line.kind.color(), opacity, TextJustification::Left);
+            shown += 1;
+        }
+
+        // text is drawn before the background so depth
+        // testing keeps it in front
+        if opened && shown > 0 {
+            let top_y = BOTTOM_Y + shown as f32 * LINE_HEIGHT;
+            self.background.draw(LEFT_X - 5.0, BOTTOM_Y - 5.0, LEFT_X + WIDTH, top_y, 0.35);
+        }
+    }
+}
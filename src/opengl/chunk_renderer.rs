@@ -0,0 +1,133 @@
+use std::{cmp::Ordering, ops::Deref};
+
+use cgmath::Vector3;
+use gl::types::*;
+
+use crate::core::{chunk::BLOCK_VERTEX_SIZE, chunk_mesh::ChunkMesh};
+
+use super::{shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// offset from a block point to the center of each face,
+// in the order of the faces bitmask (front first)
+const FACE_OFFSETS: [(f32, f32, f32); 6] = [
+    (0.0, 0.0, -0.5), // front
+    (0.5, 0.0, 0.0),  // right
+    (0.0, 0.0, 0.5),  // back
+    (0.0, -0.5, 0.0), // bottom
+    (-0.5, 0.0, 0.0), // left
+    (0.0, 0.5, 0.0)   // top
+];
+
+// draws chunk meshes in three passes: opaque blocks, cutout blocks with
+// their see-through texels discarded, then translucent blocks blended
+// back to front without writing depth
+pub struct ChunkRenderer {
+    // translucent faces of the chunk the camera is in, one point per face
+    // sorted back to front (other chunks are only sorted as a whole, their
+    // faces overlap far less from outside), along with the generation of
+    // the mesh and the camera block they were sorted for
+    sorted_faces: Vec<f32>,
+    sorted_for: Option<(u64, (i32, i32, i32))>
+}
+
+impl ChunkRenderer {
+    pub fn new() -> ChunkRenderer {
+        ChunkRenderer { sorted_faces: Vec::new(), sorted_for: None }
+    }
+
+    // the voxal shader's uniforms and texture should already be set,
+    // vao and vbo are left bound for the block selector
+    pub unsafe fn draw<M: Deref<Target = ChunkMesh>>(&mut self, shader: &Shader, vao: &VertexArray, vbo: &VertexBuffer, meshes: &[M], camera_position: Vector3<f32>) {
+        vao.bind();
+        vbo.bind();
+
+        // opaque block points
+        for mesh in meshes.iter() {
+            draw_points(vbo, &mesh.opaque);
+        }
+
+        // cutout block points, fully opaque or fully
+        // transparent texels so there's nothing to blend
+        gl::Disable(gl::BLEND);
+        shader.set_bool("cutout_pass", true);
+        for mesh in meshes.iter() {
+            draw_points(vbo, &mesh.cutout);
+        }
+        shader.set_bool("cutout_pass", false);
+        gl::Enable(gl::BLEND);
+
+        // translucent block points, furthest chunk first
+        let mut order: Vec<(usize, f32)> = meshes.iter()
+            .enumerate()
+            .filter(|(_, mesh)| !mesh.translucent.is_empty())
+            .map(|(i, mesh)| (i, mesh.distance_squared(camera_position.x, camera_position.z)))
+            .collect();
+        order.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+
+        // depth is still tested against opaque blocks but not written
+        // so translucent faces behind each other all get blended
+        gl::DepthMask(gl::FALSE);
+        shader.set_bool("water_pass", true);
+        for (i, _) in order.iter() {
+            let mesh = &meshes[*i];
+            if mesh.contains(camera_position.x, camera_position.z) {
+                self.sort_faces(mesh, camera_position);
+                draw_points(vbo, &self.sorted_faces);
+            } else {
+                draw_points(vbo, &mesh.translucent);
+            }
+        }
+        shader.set_bool("water_pass", false);
+        gl::DepthMask(gl::TRUE);
+    }
+
+    // splits the translucent block points of the camera's chunk into one
+    // point per face and sorts them back to front, only redone when the
+    // mesh changes or the camera moves into another block
+    fn sort_faces(&mut self, mesh: &ChunkMesh, camera_position: Vector3<f32>) {
+        let camera_block = (
+            camera_position.x.round() as i32,
+            camera_position.y.round() as i32,
+            camera_position.z.round() as i32
+        );
+        let key = (mesh.generation(), camera_block);
+        if self.sorted_for == Some(key) {
+            return;
+        }
+
+        let mut faces: Vec<(f32, usize, u8)> = Vec::new();
+        for (i, point) in mesh.translucent.chunks(BLOCK_VERTEX_SIZE).enumerate() {
+            let bits = point[9] as u8;
+            for (face, (ox, oy, oz)) in FACE_OFFSETS.iter().enumerate() {
+                let bit = 1 << (7 - face);
+                if bits & bit == 0 {
+                    continue;
+                }
+
+                let dx = point[0] + ox - camera_position.x;
+                let dy = point[1] + oy - camera_position.y;
+                let dz = point[2] + oz - camera_position.z;
+                faces.push((dx * dx + dy * dy + dz * dz, i, bit));
+            }
+        }
+        faces.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+        self.sorted_faces.clear();
+        for (_, i, bit) in faces.iter() {
+            let start = i * BLOCK_VERTEX_SIZE;
+            let faces_index = self.sorted_faces.len() + 9;
+            self.sorted_faces.extend_from_slice(&mesh.translucent[start..start + BLOCK_VERTEX_SIZE]);
+            self.sorted_faces[faces_index] = *bit as f32;
+        }
+        self.sorted_for = Some(key);
+    }
+}
+
+unsafe fn draw_points(vbo: &VertexBuffer, points: &[f32]) {
+    if points.is_empty() {
+        return;
+    }
+
+    vbo.set_data(points, gl::DYNAMIC_DRAW);
+    gl::DrawArrays(gl::POINTS, 0, (points.len() / BLOCK_VERTEX_SIZE) as GLint);
+}
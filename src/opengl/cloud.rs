@@ -1,92 +1,198 @@
-#![allow(dead_code)]
 use cgmath::{Matrix4, Vector3};
-use crate::opengl::{camera::Camera, shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+use noise::OpenSimplex;
 
-pub struct Cloud { 
+use crate::{core::{settings::{CloudMode, Settings}, world_time::DayLighting}, utils::simplex_utils::sample};
+use crate::opengl::{camera::Camera, fog::Fog, shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// width and depth of each cloud cell, and height of fancy clouds
+const CELL_SIZE: f32 = 10.0;
+const CLOUD_THICKNESS: f32 = 4.0;
+
+// brightness of each side so fancy clouds look solid
+const TOP_SHADE: f32 = 1.0;
+const BOTTOM_SHADE: f32 = 0.7;
+const X_SHADE: f32 = 0.9;
+const Z_SHADE: f32 = 0.8;
+
+// position (3) + shade (1)
+const CLOUD_VERTEX_SIZE: usize = 4;
+
+// layer of clouds around the camera built into a single mesh, which
+// drifts along z and is only rebuilt when the camera moves into
+// another cell of the (drifting) cloud grid
+pub struct Cloud {
     vao: VertexArray,
     vbo: VertexBuffer,
-    shader: Shader
+    shader: Shader,
+    simplex: OpenSimplex,
+    mode: CloudMode,
+    height: f32,
+    speed: f32,
+    // how far the clouds have drifted along z
+    offset: f32,
+    // cell and radius in cells the mesh was last built around
+    built_around: Option<(i32, i32, i32)>,
+    vertex_count: usize
 }
 
 impl Cloud {
-    pub unsafe fn new() -> Cloud {
+    pub unsafe fn new(settings: &Settings) -> Cloud {
         let vao = VertexArray::new();
         vao.bind();
 
         let mut vbo = VertexBuffer::new();
-        let vertices = vec!(
-            // front
-            -0.5, -0.5, -0.5,
-             0.5, -0.5, -0.5,
-             0.5,  0.5, -0.5,
-             0.5,  0.5, -0.5,
-            -0.5,  0.5, -0.5,
-            -0.5, -0.5, -0.5,
-
-            // back
-            -0.5, -0.5,  0.5,  
-            0.5, -0.5,  0.5,   
-            0.5,  0.5,  0.5,   
-            0.5,  0.5,  0.5,   
-            -0.5,  0.5,  0.5,  
-            -0.5, -0.5,  0.5,  
-
-            // left
-            -0.5,  0.5,  0.5,  
-            -0.5,  0.5, -0.5,  
-            -0.5, -0.5, -0.5,  
-            -0.5, -0.5, -0.5,  
-            -0.5, -0.5,  0.5,  
-            -0.5,  0.5,  0.5,  
-
-            // right
-            0.5,  0.5,  0.5,  
-            0.5,  0.5, -0.5,  
-            0.5, -0.5, -0.5,  
-            0.5, -0.5, -0.5,  
-            0.5, -0.5,  0.5,  
-            0.5,  0.5,  0.5,  
-
-            // bottom
-            -0.5, -0.5, -0.5,  
-            0.5, -0.5, -0.5,  
-            0.5, -0.5,  0.5,  
-            0.5, -0.5,  0.5,  
-            -0.5, -0.5,  0.5,  
-            -0.5, -0.5, -0.5,  
-
-            // top
-            -0.5,  0.5, -0.5,  
-            0.5,  0.5, -0.5,   
-            0.5,  0.5,  0.5,   
-            0.5,  0.5,  0.5,   
-            -0.5,  0.5,  0.5,  
-            -0.5,  0.5, -0.5,  
-        );
         vbo.bind();
-        vbo.set_data(&vertices, gl::STATIC_DRAW);
-        
+
         // position
-        vbo.add_float_attribute(3, 3);
+        vbo.add_float_attribute(3, CLOUD_VERTEX_SIZE);
+        // shade
+        vbo.add_float_attribute(1, CLOUD_VERTEX_SIZE);
 
         VertexArray::unbind();
         VertexBuffer::unbind();
 
         let shader = Shader::new("assets/shaders/cloud/cloud_vertex.vert", "assets/shaders/cloud/cloud_fragment.frag");
-        Cloud { vao, vbo, shader }
+        Cloud {
+            vao,
+            vbo,
+            shader,
+            simplex: OpenSimplex::new(),
+            mode: settings.clouds,
+            height: settings.cloud_height,
+            speed: settings.cloud_speed,
+            offset: 0.0,
+            built_around: None,
+            vertex_count: 0
+        }
+    }
+
+    // drifts the clouds along by deltatime ms
+    pub fn update(&mut self, deltatime: f32) {
+        self.offset += self.speed * deltatime / 1000.0;
     }
 
-    pub unsafe fn draw(&self, camera: &Camera, x: i32, y: i32, z: i32, z_offset: f32) {
+    // render distance is in chunks, clouds reach twice as far
+    // (in cells) so they carry on past the edge of the terrain
+    pub unsafe fn draw(&mut self, camera: &Camera, fog: &Fog, lighting: &DayLighting, render_distance: u32) {
+        if self.mode == CloudMode::Off {
+            return;
+        }
+
+        let radius = render_distance as i32 * 2;
+        let cell_x = (camera.position.x / CELL_SIZE).floor() as i32;
+        let cell_z = ((camera.position.z - self.offset) / CELL_SIZE).floor() as i32;
+        if self.built_around != Some((cell_x, cell_z, radius)) {
+            self.build_mesh(cell_x, cell_z, radius);
+            self.built_around = Some((cell_x, cell_z, radius));
+        }
+
+        if self.vertex_count == 0 {
+            return;
+        }
+
         self.shader.use_program();
         self.shader.set_mat4("view", camera.get_view());
-        self.shader.set_mat4("model", Matrix4::from_translation(Vector3::new(x as f32 * 10.0, y as f32, z as f32 * 10.0 + z_offset)) * Matrix4::from_nonuniform_scale(10.0, 2.0, 10.0));
+        self.shader.set_mat4("model", Matrix4::from_translation(Vector3::new(0.0, self.height, self.offset)));
         self.shader.set_mat4("projection", camera.get_projection());
+        self.shader.set_vec3("view_pos", camera.position);
+        self.shader.set_float("daylight", lighting.daylight);
+        fog.set_uniforms(&self.shader);
 
         self.vao.bind();
         self.vbo.bind();
-        gl::DrawArrays(gl::TRIANGLES, 0, 36);
-        
+        gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count as i32);
+
         VertexArray::unbind();
         VertexBuffer::unbind();
     }
-}
\ No newline at end of file
+
+    // builds the mesh for every cloud cell within radius of the given cell,
+    // top and bottom faces are merged along x and sides are only added
+    // where a cloud meets open sky
+    unsafe fn build_mesh(&mut self, center_x: i32, center_z: i32, radius: i32) {
+        let width = (radius * 2 + 1) as usize;
+        let mut cells = vec![false; width * width];
+        for x in 0..width {
+            for z in 0..width {
+                let (local_x, local_z) = (x as i32 - radius, z as i32 - radius);
+                if local_x * local_x + local_z * local_z > radius * radius {
+                    continue;
+                }
+
+                let noise = sample((center_x + local_x) as f32 / 4.0, (center_z + local_z) as f32 / 4.0, self.simplex);
+                cells[x * width + z] = noise > 0.5;
+            }
+        }
+
+        let is_cloud = |x: i32, z: i32| -> bool {
+            x >= 0 && z >= 0 && x < width as i32 && z < width as i32 && cells[x as usize * width + z as usize]
+        };
+
+        let thickness = if self.mode == CloudMode::Fancy { CLOUD_THICKNESS } else { 0.0 };
+        let mut vertices = Vec::new();
+        for z in 0..width as i32 {
+            let world_z = (center_z + z - radius) as f32 * CELL_SIZE;
+
+            // runs of cloud along x share one top and bottom quad
+            let mut x = 0;
+            while x < width as i32 {
+                if !is_cloud(x, z) {
+                    x += 1;
+                    continue;
+                }
+
+                let start = x;
+                while is_cloud(x, z) {
+                    x += 1;
+                }
+
+                let x0 = (center_x + start - radius) as f32 * CELL_SIZE;
+                let x1 = (center_x + x - radius) as f32 * CELL_SIZE;
+                let (z0, z1) = (world_z, world_z + CELL_SIZE);
+                push_quad(&mut vertices, [[x0, thickness, z0], [x1, thickness, z0], [x1, thickness, z1], [x0, thickness, z1]], TOP_SHADE);
+                if self.mode == CloudMode::Fancy {
+                    push_quad(&mut vertices, [[x0, 0.0, z0], [x1, 0.0, z0], [x1, 0.0, z1], [x0, 0.0, z1]], BOTTOM_SHADE);
+                }
+            }
+
+            if self.mode != CloudMode::Fancy {
+                continue;
+            }
+
+            for x in 0..width as i32 {
+                if !is_cloud(x, z) {
+                    continue;
+                }
+
+                let x0 = (center_x + x - radius) as f32 * CELL_SIZE;
+                let (x1, z0, z1) = (x0 + CELL_SIZE, world_z, world_z + CELL_SIZE);
+                let h = thickness;
+                if !is_cloud(x - 1, z) {
+                    push_quad(&mut vertices, [[x0, 0.0, z0], [x0, 0.0, z1], [x0, h, z1], [x0, h, z0]], X_SHADE);
+                }
+                if !is_cloud(x + 1, z) {
+                    push_quad(&mut vertices, [[x1, 0.0, z0], [x1, 0.0, z1], [x1, h, z1], [x1, h, z0]], X_SHADE);
+                }
+                if !is_cloud(x, z - 1) {
+                    push_quad(&mut vertices, [[x0, 0.0, z0], [x1, 0.0, z0], [x1, h, z0], [x0, h, z0]], Z_SHADE);
+                }
+                if !is_cloud(x, z + 1) {
+                    push_quad(&mut vertices, [[x0, 0.0, z1], [x1, 0.0, z1], [x1, h, z1], [x0, h, z1]], Z_SHADE);
+                }
+            }
+        }
+
+        self.vbo.bind();
+        self.vbo.set_data(&vertices, gl::STATIC_DRAW);
+        VertexBuffer::unbind();
+        self.vertex_count = vertices.len() / CLOUD_VERTEX_SIZE;
+    }
+}
+
+// adds a quad as two triangles from its corners in order around its edge
+fn push_quad(vertices: &mut Vec<f32>, corners: [[f32; 3]; 4], shade: f32) {
+    for i in [0, 1, 2, 2, 3, 0].iter() {
+        vertices.extend_from_slice(&corners[*i]);
+        vertices.push(shade);
+    }
+}
@@ -3,7 +3,7 @@ use cgmath::Matrix4;
 
 use crate::opengl::camera::Camera;
 
-use super::{face_uvs::FaceUVs, shader::Shader, texture::Texture, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+use super::{face_uvs::FaceUVs, fog::Fog, shader::Shader, texture::Texture, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
 
 pub struct Cube {
     vao: VertexArray,
@@ -44,12 +44,12 @@ impl Cube {
             -0.5, -0.5,  0.5,  face_back.left, face_back.bottom,
 
             // left
-            -0.5,  0.5,  0.5,  face_left.left, face_back.top,
-            -0.5,  0.5, -0.5,  face_left.right, face_back.top,
-            -0.5, -0.5, -0.5,  face_left.right, face_back.bottom,
-            -0.5, -0.5, -0.5,  face_left.right, face_back.bottom,
-            -0.5, -0.5,  0.5,  face_left.left, face_back.bottom,
-            -0.5,  0.5,  0.5,  face_left.left, face_back.top,
+            -0.5,  0.5,  0.5,  face_left.left, face_left.top,
+            -0.5,  0.5, -0.5,  face_left.right, face_left.top,
+            -0.5, -0.5, -0.5,  face_left.right, face_left.bottom,
+            -0.5, -0.5, -0.5,  face_left.right, face_left.bottom,
+            -0.5, -0.5,  0.5,  face_left.left, face_left.bottom,
+            -0.5,  0.5,  0.5,  face_left.left, face_left.top,
 
             // right
             0.5,  0.5,  0.5,  face_right.left, face_right.top,
@@ -91,7 +91,7 @@ impl Cube {
         Cube { vao, vbo, shader, texture }
     }
 
-    pub unsafe fn draw(&self, camera: &Camera, model: Matrix4<f32>) {
+    pub unsafe fn draw(&self, camera: &Camera, fog: &Fog, model: Matrix4<f32>) {
         self.texture.bind();
 
         self.shader.use_program();
@@ -99,6 +99,8 @@ impl Cube {
         self.shader.set_mat4("view", camera.get_view());
         self.shader.set_mat4("projection", camera.get_projection());
         self.shader.set_texture("player_texture", &self.texture);
+        self.shader.set_vec3("view_pos", camera.position);
+        fog.set_uniforms(&self.shader);
 
         self.vao.bind();
         self.vbo.bind();
@@ -1,20 +1,18 @@
-#![allow(dead_code)]
 use std::ptr;
 use gl::types::*;
 
-pub const SHADOW_WIDTH: GLint = 10024;
-pub const SHADOW_HEIGHT: GLint = 10024;
-
-// framebuffer for getting shadow map 
-// for shadow calculations
+// framebuffer for getting shadow map
+// for shadow calculations, one layer
+// of the depth map per shadow cascade
 pub struct DepthFrameBuffer {
     id: GLuint,
-    pub depth_map: GLuint
+    pub depth_map: GLuint,
+    pub resolution: GLint
 }
 
 impl DepthFrameBuffer {
     // creates and binds framebuffer object
-    pub unsafe fn new() -> DepthFrameBuffer {
+    pub unsafe fn new(resolution: GLint, layers: GLint) -> DepthFrameBuffer {
         let mut id = 0;
         gl::GenFramebuffers(1, &mut id);
 
@@ -24,49 +22,56 @@ impl DepthFrameBuffer {
         // depth map attachment
         let mut depth_map = 0;
         gl::GenTextures(1, &mut depth_map);
-        gl::BindTexture(gl::TEXTURE_2D, depth_map);
+        gl::BindTexture(gl::TEXTURE_2D_ARRAY, depth_map);
 
-        gl::TexImage2D(gl::TEXTURE_2D, 0, gl::DEPTH_COMPONENT as GLint, SHADOW_WIDTH, SHADOW_HEIGHT, 0, gl::DEPTH_COMPONENT, gl::FLOAT, ptr::null());
+        gl::TexImage3D(gl::TEXTURE_2D_ARRAY, 0, gl::DEPTH_COMPONENT32F as GLint, resolution, resolution, layers, 0, gl::DEPTH_COMPONENT, gl::FLOAT, ptr::null());
 
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);  
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as GLint);  
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as GLint);  
+        gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as GLint);
 
         let border_color = [ 1.0, 1.0, 1.0, 1.0 ];
-        gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, border_color.as_ptr());
+        gl::TexParameterfv(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_BORDER_COLOR, border_color.as_ptr());
 
-        // attach depth map
-        gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_2D, depth_map, 0);
+        // attach first layer so completeness can be checked
+        gl::FramebufferTextureLayer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, depth_map, 0, 0);
 
         // tell OpenGL we're not going to attach any color buffer
         // as we only need depth
         gl::DrawBuffer(gl::NONE);
-        gl::DrawBuffer(gl::NONE);
+        gl::ReadBuffer(gl::NONE);
 
-        if DepthFrameBuffer::is_complete() {
-            println!("Depth framebuffer is complete!");
+        if !DepthFrameBuffer::is_complete() {
+            println!("Depth framebuffer is not complete");
         }
 
         DepthFrameBuffer::unbind();
 
-        DepthFrameBuffer { id, depth_map }
+        DepthFrameBuffer { id, depth_map, resolution }
     }
 
     pub unsafe fn bind(&self) {
         gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
     }
 
-    pub unsafe fn unbind() {
-        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    // binds the framebuffer to draw into a single layer (cascade)
+    pub unsafe fn bind_layer(&self, layer: GLint) {
+        self.bind();
+        gl::FramebufferTextureLayer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, self.depth_map, 0, layer);
     }
 
-    pub unsafe fn delete(&self) {
-        gl::DeleteFramebuffers(1, &self.id);
+    pub unsafe fn bind_depth_map(&self, texture_id: GLenum) {
+        gl::ActiveTexture(texture_id);
+        gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.depth_map);
+    }
+
+    pub unsafe fn unbind() {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
     }
 
     // framebuffer must be bound before checking completeness
     pub unsafe fn is_complete() -> bool {
         gl::CheckFramebufferStatus(gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE
     }
-}
\ No newline at end of file
+}
@@ -41,10 +41,10 @@ impl ElementBuffer {
         self.attribute_offset += length;
     }
 
-    pub unsafe fn set_data(&self, indices: &Vec<usize>, flag: GLuint) {
+    pub unsafe fn set_data(&self, indices: &[usize], flag: GLuint) {
         gl::BufferData(
             gl::ELEMENT_ARRAY_BUFFER, 
-            (indices.len() * std::mem::size_of::<usize>()) as GLsizeiptr,
+            std::mem::size_of_val(indices) as GLsizeiptr,
             indices.as_ptr() as *const c_void, 
             flag
         );
@@ -0,0 +1,25 @@
+use crate::core::{entity::{Entity, EntityKind}, interpolation::limb_swing};
+
+use super::{block_model::BlockModel, camera::Camera, fog::Fog, player_model::PlayerModel};
+
+// render hook for entities, draws each
+// with the model for its kind
+pub struct EntityRenderer {
+    player_model: PlayerModel,
+    block_model: BlockModel
+}
+
+impl EntityRenderer {
+    pub unsafe fn new() -> EntityRenderer {
+        let player_model = PlayerModel::new("assets/textures/player_skin.png");
+        let block_model = BlockModel::new();
+        EntityRenderer { player_model, block_model }
+    }
+
+    pub unsafe fn draw(&self, camera: &Camera, fog: &Fog, entity: &Entity) {
+        match &entity.kind {
+            EntityKind::Player { .. } => self.player_model.draw(camera, fog, entity.position, entity.pitch, entity.yaw, limb_swing(entity)),
+            EntityKind::FallingBlock { block } => self.block_model.draw(camera, fog, *block, entity.position)
+        }
+    }
+}
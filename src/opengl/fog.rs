@@ -0,0 +1,49 @@
+use cgmath::Vector3;
+
+use super::shader::Shader;
+
+// colour of the water fog while the camera is underwater, also
+// the tint of the underwater post effect so the two match
+pub const UNDERWATER_FOG_COLOR: (f32, f32, f32) = (0.1, 0.3, 0.65);
+
+// distance fog blending the world into the sky towards the edge
+// of the render distance so terrain doesn't end at a hard edge
+pub struct Fog {
+    pub color: Vector3<f32>,
+    // distances in blocks where linear fog starts and is full
+    pub start: f32,
+    pub end: f32,
+    // density of exponential fog on top of the linear fog
+    pub density: f32
+}
+
+impl Fog {
+    // render distance is in chunks
+    pub fn new(render_distance: u32, sky_color: Vector3<f32>, underwater: bool) -> Fog {
+        if underwater {
+            let (r, g, b) = UNDERWATER_FOG_COLOR;
+            return Fog {
+                color: Vector3::new(r, g, b),
+                start: 0.0,
+                end: 32.0,
+                density: 1.0 / 12.0
+            }
+        }
+
+        // fully fogged just before the furthest chunks
+        let end = (render_distance as f32 - 1.0).max(1.0) * 16.0;
+        Fog {
+            color: sky_color,
+            start: end * 0.6,
+            end,
+            density: 0.6 / end
+        }
+    }
+
+    pub unsafe fn set_uniforms(&self, shader: &Shader) {
+        shader.set_vec3("fog_color", self.color);
+        shader.set_float("fog_start", self.start);
+        shader.set_float("fog_end", self.end);
+        shader.set_float("fog_density", self.density);
+    }
+}
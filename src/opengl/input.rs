@@ -1,5 +1,5 @@
 use cgmath::Vector3;
-use glfw::Key;
+use glutin::event::VirtualKeyCode;
 
 use super::{tex_quad::TexQuad, text_renderer::{TextJustification, TextRenderer}};
 
@@ -13,7 +13,10 @@ pub struct Input {
     top_y: f32,
     focused: bool,
     scale: f32,
-    justification: TextJustification
+    justification: TextJustification,
+    // lines Tab cycles through, reset by typing
+    completions: Vec<String>,
+    completion_index: usize
 }
 
 impl Input {
@@ -23,7 +26,7 @@ impl Input {
         let right_x = left_x + width;
         let bottom_y = y - height / 2.0;
         let top_y = bottom_y + height;
-        Input { texquad, text: String::new(), width, left_x, right_x, bottom_y, top_y, focused: false, scale, justification }
+        Input { texquad, text: String::new(), width, left_x, right_x, bottom_y, top_y, focused: false, scale, justification, completions: Vec::new(), completion_index: 0 }
     }
 
     pub fn update_focus(&mut self, mouse_x: f32, mouse_y: f32) {
@@ -34,24 +37,28 @@ impl Input {
         self.focused = focused;
     }
 
-    pub fn type_key(&mut self, key: Key, shift: bool, text_renderer: &TextRenderer) {
+    pub fn type_key(&mut self, key: VirtualKeyCode, shift: bool, text_renderer: &TextRenderer) {
         if !self.focused {
             return;
         }
+        self.completions.clear();
 
-        if key == Key::Backspace {
-            if self.text.len() > 0 {
+        if key == VirtualKeyCode::Back {
+            if !self.text.is_empty() {
                 self.text.remove(self.text.len() - 1);
             }
             return;
         }
 
         // return if key is invalid or if text has reached input bounds
-        if text_renderer.calc_width(self.text.as_str(), self.scale) > self.width - 30.0 || key as usize > Key::Z as usize {
+        let ch_id = match key_ascii(key) {
+            Some(ch_id) => ch_id as usize,
+            None => return
+        };
+        if text_renderer.calc_width(self.text.as_str(), self.scale) > self.width - 30.0 {
             return;
         }
 
-        let ch_id = (key as usize - Key::A as usize) + 65;
         let ch = if shift {
             // map characters to uppercase ASCII
             let ch_id = match ch_id as u8 {
@@ -83,7 +90,7 @@ impl Input {
             } else {
                 ch_id
             };
-            (ch_id as u8 as char).to_uppercase().nth(0).unwrap()
+            (ch_id as char).to_uppercase().nth(0).unwrap()
         } else {
             (ch_id as u8 as char).to_lowercase().nth(0).unwrap()
         };
@@ -91,12 +98,31 @@ impl Input {
         self.text.push_str(ch.to_string().as_str());
     }
 
+    // finishes the text with the first of the completions for it,
+    // pressing Tab again moves on to the next one
+    pub fn complete(&mut self, complete: impl Fn(&str) -> Vec<String>) {
+        if !self.focused {
+            return;
+        }
+
+        if self.completions.is_empty() {
+            self.completions = complete(self.text.as_str());
+            self.completion_index = 0;
+        } else {
+            self.completion_index = (self.completion_index + 1) % self.completions.len();
+        }
+
+        if let Some(completion) = self.completions.get(self.completion_index) {
+            self.text = completion.clone();
+        }
+    }
+
     pub unsafe fn draw(&self, text_renderer: &TextRenderer, ) {
         let text_x = match self.justification {
             TextJustification::Center => self.left_x + ((self.right_x - self.left_x) / 2.0),
             TextJustification::Left => self.left_x + 20.0
         };
-        let text_height = text_renderer.get_char('d' as char).bearing.y as f32;
+        let text_height = text_renderer.get_char('d').bearing.y as f32;
         let text_y = self.bottom_y + ((self.top_y - self.bottom_y) / 2.0) - text_height / 2.0;
 
         // draw text
@@ -108,7 +134,7 @@ impl Input {
                 TextJustification::Left => text_x + width,
                 TextJustification::Center => text_x + width / 2.0,
             };
-            if self.text.len() > 0 {
+            if !self.text.is_empty() {
                 underscore_x += 1.0;
             }
             text_renderer.render_text("_", underscore_x, text_y, self.scale, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
@@ -116,4 +142,35 @@ impl Input {
 
         self.texquad.draw(self.left_x, self.bottom_y, self.right_x, self.top_y, 1.0);
     }
-}
\ No newline at end of file
+}
+// ascii code of what a key types without shift, None for keys that don't type
+fn key_ascii(key: VirtualKeyCode) -> Option<u8> {
+    use VirtualKeyCode::*;
+
+    // letters and digits are in order, with 0 after 9
+    let code = key as u32;
+    if code >= A as u32 && code <= Z as u32 {
+        return Some(b'A' + (code - A as u32) as u8)
+    }
+    if code >= Key1 as u32 && code <= Key9 as u32 {
+        return Some(b'1' + (code - Key1 as u32) as u8)
+    }
+
+    let ascii = match key {
+        Key0 => b'0',
+        Space => b' ',
+        Apostrophe => b'\'',
+        Comma => b',',
+        Minus => b'-',
+        Period => b'.',
+        Slash => b'/',
+        Semicolon => b';',
+        Equals => b'=',
+        LBracket => b'[',
+        Backslash => b'\\',
+        RBracket => b']',
+        Grave => b'`',
+        _ => return None
+    };
+    Some(ascii)
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use crate::{core::{block_type::{BlockType, block_to_uv}, chunk::CHUNK_SIZE, face::Face, fluid::WATER_SURFACE_HEIGHT, settings::Settings, world::World, world_time::DayLighting}, utils::num_utils::distance};
+
+use super::{camera::Camera, fog::Fog, shader::Shader, texture::Texture, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// corner x and z (2), size (1), top (1), bottom of skirt (1), top tile (1), side tile (1)
+const LOD_VERTEX_SIZE: usize = 7;
+
+// chunks to build simplified meshes for per frame, so moving
+// into a new chunk doesn't stall the game for a whole ring
+const CHUNKS_BUILT_PER_FRAME: usize = 96;
+
+// simplified terrain in rings beyond the render distance, each chunk is
+// a grid of columns taken from the top of the terrain, with every ring
+// using columns twice as wide as the last
+pub struct LodTerrain {
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    shader: Shader,
+    // outer edge of each ring in chunks, see Settings::lod_rings
+    rings: Vec<u32>,
+    // columns of each built chunk, along with their width
+    meshes: HashMap<(i32, i32), (usize, Vec<f32>)>,
+    // chunk the rings were last laid out around
+    built_around: Option<(i32, i32)>,
+    // chunks still to build with their column widths, nearest last
+    queue: Vec<(i32, i32, usize)>,
+    // built chunks not yet in the vertex buffer
+    pending_upload: bool,
+    point_count: usize
+}
+
+impl LodTerrain {
+    pub unsafe fn new(settings: &Settings) -> LodTerrain {
+        let vao = VertexArray::new();
+        vao.bind();
+
+        let mut vbo = VertexBuffer::new();
+        vbo.bind();
+
+        // corner
+        vbo.add_float_attribute(2, LOD_VERTEX_SIZE);
+        // size
+        vbo.add_float_attribute(1, LOD_VERTEX_SIZE);
+        // top
+        vbo.add_float_attribute(1, LOD_VERTEX_SIZE);
+        // bottom
+        vbo.add_float_attribute(1, LOD_VERTEX_SIZE);
+        // top tile
+        vbo.add_float_attribute(1, LOD_VERTEX_SIZE);
+        // side tile
+        vbo.add_float_attribute(1, LOD_VERTEX_SIZE);
+
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+
+        let shader = Shader::new_with_geom("assets/shaders/lod/lod_vertex.vert", "assets/shaders/lod/lod_fragment.frag", "assets/shaders/lod/lod_geometry.geom");
+        LodTerrain {
+            vao,
+            vbo,
+            shader,
+            rings: settings.lod_rings.clone(),
+            meshes: HashMap::new(),
+            built_around: None,
+            queue: Vec::new(),
+            pending_upload: false,
+            point_count: 0
+        }
+    }
+
+    // forgets every built chunk, e.g. when opening another world
+    pub fn clear(&mut self) {
+        self.meshes.clear();
+        self.queue.clear();
+        self.built_around = None;
+        self.pending_upload = false;
+        self.point_count = 0;
+    }
+
+    // lays the rings out around the chunk the world was last meshed
+    // around and builds some of the chunks still missing
+    pub unsafe fn update(&mut self, world: &World) {
+        if self.rings.is_empty() {
+            return;
+        }
+
+        // chunks now drawn at full detail (or out of range)
+        // are dropped from the rings straight away
+        let (center_x, center_z) = world.player_chunk();
+        if self.built_around != Some((center_x, center_z)) {
+            self.layout(world, center_x, center_z);
+            self.built_around = Some((center_x, center_z));
+            self.upload();
+        }
+
+        for _ in 0..CHUNKS_BUILT_PER_FRAME {
+            match self.queue.pop() {
+                Some((chunk_x, chunk_z, size)) => {
+                    let columns = build_chunk(world, chunk_x, chunk_z, size);
+                    self.meshes.insert((chunk_x, chunk_z), (size, columns));
+                    self.pending_upload = true;
+                },
+                None => break
+            }
+        }
+
+        // built chunks are uploaded together once the queue is empty
+        // rather than re-uploading every ring each frame it drains
+        if self.pending_upload && self.queue.is_empty() {
+            self.upload();
+        }
+    }
+
+    pub unsafe fn draw(&self, camera: &Camera, fog: &Fog, lighting: &DayLighting, texture_map: &Texture) {
+        if self.point_count == 0 {
+            return;
+        }
+
+        self.shader.use_program();
+        self.shader.set_mat4("view", camera.get_view());
+        self.shader.set_mat4("projection", camera.get_projection());
+        self.shader.set_vec3("view_pos", camera.position);
+        self.shader.set_vec3("light_dir", lighting.light_direction);
+        self.shader.set_vec3("light_color", lighting.light_color);
+        self.shader.set_float("ambient_strength", lighting.ambient_strength);
+        fog.set_uniforms(&self.shader);
+
+        texture_map.bind();
+        self.shader.set_texture("texture_map", texture_map);
+
+        self.vao.bind();
+        self.vbo.bind();
+        gl::DrawArrays(gl::POINTS, 0, self.point_count as i32);
+
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+    }
+
+    // works out which chunks belong in which ring, dropping those
+    // that left the rings or changed ring and queueing those missing
+    fn layout(&mut self, world: &World, center_x: i32, center_z: i32) {
+        let outer = *self.rings.last().unwrap() as i32;
+        let mut wanted = HashMap::new();
+        for x in center_x - outer..=center_x + outer {
+            for z in center_z - outer..=center_z + outer {
+                if world.in_render_distance(center_x, center_z, x, z) {
+                    continue;
+                }
+
+                let chunk_distance = distance(center_x, center_z, x, z);
+                if let Some(ring) = self.rings.iter().position(|ring| chunk_distance <= *ring as f32) {
+                    // 2, 4, 8... blocks wide, at most a whole chunk
+                    let size = (2 << ring).min(CHUNK_SIZE);
+                    wanted.insert((x, z), size);
+                }
+            }
+        }
+
+        self.meshes.retain(|coords, (size, _)| wanted.get(coords) == Some(size));
+
+        let mut queue: Vec<(i32, i32, usize)> = wanted.iter()
+            .filter(|(coords, _)| !self.meshes.contains_key(coords))
+            .map(|((x, z), size)| (*x, *z, *size))
+            .collect();
+        queue.sort_by(|a, b| {
+            let a = distance(center_x, center_z, a.0, a.1);
+            let b = distance(center_x, center_z, b.0, b.1);
+            b.partial_cmp(&a).unwrap()
+        });
+        self.queue = queue;
+    }
+
+    unsafe fn upload(&mut self) {
+        let mut points = Vec::new();
+        for (_, columns) in self.meshes.values() {
+            points.extend_from_slice(columns);
+        }
+
+        self.vbo.bind();
+        self.vbo.set_data(&points, gl::DYNAMIC_DRAW);
+        VertexBuffer::unbind();
+        self.point_count = points.len() / LOD_VERTEX_SIZE;
+        self.pending_upload = false;
+    }
+}
+
+// columns size blocks wide covering a chunk, each as tall as the terrain at
+// its middle, with a skirt reaching below the lowest of its neighbours
+fn build_chunk(world: &World, chunk_x: i32, chunk_z: i32, size: usize) -> Vec<f32> {
+    let cells = (CHUNK_SIZE / size) as i32;
+    let size_i = size as i32;
+    let (world_x, world_z) = (chunk_x * CHUNK_SIZE as i32, chunk_z * CHUNK_SIZE as i32);
+
+    // surfaces of the chunk's columns plus a border of the columns around it
+    let width = (cells + 2) as usize;
+    let mut surfaces = Vec::with_capacity(width * width);
+    for i in -1..=cells {
+        for j in -1..=cells {
+            let x = world_x + i * size_i + size_i / 2;
+            let z = world_z + j * size_i + size_i / 2;
+            surfaces.push(world.surface_at(x, z));
+        }
+    }
+
+    let surface = |i: i32, j: i32| surfaces[(i + 1) as usize * width + (j + 1) as usize];
+    let top_of = |(y, block): (usize, BlockType)| -> f32 {
+        // blocks are centered on their coords
+        if block == BlockType::Water {
+            y as f32 - 0.5 + WATER_SURFACE_HEIGHT
+        } else {
+            y as f32 + 0.5
+        }
+    };
+
+    let mut columns = Vec::with_capacity((cells * cells) as usize * LOD_VERTEX_SIZE);
+    for i in 0..cells {
+        for j in 0..cells {
+            let (y, block) = surface(i, j);
+            if block == BlockType::Air {
+                continue;
+            }
+
+            let top = top_of((y, block));
+            let lowest_neighbour = [surface(i - 1, j), surface(i + 1, j), surface(i, j - 1), surface(i, j + 1)].iter()
+                .map(|neighbour| top_of(*neighbour))
+                .fold(top, f32::min);
+
+            columns.push((world_x + i * size_i) as f32 - 0.5);
+            columns.push((world_z + j * size_i) as f32 - 0.5);
+            columns.push(size as f32);
+            columns.push(top);
+            // a column's worth deeper, to cover neighbours
+            // drawn at another detail (or at full detail)
+            columns.push(lowest_neighbour - size as f32);
+            columns.push(block_to_uv(block, Face::Top));
+            columns.push(block_to_uv(block, Face::Front));
+        }
+    }
+
+    columns
+}
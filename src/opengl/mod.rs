@@ -14,4 +14,16 @@ pub mod depth_framebuffer;
 pub mod face_uvs;
 pub mod player_model;
 pub mod skybox;
-pub mod cloud;
\ No newline at end of file
+pub mod cloud;
+pub mod screenshot;
+pub mod world_list;
+pub mod block_model;
+pub mod entity_renderer;
+pub mod sun_moon;
+pub mod shadow_map;
+pub mod post_processing;
+pub mod fog;
+pub mod chunk_renderer;
+pub mod lod_terrain;
+pub mod server_list;
+pub mod chat_renderer;
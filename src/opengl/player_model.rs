@@ -1,10 +1,17 @@
 use cgmath::{Deg, Matrix4, Vector3};
 
-use super::{camera::Camera, cube::Cube, face_uvs::FaceUVs, texture::Texture};
+use super::{camera::Camera, cube::Cube, face_uvs::FaceUVs, fog::Fog, texture::Texture};
+
+// width and length of the arms and legs
+const ARM_SIZE: (f32, f32) = (0.2, 0.6);
+const LEG_SIZE: (f32, f32) = (0.28, 0.4);
 
 pub struct PlayerModel {
     head: Cube,
-    torso: Cube
+    torso: Cube,
+    // arms and legs, the skin has no room
+    // for their own so they reuse the torso's
+    limb: Cube
 }
 
 impl PlayerModel {
@@ -28,14 +35,26 @@ impl PlayerModel {
             FaceUVs::new(50.0, 20.0, 40.0, 10.0, 60.0, 20.0),
             FaceUVs::new(60.0, 20.0, 50.0, 10.0, 60.0, 20.0),
         );
-        PlayerModel { head, torso }
+        let limb = Cube::new(
+            texture,
+            FaceUVs::new(10.0, 20.0, 0.0, 10.0, 60.0, 20.0),
+            FaceUVs::new(20.0, 20.0, 10.0, 10.0, 60.0, 20.0),
+            FaceUVs::new(30.0, 20.0, 20.0, 10.0, 60.0, 20.0),
+            FaceUVs::new(40.0, 20.0, 30.0, 10.0, 60.0, 20.0),
+            FaceUVs::new(50.0, 20.0, 40.0, 10.0, 60.0, 20.0),
+            FaceUVs::new(60.0, 20.0, 50.0, 10.0, 60.0, 20.0),
+        );
+        PlayerModel { head, torso, limb }
     }
 
-    pub unsafe fn draw(&self, camera: &Camera, position: Vector3<f32>, pitch: f32, yaw: f32) {
+    // limb_swing is how far (in degrees) the left leg and right
+    // arm are swung forward, the others swing the opposite way
+    pub unsafe fn draw(&self, camera: &Camera, fog: &Fog, position: Vector3<f32>, pitch: f32, yaw: f32, limb_swing: f32) {
         let pitch_rotate = Matrix4::from_angle_x(Deg(pitch));
         let yaw_rotate = Matrix4::from_angle_y(Deg(-yaw - 90.0));
         self.head.draw(
             camera, 
+            fog,
             Matrix4::from_translation(Vector3::new(position.x, position.y - 0.35, position.z))
             * yaw_rotate
             * pitch_rotate
@@ -43,9 +62,30 @@ impl PlayerModel {
         );
         self.torso.draw(
             camera, 
+            fog,
             Matrix4::from_translation(Vector3::new(position.x, position.y - 1.1, position.z))
             * yaw_rotate 
             * Matrix4::from_nonuniform_scale(0.9, 0.7, 0.6)
         );
+
+        // shoulders and hips, relative to the eyes
+        let limbs = [
+            (Vector3::new(-0.55, -0.8, 0.0), ARM_SIZE, -limb_swing),
+            (Vector3::new(0.55, -0.8, 0.0), ARM_SIZE, limb_swing),
+            (Vector3::new(-0.2, -1.45, 0.0), LEG_SIZE, limb_swing),
+            (Vector3::new(0.2, -1.45, 0.0), LEG_SIZE, -limb_swing)
+        ];
+        for (pivot, (width, length), swing) in limbs.iter() {
+            self.limb.draw(
+                camera,
+                fog,
+                Matrix4::from_translation(position)
+                * yaw_rotate
+                * Matrix4::from_translation(*pivot)
+                * Matrix4::from_angle_x(Deg(*swing))
+                * Matrix4::from_translation(Vector3::new(0.0, -length / 2.0, 0.0))
+                * Matrix4::from_nonuniform_scale(*width, *length, *width)
+            );
+        }
     }
 }
\ No newline at end of file
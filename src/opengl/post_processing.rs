@@ -0,0 +1,172 @@
+use cgmath::{Vector2, Vector3};
+
+use crate::core::{settings::Settings, window_mode::WindowMode};
+
+use super::{fog::UNDERWATER_FOG_COLOR, framebuffer::FrameBuffer, shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// a full screen pass applied to the rendered scene
+pub enum PostEffect {
+    // wobbles and tints the image while the camera is in water
+    Underwater { tint: Vector3<f32>, time: f32 },
+    Tonemap { exposure: f32, gamma: f32 },
+    Fxaa,
+    // darkens the corners of the screen
+    Vignette { strength: f32 }
+}
+
+// draws the scene into an offscreen framebuffer, then runs it
+// through a chain of effects, ping-ponging between two more
+// framebuffers, with the last effect drawing to the screen
+pub struct PostProcessing {
+    scene: FrameBuffer,
+    ping_pong: [FrameBuffer; 2],
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    copy_shader: Shader,
+    underwater_shader: Shader,
+    tonemap_shader: Shader,
+    fxaa_shader: Shader,
+    vignette_shader: Shader
+}
+
+impl PostProcessing {
+    pub unsafe fn new(width: u32, height: u32) -> PostProcessing {
+        let vao = VertexArray::new();
+        vao.bind();
+
+        let mut vbo = VertexBuffer::new();
+        vbo.bind();
+
+        vbo.add_float_attribute(2, 4);
+        vbo.add_float_attribute(2, 4);
+
+        let vertices: Vec<f32> = vec![
+            // positions // uvs
+            -1.0,  1.0,  0.0, 1.0,
+            -1.0, -1.0,  0.0, 0.0,
+             1.0, -1.0,  1.0, 0.0,
+
+            -1.0,  1.0,  0.0, 1.0,
+             1.0, -1.0,  1.0, 0.0,
+             1.0,  1.0,  1.0, 1.0
+        ];
+
+        vbo.set_data(&vertices, gl::STATIC_DRAW);
+
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+
+        let vertex_path = "assets/shaders/framebuffer/fb_vertex.vert";
+        PostProcessing {
+            scene: FrameBuffer::new(width, height),
+            ping_pong: [FrameBuffer::new(width, height), FrameBuffer::new(width, height)],
+            vao,
+            vbo,
+            copy_shader: Shader::new(vertex_path, "assets/shaders/framebuffer/fb_fragment.frag"),
+            underwater_shader: Shader::new(vertex_path, "assets/shaders/post/underwater.frag"),
+            tonemap_shader: Shader::new(vertex_path, "assets/shaders/post/tonemap.frag"),
+            fxaa_shader: Shader::new(vertex_path, "assets/shaders/post/fxaa.frag"),
+            vignette_shader: Shader::new(vertex_path, "assets/shaders/post/vignette.frag")
+        }
+    }
+
+    // effects for the current window mode, in the order they're applied
+    pub fn effects_for(window_mode: &WindowMode, underwater: bool, settings: &Settings, time: f32) -> Vec<PostEffect> {
+        let mut effects = Vec::new();
+        let in_game = *window_mode == WindowMode::InWorld || *window_mode == WindowMode::InServer;
+        if in_game && underwater {
+            let (r, g, b) = UNDERWATER_FOG_COLOR;
+            effects.push(PostEffect::Underwater { tint: Vector3::new(r, g, b), time });
+        }
+
+        if settings.tonemap {
+            effects.push(PostEffect::Tonemap { exposure: settings.exposure, gamma: settings.gamma });
+        }
+
+        if settings.fxaa {
+            effects.push(PostEffect::Fxaa);
+        }
+
+        if settings.vignette {
+            // heavier in menus to frame the buttons
+            let strength = if in_game { 0.25 } else { 0.45 };
+            effects.push(PostEffect::Vignette { strength });
+        }
+
+        effects
+    }
+
+    // binds the scene framebuffer to draw the frame
+    // into, resizing it first if the window has changed
+    pub unsafe fn begin(&mut self, width: u32, height: u32) {
+        if width != self.scene.width || height != self.scene.height {
+            self.scene.delete();
+            self.ping_pong[0].delete();
+            self.ping_pong[1].delete();
+            self.scene = FrameBuffer::new(width, height);
+            self.ping_pong = [FrameBuffer::new(width, height), FrameBuffer::new(width, height)];
+        }
+
+        self.scene.bind();
+    }
+
+    // runs the scene through each effect and draws the result to the screen
+    pub unsafe fn draw(&self, effects: &[PostEffect]) {
+        gl::Disable(gl::DEPTH_TEST);
+        self.vao.bind();
+        self.vbo.bind();
+
+        let mut source = &self.scene;
+        if effects.is_empty() {
+            FrameBuffer::unbind();
+            self.copy_shader.use_program();
+            self.draw_pass(&self.copy_shader, source);
+        }
+
+        for (i, effect) in effects.iter().enumerate() {
+            let target = &self.ping_pong[i % 2];
+            if i == effects.len() - 1 {
+                FrameBuffer::unbind();
+            } else {
+                target.bind();
+            }
+
+            let shader = self.shader_for(effect);
+            shader.use_program();
+            match effect {
+                PostEffect::Underwater { tint, time } => {
+                    shader.set_vec3("tint", *tint);
+                    shader.set_float("time", *time);
+                },
+                PostEffect::Tonemap { exposure, gamma } => {
+                    shader.set_float("exposure", *exposure);
+                    shader.set_float("gamma", *gamma);
+                },
+                PostEffect::Fxaa => {
+                    let inverse_screen_size = Vector2::new(1.0 / source.width as f32, 1.0 / source.height as f32);
+                    shader.set_vec2("inverse_screen_size", inverse_screen_size);
+                },
+                PostEffect::Vignette { strength } => shader.set_float("strength", *strength)
+            }
+            self.draw_pass(shader, source);
+            source = target;
+        }
+
+        gl::Enable(gl::DEPTH_TEST);
+    }
+
+    fn shader_for(&self, effect: &PostEffect) -> &Shader {
+        match effect {
+            PostEffect::Underwater { .. } => &self.underwater_shader,
+            PostEffect::Tonemap { .. } => &self.tonemap_shader,
+            PostEffect::Fxaa => &self.fxaa_shader,
+            PostEffect::Vignette { .. } => &self.vignette_shader
+        }
+    }
+
+    unsafe fn draw_pass(&self, shader: &Shader, source: &FrameBuffer) {
+        source.bind_color_buffer(gl::TEXTURE0);
+        shader.set_int("screenTexture", 0);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+    }
+}
@@ -0,0 +1,21 @@
+use std::ffi::c_void;
+
+use image::{FilterType, RgbaImage, imageops};
+
+// reads back the bound framebuffer and writes it to disk,
+// downscaled to the given size (used for world list thumbnails)
+pub unsafe fn save_screenshot(path: &str, width: u32, height: u32, scaled_width: u32, scaled_height: u32) {
+    let mut data = vec![0u8; (width * height * 4) as usize];
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+    gl::ReadPixels(0, 0, width as i32, height as i32, gl::RGBA, gl::UNSIGNED_BYTE, data.as_mut_ptr() as *mut c_void);
+
+    let image = RgbaImage::from_raw(width, height, data)
+        .expect("Unable to convert pixel array to RgbaImage");
+
+    // OpenGL rows start from the bottom of the screen
+    let image = imageops::flip_vertical(&image);
+    let image = imageops::resize(&image, scaled_width, scaled_height, FilterType::Triangle);
+    if let Err(e) = image.save(path) {
+        println!("Failed to save screenshot to {}: {}", path, e);
+    }
+}
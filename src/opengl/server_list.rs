@@ -0,0 +1,232 @@
+use std::io;
+
+use cgmath::Vector3;
+
+use crate::multiplayer::{lan_discovery::LanServer, server_info::{SavedServer, ServerStatus, StatusQuery, load_servers, save_servers}};
+
+use super::{tex_quad::TexQuad, text_renderer::{TextJustification, TextRenderer}};
+
+pub struct ServerRow {
+    pub name: String,
+    pub address: String,
+    // found by LAN discovery rather than saved
+    pub lan: bool,
+    query: StatusQuery
+}
+
+impl ServerRow {
+    fn new(name: &str, address: &str, lan: bool) -> ServerRow {
+        ServerRow { name: String::from(name), address: String::from(address), lan, query: StatusQuery::start(address) }
+    }
+}
+
+// scrollable list of saved and LAN servers for the multiplayer menu,
+// saved servers come first so their rows line up with `servers`
+pub struct ServerList {
+    pub servers: Vec<SavedServer>,
+    rows: Vec<ServerRow>,
+    background: TexQuad,
+    pub selected: Option<usize>,
+    scroll: usize,
+    left_x: f32,
+    right_x: f32,
+    top_y: f32,
+    row_height: f32,
+    visible_rows: usize
+}
+
+impl ServerList {
+    pub unsafe fn new(x: f32, top_y: f32, width: f32, row_height: f32, visible_rows: usize, screen_width: u32, screen_height: u32) -> ServerList {
+        let background = TexQuad::new("assets/textures/input.png", gl::TEXTURE0, true, screen_width, screen_height);
+        let left_x = x - width / 2.0;
+        let right_x = left_x + width;
+        ServerList {
+            servers: vec![],
+            rows: vec![],
+            background,
+            selected: None,
+            scroll: 0,
+            left_x,
+            right_x,
+            top_y,
+            row_height,
+            visible_rows
+        }
+    }
+
+    // re-read saved servers from disk and ask every server
+    // listed for its status again
+    pub fn refresh(&mut self) {
+        self.servers = load_servers();
+        let lan_servers: Vec<(String, String)> = self.rows.iter()
+            .filter(|row| row.lan)
+            .map(|row| (row.name.clone(), row.address.clone()))
+            .collect();
+
+        self.rows = self.servers.iter()
+            .map(|server| ServerRow::new(server.name.as_str(), server.address.as_str(), false))
+            .chain(lan_servers.iter().map(|(name, address)| ServerRow::new(name.as_str(), address.as_str(), true)))
+            .collect();
+        self.selected = self.selected.filter(|selected| *selected < self.rows.len());
+        self.clamp_scroll();
+    }
+
+    // replaces the LAN rows with the servers found, keeping
+    // the status of those already listed
+    pub fn set_lan_servers(&mut self, lan_servers: &[LanServer]) {
+        let selected_address = self.selected_row().map(|row| row.address.clone());
+        let mut old_rows: Vec<ServerRow> = self.rows.drain(self.servers.len()..).collect();
+        for server in lan_servers.iter() {
+            let row = match old_rows.iter().position(|row| row.address == server.address) {
+                Some(i) => {
+                    let mut row = old_rows.remove(i);
+                    row.name = server.motd.clone();
+                    row
+                },
+                None => ServerRow::new(server.motd.as_str(), server.address.as_str(), true)
+            };
+            self.rows.push(row);
+        }
+
+        if let Some(address) = selected_address {
+            self.selected = self.rows.iter().position(|row| row.address == address);
+        }
+        self.clamp_scroll();
+    }
+
+    // picks up answers to status queries
+    pub fn poll(&mut self) {
+        for row in self.rows.iter_mut() {
+            row.query.poll();
+        }
+    }
+
+    pub fn selected_row(&self) -> Option<&ServerRow> {
+        match self.selected {
+            Some(selected) => self.rows.get(selected),
+            None => None
+        }
+    }
+
+    // index into `servers` of the selected row, None for LAN servers
+    pub fn selected_saved(&self) -> Option<usize> {
+        self.selected.filter(|selected| *selected < self.servers.len())
+    }
+
+    pub fn add(&mut self, server: SavedServer) -> io::Result<()> {
+        self.servers.push(server);
+        save_servers(&self.servers)?;
+        self.refresh();
+        self.selected = Some(self.servers.len() - 1);
+        self.scroll_to_selected();
+        Ok(())
+    }
+
+    pub fn edit(&mut self, index: usize, server: SavedServer) -> io::Result<()> {
+        self.servers[index] = server;
+        save_servers(&self.servers)?;
+        self.refresh();
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> io::Result<()> {
+        self.servers.remove(index);
+        save_servers(&self.servers)?;
+        self.selected = None;
+        self.refresh();
+        Ok(())
+    }
+
+    pub fn scroll(&mut self, rows: i32) {
+        self.scroll = (self.scroll as i32 + rows).max(0) as usize;
+        self.clamp_scroll();
+    }
+
+    fn scroll_to_selected(&mut self) {
+        if let Some(selected) = self.selected {
+            if selected < self.scroll {
+                self.scroll = selected;
+            } else if selected >= self.scroll + self.visible_rows {
+                self.scroll = selected + 1 - self.visible_rows;
+            }
+        }
+    }
+
+    fn clamp_scroll(&mut self) {
+        let max_scroll = self.rows.len().saturating_sub(self.visible_rows);
+        self.scroll = self.scroll.min(max_scroll);
+    }
+
+    // selects the row under the cursor, returns true if
+    // a row was clicked
+    pub fn click(&mut self, mouse_x: f32, mouse_y: f32) -> bool {
+        if mouse_x < self.left_x || mouse_x > self.right_x || mouse_y > self.top_y {
+            return false
+        }
+
+        let row = ((self.top_y - mouse_y) / self.row_height) as usize;
+        if row >= self.visible_rows || self.scroll + row >= self.rows.len() {
+            return false
+        }
+
+        self.selected = Some(self.scroll + row);
+        true
+    }
+
+    pub unsafe fn draw(&self, text_renderer: &TextRenderer) {
+        if self.rows.is_empty() {
+            let x = self.left_x + (self.right_x - self.left_x) / 2.0;
+            text_renderer.render_text("No servers yet, add one below", x, self.top_y - self.row_height, 0.8, Vector3::new(1.0, 1.0, 1.0), TextJustification::Center);
+            return;
+        }
+
+        let padding = 6.0;
+        let end = (self.scroll + self.visible_rows).min(self.rows.len());
+        for (row_index, i) in (self.scroll..end).enumerate() {
+            let row = &self.rows[i];
+            let top_y = self.top_y - row_index as f32 * self.row_height;
+            let bottom_y = top_y - self.row_height + padding;
+            let text_x = self.left_x + padding * 2.0;
+            let right_text_x = self.right_x - 12.0;
+
+            // text is drawn before quads so depth testing
+            // keeps it in front of the row background
+            let white = Vector3::new(1.0, 1.0, 1.0);
+            let grey = Vector3::new(0.75, 0.75, 0.75);
+            let name = match row.lan {
+                true => format!("[LAN] {}", row.name),
+                false => row.name.clone()
+            };
+            text_renderer.render_text(name.as_str(), text_x, top_y - 24.0, 0.8, white, TextJustification::Left);
+
+            let (description, details, players) = match &row.query.status {
+                ServerStatus::Pinging => (String::from("Pinging..."), String::new(), String::new()),
+                ServerStatus::Online { motd, players, max_players, version, latency } => (
+                    motd.clone(),
+                    format!("{}  {} ms", version, latency),
+                    format!("{}/{}", players, max_players)
+                ),
+                ServerStatus::Reachable => (String::from("Online"), String::new(), String::new()),
+                ServerStatus::Offline(error) => (format!("Can't connect: {}", error), String::new(), String::new())
+            };
+            let description_color = match row.query.status {
+                ServerStatus::Offline(_) => Vector3::new(1.0, 0.4, 0.4),
+                _ => grey
+            };
+            text_renderer.render_text(description.as_str(), text_x, top_y - 44.0, 0.55, description_color, TextJustification::Left);
+            text_renderer.render_text(players.as_str(), right_text_x - text_renderer.calc_width(players.as_str(), 0.7), top_y - 24.0, 0.7, white, TextJustification::Left);
+            text_renderer.render_text(details.as_str(), right_text_x - text_renderer.calc_width(details.as_str(), 0.55), top_y - 44.0, 0.55, grey, TextJustification::Left);
+
+            let opacity = if self.selected == Some(i) { 1.0 } else { 0.45 };
+            self.background.draw(self.left_x, bottom_y, self.right_x, top_y, opacity);
+        }
+
+        // scrollbar
+        if self.rows.len() > self.visible_rows {
+            let list_height = self.row_height * self.visible_rows as f32;
+            let bar_height = list_height * self.visible_rows as f32 / self.rows.len() as f32;
+            let bar_top = self.top_y - list_height * self.scroll as f32 / self.rows.len() as f32;
+            self.background.draw(self.right_x + 6.0, bar_top - bar_height, self.right_x + 12.0, bar_top, 1.0);
+        }
+    }
+}
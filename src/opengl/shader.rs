@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 use std::fs;
 use std::ffi::CString;
-use cgmath::{Matrix, Matrix4, Vector3};
+use cgmath::{Matrix, Matrix3, Matrix4, Vector2, Vector3};
 use gl::types::*;
 use std::ptr;
 use std::str;
@@ -32,7 +32,7 @@ impl Shader {
     pub unsafe fn new_with_geom(vertex_path: &str, fragment_path: &str, geometry_path: &str) -> Shader {
         let (vertex_shader, fragment_shader) = Shader::gen_shader_program_with_vert_and_frag(vertex_path, fragment_path);
         let geometry_source = fs::read_to_string(geometry_path)
-            .expect(format!("Could not read vertex shader at path {}", geometry_path).as_str());
+            .unwrap_or_else(|_| panic!("Could not read vertex shader at path {}", geometry_path));
         let geometry_source_cstring = CString::new(geometry_source.as_bytes())
             .expect("Could not convert vertex shader source to CString");
 
@@ -42,8 +42,7 @@ impl Shader {
         gl::CompileShader(geometry_shader);
 
         let mut success = gl::FALSE as GLint;
-        let mut info_log = Vec::with_capacity(512);
-        info_log.set_len(512 - 1); // subtract 1 to skip trailing null char
+        let mut info_log = vec![0u8; 512 - 1]; // subtract 1 to skip trailing null char
         gl::GetShaderiv(geometry_shader, gl::COMPILE_STATUS, &mut success);
         if success != gl::TRUE as GLint {
             gl::GetShaderInfoLog(geometry_shader, 512, ptr::null_mut(), info_log.as_mut_ptr() as *mut GLchar);
@@ -69,8 +68,7 @@ impl Shader {
         gl::LinkProgram(id);
 
         let mut success = gl::FALSE as GLint;
-        let mut info_log = Vec::with_capacity(512);
-        info_log.set_len(512 - 1); // subtract 1 to skip trailing null char
+        let mut info_log = vec![0u8; 512 - 1]; // subtract 1 to skip trailing null char
         gl::GetProgramiv(id, gl::LINK_STATUS, &mut success);
         if success != gl::TRUE as GLint {
             gl::GetShaderInfoLog(id, 512, ptr::null_mut(), info_log.as_mut_ptr() as *mut GLchar);
@@ -80,9 +78,9 @@ impl Shader {
 
     unsafe fn gen_shader_program_with_vert_and_frag(vertex_path: &str, fragment_path: &str) -> (GLuint, GLuint) {
         let vertex_source = fs::read_to_string(vertex_path)
-            .expect(format!("Could not read vertex shader at path {}", vertex_path).as_str());
+            .unwrap_or_else(|_| panic!("Could not read vertex shader at path {}", vertex_path));
         let fragment_source = fs::read_to_string(fragment_path)
-            .expect(format!("Could not read fragment shader at path {}", fragment_path).as_str());
+            .unwrap_or_else(|_| panic!("Could not read fragment shader at path {}", fragment_path));
         let vertex_source_cstring = CString::new(vertex_source.as_bytes())
             .expect("Could not convert vertex shader source to CString");
         let fragment_source_cstring = CString::new(fragment_source.as_bytes())
@@ -94,8 +92,7 @@ impl Shader {
         gl::CompileShader(vertex_shader);
 
         let mut success = gl::FALSE as GLint;
-        let mut info_log = Vec::with_capacity(512);
-        info_log.set_len(512 - 1); // subtract 1 to skip trailing null char
+        let mut info_log = vec![0u8; 512 - 1]; // subtract 1 to skip trailing null char
         gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut success);
         if success != gl::TRUE as GLint {
             gl::GetShaderInfoLog(vertex_shader, 512, ptr::null_mut(), info_log.as_mut_ptr() as *mut GLchar);
@@ -108,8 +105,7 @@ impl Shader {
         gl::CompileShader(fragment_shader);
 
         let mut success = gl::FALSE as GLint;
-        let mut info_log = Vec::with_capacity(512);
-        info_log.set_len(512 - 1); // subtract 1 to skip trailing null char
+        let mut info_log = vec![0u8; 512 - 1]; // subtract 1 to skip trailing null char
         gl::GetShaderiv(fragment_shader, gl::COMPILE_STATUS, &mut success);
         if success != gl::TRUE as GLint {
             gl::GetShaderInfoLog(fragment_shader, 512, ptr::null_mut(), info_log.as_mut_ptr() as *mut GLchar);
@@ -148,15 +144,23 @@ impl Shader {
         gl::Uniform1f(self.get_uniform_loc(name), value)
     }
 
+    pub unsafe fn set_mat3(&self, name: &str, value: Matrix3<f32>) {
+        gl::UniformMatrix3fv(self.get_uniform_loc(name), 1, gl::FALSE, value.as_ptr());
+    }
+
     pub unsafe fn set_mat4(&self, name: &str, value: Matrix4<f32>) {
         gl::UniformMatrix4fv(self.get_uniform_loc(name), 1, gl::FALSE, value.as_ptr());
     }
 
+    pub unsafe fn set_vec2(&self, name: &str, value: Vector2<f32>) {
+        gl::Uniform2f(self.get_uniform_loc(name), value.x, value.y);
+    }
+
     pub unsafe fn set_vec3(&self, name: &str, value: Vector3<f32>) {
         gl::Uniform3f(self.get_uniform_loc(name), value.x, value.y, value.z);
     }
 }
 
 fn stringify_vec_u8(info_log: Vec<u8>) -> String {
-    info_log.iter().map(|c| *c as u8 as char).collect::<String>()
+    info_log.iter().map(|c| *c as char).collect::<String>()
 }
\ No newline at end of file
@@ -0,0 +1,158 @@
+use std::ops::Deref;
+
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Transform, Vector3, ortho};
+use gl::types::*;
+
+use crate::core::{chunk::BLOCK_VERTEX_SIZE, chunk_mesh::ChunkMesh, settings::Settings};
+
+use super::{camera::Camera, depth_framebuffer::DepthFrameBuffer, shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// must match MAX_CASCADES in voxal/fragment.frag
+pub const MAX_CASCADES: usize = 4;
+
+// extra depth behind each cascade towards the light so
+// terrain outside of the view can still cast shadows into it
+const SHADOW_CASTER_MARGIN: f32 = 128.0;
+
+// cascaded directional shadow map from the sun (or moon), each
+// cascade covers a slice of the camera's view further out than the
+// last at a lower effective resolution
+pub struct ShadowMap {
+    framebuffer: DepthFrameBuffer,
+    shader: Shader,
+    enabled: bool,
+    light_space_matrices: Vec<Matrix4<f32>>,
+    splits: Vec<f32>
+}
+
+impl ShadowMap {
+    pub unsafe fn new(settings: &Settings) -> ShadowMap {
+        let cascades = (settings.shadow_cascades as usize).min(MAX_CASCADES);
+
+        // still create a tiny depth map if shadows are off as
+        // the voxal shader's shadow_map sampler needs a texture
+        let framebuffer = if settings.shadows {
+            DepthFrameBuffer::new(settings.shadow_resolution as GLint, cascades as GLint)
+        } else {
+            DepthFrameBuffer::new(1, 1)
+        };
+
+        // split distances grow quadratically so nearby
+        // shadows get most of the resolution
+        let splits = (1..=cascades)
+            .map(|i| settings.shadow_distance * (i as f32 / cascades as f32).powi(2))
+            .collect();
+
+        let shader = Shader::new_with_geom("assets/shaders/depth_map/depth_vertex.vert", "assets/shaders/depth_map/depth_fragment.frag", "assets/shaders/depth_map/depth_geometry.geom");
+        ShadowMap {
+            framebuffer,
+            shader,
+            enabled: settings.shadows,
+            light_space_matrices: vec![Matrix4::identity(); cascades],
+            splits
+        }
+    }
+
+    // renders the opaque and cutout block points of the given meshes into each cascade
+    pub unsafe fn render<M: Deref<Target = ChunkMesh>>(&mut self, camera: &Camera, light_direction: Vector3<f32>, meshes: &[M], vao: &VertexArray, vbo: &VertexBuffer, screen_width: u32, screen_height: u32) {
+        if !self.enabled {
+            return;
+        }
+
+        // the scene may be drawing into an offscreen framebuffer, rebound after
+        let mut previous_framebuffer = 0;
+        gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+
+        gl::Viewport(0, 0, self.framebuffer.resolution, self.framebuffer.resolution);
+        self.shader.use_program();
+        vao.bind();
+        vbo.bind();
+
+        let mut near = 0.0;
+        for i in 0..self.splits.len() {
+            let far = self.splits[i];
+            self.light_space_matrices[i] = self.cascade_matrix(camera, light_direction, near, far);
+
+            self.framebuffer.bind_layer(i as GLint);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+            self.shader.set_mat4("light_space_matrix", self.light_space_matrices[i]);
+            for mesh in meshes.iter() {
+                if !in_range(mesh, camera.position, far) {
+                    continue;
+                }
+
+                for points in [&mesh.opaque, &mesh.cutout].iter() {
+                    if points.is_empty() {
+                        continue;
+                    }
+
+                    vbo.set_data(points, gl::DYNAMIC_DRAW);
+                    gl::DrawArrays(gl::POINTS, 0, (points.len() / BLOCK_VERTEX_SIZE) as GLint);
+                }
+            }
+            near = far;
+        }
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, previous_framebuffer as GLuint);
+        gl::Viewport(0, 0, screen_width as GLint, screen_height as GLint);
+    }
+
+    // binds the depth map and sets the cascade uniforms on the
+    // voxal shader, shadows are only drawn if cast is true
+    pub unsafe fn set_uniforms(&self, shader: &Shader, cast: bool) {
+        self.framebuffer.bind_depth_map(gl::TEXTURE1);
+        gl::ActiveTexture(gl::TEXTURE0);
+        shader.set_int("shadow_map", 1);
+
+        let cascade_count = if cast && self.enabled { self.splits.len() } else { 0 };
+        shader.set_int("cascade_count", cascade_count as i32);
+        for i in 0..cascade_count {
+            shader.set_mat4(format!("light_space_matrices[{}]", i).as_str(), self.light_space_matrices[i]);
+            shader.set_float(format!("cascade_splits[{}]", i).as_str(), self.splits[i]);
+        }
+    }
+
+    // orthographic light projection fitted around the slice
+    // of the camera's view between near and far
+    fn cascade_matrix(&self, camera: &Camera, light_direction: Vector3<f32>, near: f32, far: f32) -> Matrix4<f32> {
+        let aspect = camera.screen_width as f32 / camera.screen_height as f32;
+        let tan_half_fov = (Deg(camera.fov) / 2.0).0.to_radians().tan();
+        let front = camera.front.normalize();
+        let right = front.cross(camera.up).normalize();
+        let up = right.cross(front);
+
+        // bounding sphere of the slice, which stays the same size
+        // as the camera turns so shadow edges don't shimmer
+        let center = camera.position + front * ((near + far) / 2.0);
+        let far_corner = camera.position + front * far + right * (far * tan_half_fov * aspect) + up * (far * tan_half_fov);
+        let near_corner = camera.position + front * near + right * (near * tan_half_fov * aspect) + up * (near * tan_half_fov);
+        let radius = (far_corner - center).magnitude().max((near_corner - center).magnitude()).ceil();
+
+        let light_up = if light_direction.y.abs() > 0.99 {
+            Vector3::new(1.0, 0.0, 0.0)
+        } else {
+            Vector3::new(0.0, 1.0, 0.0)
+        };
+
+        // snap the center to whole shadow map texels so
+        // edges don't crawl as the camera moves
+        let light_view = Matrix4::look_at_dir(Point3::origin(), light_direction, light_up);
+        let texel_size = radius * 2.0 / self.framebuffer.resolution as f32;
+        let mut light_center = light_view.transform_point(Point3::from_vec(center));
+        light_center.x = (light_center.x / texel_size).floor() * texel_size;
+        light_center.y = (light_center.y / texel_size).floor() * texel_size;
+        let center = light_view.invert().unwrap().transform_point(light_center);
+
+        let eye = center - light_direction * (radius + SHADOW_CASTER_MARGIN);
+        let view = Matrix4::look_at_dir(eye, light_direction, light_up);
+        let projection = ortho(-radius, radius, -radius, radius, 0.0, radius * 2.0 + SHADOW_CASTER_MARGIN);
+        projection * view
+    }
+}
+
+// whether a chunk mesh might be within distance of the camera
+fn in_range(mesh: &ChunkMesh, position: Vector3<f32>, distance: f32) -> bool {
+    // half a chunk diagonal
+    let margin = 12.0;
+    mesh.distance_squared(position.x, position.z).sqrt() < distance + margin
+}
@@ -1,10 +1,23 @@
-#![allow(dead_code)]
-use crate::opengl::{camera::Camera, shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+use std::{ffi::c_void, path::Path};
 
+use cgmath::{Matrix3, Rad};
+use gl::types::*;
+
+use crate::{core::world_time::DayLighting, opengl::{camera::Camera, shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer}};
+
+// optional sky textures, one per cube face in the order
+// OpenGL expects them (+x, -x, +y, -y, +z, -z)
+const CUBEMAP_DIR: &str = "assets/textures/skybox";
+const CUBEMAP_FACES: [&str; 6] = ["right", "left", "top", "bottom", "front", "back"];
+
+// sky drawn behind everything else, a horizon to zenith gradient
+// coloured by the time of day with stars at night, or the
+// cubemap in assets/textures/skybox if there is one
 pub struct SkyBox { 
     vao: VertexArray,
     vbo: VertexBuffer,
-    shader: Shader
+    shader: Shader,
+    cubemap: Option<GLuint>
 }
 
 impl SkyBox {
@@ -72,21 +85,89 @@ impl SkyBox {
         VertexBuffer::unbind();
 
         let shader = Shader::new("assets/shaders/skybox/skybox_vertex.vert", "assets/shaders/skybox/skybox_fragment.frag");
-        SkyBox { vao, vbo, shader }
+        let cubemap = load_cubemap(CUBEMAP_DIR);
+        SkyBox { vao, vbo, shader, cubemap }
     }
 
-    pub unsafe fn draw(&self, camera: &Camera) {
+    pub unsafe fn draw(&self, camera: &Camera, lighting: &DayLighting) {
+        // the vertex shader puts the sky on the far plane, so
+        // draw it where nothing else has been drawn yet
         gl::DepthMask(gl::FALSE);
+        gl::DepthFunc(gl::LEQUAL);
         self.shader.use_program();
         self.shader.set_mat4("view", camera.get_view());
         self.shader.set_mat4("projection", camera.get_projection());
 
+        self.shader.set_vec3("horizon_color", lighting.sky_color);
+        self.shader.set_vec3("zenith_color", lighting.zenith_color);
+        self.shader.set_vec3("sun_direction", lighting.sun_direction);
+        self.shader.set_float("daylight", lighting.daylight);
+
+        // stars turn with the sun and moon
+        let sun_angle = lighting.sun_direction.y.atan2(lighting.sun_direction.x);
+        self.shader.set_mat3("star_rotation", Matrix3::from_angle_z(Rad(-sun_angle)));
+
+        match self.cubemap {
+            Some(cubemap) => {
+                gl::ActiveTexture(gl::TEXTURE0);
+                gl::BindTexture(gl::TEXTURE_CUBE_MAP, cubemap);
+                self.shader.set_int("cubemap", 0);
+                self.shader.set_bool("use_cubemap", true);
+            },
+            None => self.shader.set_bool("use_cubemap", false)
+        }
+
         self.vao.bind();
         self.vbo.bind();
         gl::DrawArrays(gl::TRIANGLES, 0, 36);
         
         VertexArray::unbind();
         VertexBuffer::unbind();
+        gl::DepthFunc(gl::LESS);
         gl::DepthMask(gl::TRUE);
     }
+}
+
+// loads the six faces of a cubemap from a directory,
+// None if any are missing so the gradient is used
+unsafe fn load_cubemap(directory: &str) -> Option<GLuint> {
+    let mut faces = Vec::new();
+    for name in CUBEMAP_FACES.iter() {
+        let path = format!("{}/{}.png", directory, name);
+        if !Path::new(path.as_str()).exists() {
+            return None;
+        }
+
+        match image::open(path.as_str()) {
+            Ok(image) => faces.push(image.to_rgba()),
+            Err(e) => {
+                println!("Failed to load skybox face {}: {}", path, e);
+                return None;
+            }
+        }
+    }
+
+    let mut id = 0;
+    gl::GenTextures(1, &mut id);
+    gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+    for (i, face) in faces.iter().enumerate() {
+        gl::TexImage2D(
+            gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as GLenum,
+            0,
+            gl::RGBA as GLint,
+            face.width() as GLint,
+            face.height() as GLint,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            face.as_ptr() as *const c_void
+        );
+    }
+
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as GLint);
+    Some(id)
 }
\ No newline at end of file
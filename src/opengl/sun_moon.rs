@@ -0,0 +1,65 @@
+use cgmath::Vector3;
+
+use crate::core::world_time::DayLighting;
+
+use super::{camera::Camera, shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// sun and moon billboards, drawn right after the sky
+// so everything else is drawn in front of them
+pub struct SunMoon {
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    shader: Shader
+}
+
+impl SunMoon {
+    pub unsafe fn new() -> SunMoon {
+        let vao = VertexArray::new();
+        vao.bind();
+
+        let mut vbo = VertexBuffer::new();
+        let vertices = vec!(
+            -1.0, -1.0,
+             1.0, -1.0,
+             1.0,  1.0,
+             1.0,  1.0,
+            -1.0,  1.0,
+            -1.0, -1.0
+        );
+        vbo.bind();
+        vbo.set_data(&vertices, gl::STATIC_DRAW);
+
+        // position
+        vbo.add_float_attribute(2, 2);
+
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+
+        let shader = Shader::new("assets/shaders/sun/sun_vertex.vert", "assets/shaders/sun/sun_fragment.frag");
+        SunMoon { vao, vbo, shader }
+    }
+
+    pub unsafe fn draw(&self, camera: &Camera, lighting: &DayLighting) {
+        gl::Disable(gl::DEPTH_TEST);
+        self.shader.use_program();
+        self.shader.set_mat4("view", camera.get_view());
+        self.shader.set_mat4("projection", camera.get_projection());
+
+        self.vao.bind();
+        self.vbo.bind();
+
+        self.shader.set_vec3("direction", lighting.sun_direction);
+        self.shader.set_float("size", 12.0);
+        self.shader.set_vec3("body_color", Vector3::new(1.0, 0.95, 0.7));
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+        self.shader.set_vec3("direction", lighting.moon_direction);
+        self.shader.set_float("size", 8.0);
+        self.shader.set_vec3("body_color", Vector3::new(0.85, 0.88, 0.95));
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
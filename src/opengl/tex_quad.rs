@@ -28,6 +28,12 @@ impl TexQuad {
     }
 
     pub unsafe fn draw(&self, left_x: f32, bottom_y: f32, right_x: f32, top_y: f32, alpha: f32) {
+        self.draw_texture(&self.texture, left_x, bottom_y, right_x, top_y, alpha);
+    }
+
+    // draws another texture with this quad's shader and buffers,
+    // so many images don't each need their own
+    pub unsafe fn draw_texture(&self, texture: &Texture, left_x: f32, bottom_y: f32, right_x: f32, top_y: f32, alpha: f32) {
         self.vao.bind();
         let vertices: Vec<f32> = vec![
             // positions        // uvs
@@ -42,10 +48,10 @@ impl TexQuad {
         self.vbo.bind();
         self.vbo.set_data(&vertices, gl::DYNAMIC_DRAW);
 
-        self.texture.bind();
+        texture.bind();
         self.shader.use_program();
         self.shader.set_mat4("projection", cgmath::ortho(0.0, self.screen_width as f32, 0.0, self.screen_height as f32, -1.0, 100.0));
-        self.shader.set_texture("texture", &self.texture);
+        self.shader.set_texture("texture", texture);
         self.shader.set_float("alpha", alpha);
         gl::DrawArrays(gl::TRIANGLES, 0, 6);
     }
@@ -118,7 +118,7 @@ impl TextRenderer {
         for c in 0..128 {
             // load character
             font.load_char(c, freetype::face::LoadFlag::RENDER)
-                .expect(format!("Freetype: failed to load glyph with code '{}'", c).as_str());
+                .unwrap_or_else(|_| panic!("Freetype: failed to load glyph with code '{}'", c));
 
             let glyph = font.glyph();
             let bitmap = glyph.bitmap();
@@ -138,7 +138,7 @@ impl TextRenderer {
                 0,
                 gl::RED,
                 gl::UNSIGNED_BYTE,
-                if bitmap.buffer().len() == 0 {
+                if bitmap.buffer().is_empty() {
                     ptr::null()
                 } else {
                     bitmap.buffer().as_ptr() as *const c_void
@@ -192,7 +192,7 @@ impl TextRenderer {
     
         if justification == TextJustification::Center {
             let w = self.calc_width(text, scale);
-            x = x - w / 2.0;
+            x -= w / 2.0;
         }
 
         for c in text.bytes() {
@@ -238,17 +238,27 @@ impl TextRenderer {
         gl::BindTexture(gl::TEXTURE_2D, 0);
     }
 
-    pub unsafe fn render_text_with_mat(&self, text: &str, mut x: f32, y: f32, scale: f32, color: Vector3<f32>, model: Matrix4<f32>, justification: TextJustification) {
+    pub unsafe fn render_text_with_mat(&self, text: &str, x: f32, y: f32, scale: f32, color: Vector3<f32>, model: Matrix4<f32>, justification: TextJustification) {
+        self.render_glyphs(text, x, y, scale, color, 1.0, model, justification);
+    }
+
+    // text blended over what's behind it, e.g. chat fading out
+    pub unsafe fn render_text_with_opacity(&self, text: &str, x: f32, y: f32, scale: f32, color: Vector3<f32>, opacity: f32, justification: TextJustification) {
+        self.render_glyphs(text, x, y, scale, color, opacity, SquareMatrix::identity(), justification);
+    }
+
+    unsafe fn render_glyphs(&self, text: &str, mut x: f32, y: f32, scale: f32, color: Vector3<f32>, opacity: f32, model: Matrix4<f32>, justification: TextJustification) {
         self.shader.use_program();
         self.shader.set_mat4("projection", ortho(0.0, self.screen_width as f32, 0.0, self.screen_height as f32, -1.0, 100.0));
         self.shader.set_vec3("textColor", color);
+        self.shader.set_float("textOpacity", opacity);
         self.shader.set_mat4("model", model);
         gl::ActiveTexture(gl::TEXTURE0);
         gl::BindVertexArray(self.vao);
     
         if justification == TextJustification::Center {
             let w = self.calc_width(text, scale);
-            x = x - w / 2.0;
+            x -= w / 2.0;
         }
 
         for c in text.bytes() {
@@ -17,7 +17,7 @@ impl Texture {
         gl::ActiveTexture(texture_id);
         gl::BindTexture(gl::TEXTURE_2D, id);
 
-        let mut img = image::open(&Path::new(image_path))
+        let mut img = image::open(Path::new(image_path))
             .expect("Failed to load texture");
         if flipped {
             img = img.flipv();
@@ -27,7 +27,7 @@ impl Texture {
         let channels = match img.color() {
             ColorType::RGBA(_) => gl::RGBA,
             ColorType::RGB(_) => gl::RGB,
-            _ => panic!(format!("Invalid image color type: {:?}", img.color()))
+            _ => panic!("Invalid image color type: {:?}", img.color())
         };
 
         // set texture wrapping and filtering options
@@ -59,4 +59,9 @@ impl Texture {
         gl::ActiveTexture(self.texture_id);
         gl::BindTexture(gl::TEXTURE_2D, self.id);
     }
+
+    // frees the texture, it can't be bound again after
+    pub unsafe fn delete(&self) {
+        gl::DeleteTextures(1, &self.id);
+    }
 }
\ No newline at end of file
@@ -40,10 +40,10 @@ impl VertexBuffer {
         self.attribute_offset += length;
     }
 
-    pub unsafe fn set_data(&self, vertices: &Vec<f32>, flag: GLuint) {
+    pub unsafe fn set_data(&self, vertices: &[f32], flag: GLuint) {
         gl::BufferData(
             gl::ARRAY_BUFFER, 
-            (vertices.len() * std::mem::size_of::<GLfloat>()) as GLsizeiptr,
+            std::mem::size_of_val(vertices) as GLsizeiptr,
             vertices.as_ptr() as *const c_void, 
             flag
         );
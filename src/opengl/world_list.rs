@@ -0,0 +1,151 @@
+use cgmath::Vector3;
+
+use crate::core::world_info::{WorldInfo, list_worlds};
+
+use super::{tex_quad::TexQuad, texture::Texture, text_renderer::{TextJustification, TextRenderer}};
+
+// scrollable list of local worlds for the "Open World" menu
+pub struct WorldList {
+    pub worlds: Vec<WorldInfo>,
+    // thumbnails are drawn with the background's quad
+    thumbnails: Vec<Option<Texture>>,
+    background: TexQuad,
+    pub selected: Option<usize>,
+    scroll: usize,
+    left_x: f32,
+    right_x: f32,
+    top_y: f32,
+    row_height: f32,
+    visible_rows: usize
+}
+
+impl WorldList {
+    pub unsafe fn new(x: f32, top_y: f32, width: f32, row_height: f32, visible_rows: usize, screen_width: u32, screen_height: u32) -> WorldList {
+        let background = TexQuad::new("assets/textures/input.png", gl::TEXTURE0, true, screen_width, screen_height);
+        let left_x = x - width / 2.0;
+        let right_x = left_x + width;
+        WorldList {
+            worlds: vec![],
+            thumbnails: vec![],
+            background,
+            selected: None,
+            scroll: 0,
+            left_x,
+            right_x,
+            top_y,
+            row_height,
+            visible_rows
+        }
+    }
+
+    // re-read worlds from disk, keeping the current
+    // selection if the world still exists
+    pub unsafe fn refresh(&mut self) {
+        let selected_name = self.selected_world().map(|world| world.name.clone());
+        self.worlds = list_worlds();
+
+        // thumbnails are reloaded since playing a world replaces its own
+        for thumbnail in self.thumbnails.iter().flatten() {
+            thumbnail.delete();
+        }
+        self.thumbnails = self.worlds.iter()
+            .map(|world| world.thumbnail_path.as_ref().map(|path| Texture::new(path.as_str(), gl::TEXTURE0, true)))
+            .collect();
+
+        self.selected = None;
+        if let Some(name) = selected_name {
+            self.select_by_name(name.as_str());
+        }
+        self.clamp_scroll();
+    }
+
+    pub fn select_by_name(&mut self, name: &str) {
+        self.selected = self.worlds.iter().position(|world| world.name == name);
+
+        // scroll selected row into view
+        if let Some(selected) = self.selected {
+            if selected < self.scroll {
+                self.scroll = selected;
+            } else if selected >= self.scroll + self.visible_rows {
+                self.scroll = selected + 1 - self.visible_rows;
+            }
+        }
+    }
+
+    pub fn selected_world(&self) -> Option<&WorldInfo> {
+        match self.selected {
+            Some(selected) => self.worlds.get(selected),
+            None => None
+        }
+    }
+
+    pub fn scroll(&mut self, rows: i32) {
+        self.scroll = (self.scroll as i32 + rows).max(0) as usize;
+        self.clamp_scroll();
+    }
+
+    fn clamp_scroll(&mut self) {
+        let max_scroll = self.worlds.len().saturating_sub(self.visible_rows);
+        self.scroll = self.scroll.min(max_scroll);
+    }
+
+    // selects the row under the cursor, returns true if
+    // a row was clicked
+    pub fn click(&mut self, mouse_x: f32, mouse_y: f32) -> bool {
+        if mouse_x < self.left_x || mouse_x > self.right_x || mouse_y > self.top_y {
+            return false
+        }
+
+        let row = ((self.top_y - mouse_y) / self.row_height) as usize;
+        if row >= self.visible_rows || self.scroll + row >= self.worlds.len() {
+            return false
+        }
+
+        self.selected = Some(self.scroll + row);
+        true
+    }
+
+    pub unsafe fn draw(&self, text_renderer: &TextRenderer) {
+        if self.worlds.is_empty() {
+            let x = self.left_x + (self.right_x - self.left_x) / 2.0;
+            text_renderer.render_text("No worlds yet, create one below", x, self.top_y - self.row_height, 0.8, Vector3::new(1.0, 1.0, 1.0), TextJustification::Center);
+            return;
+        }
+
+        let padding = 6.0;
+        let thumbnail_height = self.row_height - padding * 2.0;
+        let thumbnail_width = thumbnail_height * 16.0 / 9.0;
+        let end = (self.scroll + self.visible_rows).min(self.worlds.len());
+        for (row, i) in (self.scroll..end).enumerate() {
+            let world = &self.worlds[i];
+            let top_y = self.top_y - row as f32 * self.row_height;
+            let bottom_y = top_y - self.row_height + padding;
+            let text_x = self.left_x + padding * 2.0 + thumbnail_width + 10.0;
+
+            // text is drawn before quads so depth testing
+            // keeps it in front of the row background
+            let white = Vector3::new(1.0, 1.0, 1.0);
+            let grey = Vector3::new(0.75, 0.75, 0.75);
+            text_renderer.render_text(world.name.as_str(), text_x, top_y - 24.0, 0.8, white, TextJustification::Left);
+            text_renderer.render_text(world.last_played_text().as_str(), text_x, top_y - 44.0, 0.55, grey, TextJustification::Left);
+            text_renderer.render_text(world.size_text().as_str(), self.right_x - 12.0 - text_renderer.calc_width(world.size_text().as_str(), 0.55), top_y - 44.0, 0.55, grey, TextJustification::Left);
+
+            let thumbnail_left_x = self.left_x + padding * 2.0;
+            match &self.thumbnails[i] {
+                Some(thumbnail) => self.background.draw_texture(thumbnail, thumbnail_left_x, bottom_y + padding, thumbnail_left_x + thumbnail_width, top_y - padding, 1.0),
+                None => text_renderer.render_text("?", thumbnail_left_x + thumbnail_width / 2.0, bottom_y + thumbnail_height / 2.0 - 4.0, 1.0, grey, TextJustification::Center)
+            }
+
+            let opacity = if self.selected == Some(i) { 1.0 } else { 0.45 };
+            self.background.draw(self.left_x, bottom_y, self.right_x, top_y, opacity);
+        }
+
+        // scrollbar
+        if self.worlds.len() > self.visible_rows {
+            let list_height = self.row_height * self.visible_rows as f32;
+            let bar_height = list_height * self.visible_rows as f32 / self.worlds.len() as f32;
+            let bar_top = self.top_y - list_height * self.scroll as f32 / self.worlds.len() as f32;
+            self.background.draw(self.right_x + 6.0, bar_top - bar_height, self.right_x + 12.0, bar_top, 1.0);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use cgmath::Vector3;
+
+use crate::core::{block_type::BlockType, command::PermissionLevel, player::GameMode};
+
+// what commands act on, implemented for local worlds by
+// core::world_commands and by servers for their players
+pub trait CommandContext {
+    // level of whoever is running the command
+    fn permission(&self) -> PermissionLevel;
+    fn position(&self) -> Vector3<f32>;
+    fn teleport(&mut self, position: Vector3<f32>) -> Result<(), String>;
+    // puts the block in the player's hand
+    fn give(&mut self, block: BlockType) -> Result<(), String>;
+    fn time(&self) -> u64;
+    fn set_time(&mut self, ticks: u64) -> Result<(), String>;
+    fn seed(&self) -> Option<u32>;
+    fn set_game_mode(&mut self, game_mode: GameMode) -> Result<(), String>;
+    fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockType) -> Result<(), String>;
+    fn set_spawn_point(&mut self, position: Vector3<f32>) -> Result<(), String>;
+
+    // returns how many blocks were set
+    fn fill(&mut self, from: (i32, i32, i32), to: (i32, i32, i32), block: BlockType) -> Result<usize, String> {
+        let mut count = 0;
+        for x in from.0.min(to.0)..=from.0.max(to.0) {
+            for y in from.1.min(to.1)..=from.1.max(to.1) {
+                for z in from.2.min(to.2)..=from.2.max(to.2) {
+                    self.set_block(x, y, z, block)?;
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+}
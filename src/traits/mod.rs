@@ -1,2 +1,3 @@
 pub mod game_world;
-pub mod game_chunk;
\ No newline at end of file
+pub mod game_chunk;
+pub mod command_context;
@@ -1,10 +1,14 @@
 #![allow(dead_code)]
-use std::collections::HashSet;
+use std::{collections::HashSet, io::{self, ErrorKind}};
 
-use crate::core::{block_map::BlockMap, block_type::index_to_block};
+use crate::core::{block_map::BlockMap, block_type::index_to_block, chunk::{CHUNK_HEIGHT, CHUNK_SIZE}};
 
 type BlocksInMesh = Vec<(usize, usize, usize)>;
 
+// largest a serialized chunk can be, every layer
+// along with the 3 bytes that start it
+pub const MAX_SERIALIZED_CHUNK_SIZE: usize = CHUNK_HEIGHT * (3 + CHUNK_SIZE * CHUNK_SIZE);
+
 pub fn from_serialized(serialized: &String) -> (BlocksInMesh, BlockMap) {
     // format (127 as delimiter between layers)
     // 127 <y_greater_than_1> <y mod 127> 16x16 layer grid ...
@@ -55,7 +59,7 @@ pub fn to_serialized(blocks_in_mesh: &BlocksInMesh, blocks: &BlockMap) -> String
     let mut serialized = String::new();
     for y in layer_ys.iter() {
         // use 255 as delimiter, ignored in RLE compression
-        serialized.push(127 as u8 as char);
+        serialized.push(127_u8 as char);
         let y = *y as u8;
         // need two chars to represent 0-255
         let has_127 = if y > 127 { 1u8 } else { 0u8 };
@@ -69,4 +73,79 @@ pub fn to_serialized(blocks_in_mesh: &BlocksInMesh, blocks: &BlockMap) -> String
         }
     }
     serialized // run_length_encode(&serialized)
-}
\ No newline at end of file
+}
+
+// run length encoding for sending serialized chunks, whose layers are
+// mostly long runs of one block, each run is a count (1-255) then the byte
+pub fn run_length_encode(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let mut run = 1;
+        while i + run < bytes.len() && bytes[i + run] == byte && run < 255 {
+            run += 1;
+        }
+        encoded.push(run as u8);
+        encoded.push(byte);
+        i += run;
+    }
+    encoded
+}
+
+// stops at MAX_SERIALIZED_CHUNK_SIZE, as each pair can expand
+// 255 times a server could otherwise have a few bytes take up
+// far more memory than any chunk needs
+pub fn run_length_decode(encoded: &[u8]) -> io::Result<Vec<u8>> {
+    if !encoded.len().is_multiple_of(2) {
+        return Err(io::Error::new(ErrorKind::InvalidData, "run length encoding has an odd length"))
+    }
+
+    let mut bytes = Vec::new();
+    for pair in encoded.chunks(2) {
+        let length = bytes.len() + pair[0] as usize;
+        if length > MAX_SERIALIZED_CHUNK_SIZE {
+            return Err(io::Error::new(ErrorKind::InvalidData, "run length encoding decodes past the size of a chunk"))
+        }
+        bytes.resize(length, pair[1]);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::{MAX_SERIALIZED_CHUNK_SIZE, run_length_decode, run_length_encode};
+
+    #[test]
+    fn round_trips() {
+        let mut long_run = vec![4; 1000];
+        long_run.extend_from_slice(&[1, 2, 2, 3, 3, 3]);
+        for bytes in [vec![], vec![7], vec![1, 2, 3, 4], vec![0; 255], vec![0; 256], long_run].iter() {
+            assert_eq!(run_length_decode(&run_length_encode(bytes)).unwrap(), *bytes);
+        }
+    }
+
+    #[test]
+    fn splits_long_runs() {
+        assert_eq!(run_length_encode(&[9; 300]), vec![255, 9, 45, 9]);
+    }
+
+    #[test]
+    fn decodes_a_full_chunk() {
+        let bytes = vec![5; MAX_SERIALIZED_CHUNK_SIZE];
+        assert_eq!(run_length_decode(&run_length_encode(&bytes)).unwrap().len(), MAX_SERIALIZED_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn refuses_odd_lengths() {
+        assert_eq!(run_length_decode(&[3, 1, 2]).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn refuses_decoding_past_a_chunk() {
+        let encoded: Vec<u8> = [255, 1].iter().cycle().take((MAX_SERIALIZED_CHUNK_SIZE / 255 + 1) * 2).cloned().collect();
+        assert_eq!(run_length_decode(&encoded).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
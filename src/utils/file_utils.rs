@@ -0,0 +1,52 @@
+use std::{fs, io, path::Path};
+
+// recursively sums the size of every file in a directory
+pub fn dir_size(path: &Path) -> u64 {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0
+    };
+
+    let mut size = 0;
+    for entry in entries.flatten() {
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue
+        };
+
+        if metadata.is_dir() {
+            size += dir_size(&entry.path());
+        } else {
+            size += metadata.len();
+        }
+    }
+    size
+}
+
+// std has no recursive copy so walk the tree manually
+pub fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn format_size(bytes: u64) -> String {
+    let bytes = bytes as f32;
+    if bytes < 1024.0 {
+        format!("{} B", bytes)
+    } else if bytes < 1024.0 * 1024.0 {
+        format!("{:.1} KB", bytes / 1024.0)
+    } else if bytes < 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.1} MB", bytes / 1024.0 / 1024.0)
+    } else {
+        format!("{:.1} GB", bytes / 1024.0 / 1024.0 / 1024.0)
+    }
+}
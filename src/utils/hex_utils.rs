@@ -0,0 +1,23 @@
+// lowercase hex, used for keys and signatures
+// stored in game_data and sent to servers
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes.iter() {
+        hex.push_str(format!("{:02x}", byte).as_str());
+    }
+    hex
+}
+
+// None if the string isn't an even number of hex digits
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.trim();
+    if !hex.len().is_multiple_of(2) {
+        return None
+    }
+
+    let mut bytes = Vec::with_capacity(hex.len() / 2);
+    for i in (0..hex.len()).step_by(2) {
+        bytes.push(u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()?);
+    }
+    Some(bytes)
+}
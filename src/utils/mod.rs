@@ -3,4 +3,6 @@ pub mod chunk_utils;
 pub mod name_utils;
 pub mod vector_utils;
 pub mod simplex_utils;
-pub mod num_utils;
\ No newline at end of file
+pub mod num_utils;
+pub mod file_utils;
+pub mod hex_utils;
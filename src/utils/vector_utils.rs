@@ -5,14 +5,8 @@ pub fn get_direction_from_mouse_move(sensitivity: f32, yaw: f32, pitch: f32, x_o
     let y_offset = y_offset * sensitivity;
 
     let yaw = yaw + x_offset;
-    let mut pitch = pitch + y_offset;
-
     // clamp pitch
-    if pitch > 89.0 {
-        pitch = 89.0;
-    } else if pitch < -89.0 {
-        pitch = -89.0;
-    }
+    let pitch = (pitch + y_offset).clamp(-89.0, 89.0);
 
     let direction = Vector3::new(
         Deg(yaw).cos() * Deg(pitch).cos(),
@@ -9,7 +9,7 @@ pub fn localize_coords_to_chunk(world_x: i32, world_z: i32) -> (i32, i32, usize,
         chunk_z -= 1;
     }
 
-    let local_x = ((chunk_x.abs() * 16 + world_x) % 16).abs() as usize;
-    let local_z = ((chunk_z.abs() * 16 + world_z) % 16).abs() as usize;
+    let local_x = ((chunk_x.abs() * 16 + world_x) % 16).unsigned_abs() as usize;
+    let local_z = ((chunk_z.abs() * 16 + world_z) % 16).unsigned_abs() as usize;
     (chunk_x, chunk_z, local_x, local_z)
 }
\ No newline at end of file